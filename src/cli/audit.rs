@@ -0,0 +1,51 @@
+use anyhow::Result;
+use console::style;
+use rust_i18n::t;
+use std::path::PathBuf;
+
+use crate::services::audit_service::AuditService;
+use crate::services::config_service::ConfigService;
+
+/// Run `audit verify`: check the hash chain of the audit trail, exiting with
+/// status 1 if it is broken
+pub fn run_audit_verify(file: Option<PathBuf>) -> Result<()> {
+    let path = match file {
+        Some(path) => path,
+        None => ConfigService::new()?.audit_file_path(),
+    };
+    let report = AuditService::verify(&path)?;
+    let shown = path.display().to_string();
+
+    if let Some(problem) = &report.problem {
+        println!(
+            "{} {}",
+            style("✗").red().bold(),
+            style(t!(
+                "audit.broken",
+                line = problem.line,
+                reason = problem.fault.to_string()
+            ))
+            .red()
+        );
+        println!(
+            "  {}",
+            style(t!("audit.verified_before", count = report.entries)).dim()
+        );
+        std::process::exit(1);
+    }
+
+    if report.entries == 0 {
+        println!("{}", style(t!("audit.empty", path = shown)).yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{} {}",
+        style("✓").green(),
+        t!("audit.intact", count = report.entries, path = shown)
+    );
+    if let Some(hash) = &report.last_hash {
+        println!("  {}", style(t!("audit.last_hash", hash = hash)).dim());
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 /// SSH Tunnel Manager - Modern SSH tunnel and port forwarding manager
 #[derive(Parser, Debug)]
@@ -20,7 +21,15 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// List all saved connections
-    List,
+    List {
+        /// Only list connections with this tag
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Only list connections in this group (including subgroups)
+        #[arg(long)]
+        group: Option<String>,
+    },
 
     /// Add a new connection
     Add {
@@ -72,7 +81,13 @@ pub enum Commands {
     },
 
     /// List active sessions
-    Sessions,
+    ///
+    /// Shows sessions running in the GUI, interactive mode or `group connect`.
+    Sessions {
+        /// Keep refreshing, with current throughput and a traffic sparkline
+        #[arg(short, long)]
+        watch: bool,
+    },
 
     /// Disconnect a session
     Disconnect {
@@ -80,7 +95,101 @@ pub enum Commands {
         id: String,
     },
 
-    /// Add port forwarding to a connection
+    /// Run a command on a connection, or on every connection of a group
+    ///
+    /// Output is streamed as it arrives and the remote exit status becomes
+    /// the exit code. A group runs in parallel, with each line prefixed by
+    /// the connection name.
+    Exec {
+        /// Connection name
+        #[arg(required_unless_present = "group", conflicts_with = "group")]
+        connection: Option<String>,
+
+        /// Run on every connection in this group instead
+        #[arg(short, long)]
+        group: Option<String>,
+
+        /// Password (for password auth)
+        #[arg(short, long)]
+        password: Option<String>,
+
+        /// Command to run, after `--`
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+
+    /// Open an interactive shell on a connection
+    ///
+    /// The local terminal's type and size are passed on, and kept in sync
+    /// when the window is resized; the shell's exit status becomes the exit
+    /// code.
+    Shell {
+        /// Connection name
+        connection: String,
+
+        /// Password (for password auth)
+        #[arg(short, long)]
+        password: Option<String>,
+    },
+
+    /// Download files from a connection over SFTP
+    ///
+    /// Permission bits are preserved; with `--resume`, files partly
+    /// downloaded before are continued rather than copied again.
+    Get {
+        /// Connection name
+        connection: String,
+
+        /// Remote path, relative to the home directory unless absolute
+        remote: String,
+
+        /// Local path (default: the current directory)
+        local: Option<PathBuf>,
+
+        /// Copy directories recursively
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Continue partly copied files
+        #[arg(long)]
+        resume: bool,
+
+        /// Password (for password auth)
+        #[arg(short, long)]
+        password: Option<String>,
+    },
+
+    /// Upload files to a connection over SFTP
+    ///
+    /// Permission bits are preserved; with `--resume`, files partly
+    /// uploaded before are continued rather than copied again.
+    Put {
+        /// Connection name
+        connection: String,
+
+        /// Local path
+        local: PathBuf,
+
+        /// Remote path (default: the home directory)
+        remote: Option<String>,
+
+        /// Copy directories recursively
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Continue partly copied files
+        #[arg(long)]
+        resume: bool,
+
+        /// Password (for password auth)
+        #[arg(short, long)]
+        password: Option<String>,
+    },
+
+    /// Add or remove port forwarding on a connection
+    ///
+    /// The change is saved to the connection; sessions running in the GUI or
+    /// interactive mode start or stop the forward without reconnecting.
     Forward {
         /// Connection name
         connection: String,
@@ -100,12 +209,28 @@ pub enum Commands {
         /// Remote port (for local/remote forwarding)
         #[arg(long)]
         remote_port: Option<u16>,
+
+        /// Local host to connect to (for remote forwarding)
+        #[arg(long, default_value = "localhost")]
+        local_host: String,
+
+        /// Remove the forward instead of adding it
+        #[arg(long)]
+        remove: bool,
     },
 
     /// List available templates
     Templates,
 
+    /// Manage user templates
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+
     /// Create connection from template
+    ///
+    /// Template parameters not given with --set are prompted for.
     FromTemplate {
         /// Template name
         template: String,
@@ -114,12 +239,338 @@ pub enum Commands {
         #[arg(short, long)]
         name: String,
 
-        /// SSH host
+        /// SSH host (prompted for if omitted)
         #[arg(short = 'H', long)]
-        host: String,
+        host: Option<String>,
 
-        /// SSH username
+        /// SSH username (prompted for if omitted)
         #[arg(short, long)]
-        username: String,
+        username: Option<String>,
+
+        /// Template parameter value as NAME=VALUE (repeatable)
+        #[arg(long = "set", value_name = "NAME=VALUE")]
+        values: Vec<String>,
+
+        /// Use defaults instead of prompting for parameters that have one
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+
+    /// Run a forward-only SSH server for reverse tunnels
+    Serve {
+        /// Address to listen on
+        #[arg(short, long, default_value = "0.0.0.0")]
+        bind: String,
+
+        /// Port to listen on
+        #[arg(short, long, default_value = "2222")]
+        port: u16,
+
+        #[command(subcommand)]
+        action: Option<ServeAction>,
+    },
+
+    /// Show the connection log
+    ///
+    /// Filters combine; times are RFC 3339, a date (2026-01-31) or an age
+    /// such as 30m, 12h or 7d.
+    Logs {
+        /// Connection name or ID
+        #[arg(short, long)]
+        connection: Option<String>,
+
+        /// Session ID
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Lowest level to show: info, warning or error
+        #[arg(short, long)]
+        level: Option<String>,
+
+        /// Event kind to show, e.g. connected or tunnel_failed (repeatable)
+        #[arg(short, long = "event")]
+        events: Vec<String>,
+
+        /// Only entries from this time on
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only entries up to this time
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only entries whose message contains this text
+        #[arg(short, long)]
+        grep: Option<String>,
+
+        /// Show at most this many of the most recent entries
+        #[arg(short = 'n', long, default_value = "100")]
+        limit: usize,
+
+        /// Show the newest entry first
+        #[arg(short, long)]
+        reverse: bool,
+
+        /// Output format: table or json (one object per line)
+        #[arg(long, default_value = "table")]
+        format: String,
+
+        /// Keep printing new entries as they are logged, until Ctrl-C
+        #[arg(short, long)]
+        follow: bool,
+    },
+
+    /// Import connections from other tools
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+
+    /// Export connections for use with other tools
+    Export {
+        #[command(subcommand)]
+        target: ExportTarget,
+    },
+
+    /// Work with connection groups
+    Group {
+        #[command(subcommand)]
+        action: GroupAction,
+    },
+
+    /// Manage variable profiles for `${VAR}` placeholders in connections
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+
+    /// Manage configuration files
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Inspect the audit trail
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+
+    /// Manage SSH keys
+    Keys {
+        #[command(subcommand)]
+        action: KeysAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GroupAction {
+    /// List groups and how many connections they contain
+    List,
+
+    /// Connect every connection in a group and keep the tunnels up until Ctrl-C
+    Connect {
+        /// Group path, e.g. "staging" or "staging/eu"
+        group: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplateAction {
+    /// Save a template from an existing connection
+    ///
+    /// Forward ports and target hosts become parameters, defaulting to the
+    /// connection's values.
+    Create {
+        /// Template name
+        name: String,
+
+        /// Name of the connection to copy
+        #[arg(long)]
+        from_connection: String,
+
+        /// Template description
+        #[arg(short, long)]
+        description: Option<String>,
+    },
+
+    /// Delete a template
+    Delete {
+        /// Template name
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileAction {
+    /// List profiles and report connections with unresolved variables
+    List,
+
+    /// Make a profile the active one
+    Use {
+        /// Profile name
+        name: String,
+    },
+
+    /// Stop using a profile (only the environment fills placeholders)
+    Clear,
+
+    /// Set variables in a profile, creating it if needed
+    Set {
+        /// Profile name
+        profile: String,
+
+        /// Assignments as NAME=VALUE
+        #[arg(required = true)]
+        variables: Vec<String>,
+    },
+
+    /// Remove variables from a profile
+    Unset {
+        /// Profile name
+        profile: String,
+
+        /// Variable names
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ImportSource {
+    /// Import hosts from an OpenSSH client config
+    SshConfig {
+        /// Config file to read (default: ~/.ssh/config)
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// Show what would be imported without saving anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ServeAction {
+    /// Authorize a public key to use the relay server
+    Authorize {
+        /// Key name in ~/.ssh, path to a .pub file, or a public key line
+        key: String,
+
+        /// Allowed forward destination as host:port (repeatable, * matches any)
+        #[arg(long = "permit-open")]
+        permit_open: Vec<String>,
+
+        /// Allowed listen port (loopback only) or host:port for -R
+        /// (repeatable, * matches any)
+        #[arg(long = "permit-listen")]
+        permit_listen: Vec<String>,
+    },
+
+    /// Revoke an authorized key by fingerprint or comment
+    Revoke {
+        /// Key fingerprint (SHA256:...) or comment
+        key: String,
+    },
+
+    /// List authorized keys
+    Keys,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ExportTarget {
+    /// Write connections as OpenSSH config Host blocks
+    SshConfig {
+        /// Connection names to export (default: all)
+        connections: Vec<String>,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite the output file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Upgrade configuration files to the current schema version
+    Migrate {
+        /// Only report pending migrations; exit with status 1 if any
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuditAction {
+    /// Check that no audit entry was changed, removed or inserted; exit with
+    /// status 1 if any was
+    Verify {
+        /// Audit trail to check (default: the app's own)
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeysAction {
+    /// Install a public key in a connection's `~/.ssh/authorized_keys`
+    ///
+    /// Logs in the way the connection is set up, adds the key unless it is
+    /// there already, and then offers to switch the connection to the key
+    /// (saved only after a login with it works).
+    Deploy {
+        /// Key name in ~/.ssh, path of a private or `.pub` key, or a public
+        /// key line
+        key: String,
+
+        /// Connection name
+        connection: String,
+
+        /// Password (for password auth)
+        #[arg(short, long)]
+        password: Option<String>,
+
+        /// Switch the connection to the key without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Generate a key pair in ~/.ssh
+    Generate {
+        /// Key file name
+        name: String,
+
+        /// Key type: ed25519, ecdsa-p256, ecdsa-p384, rsa-2048 or rsa-4096
+        #[arg(short = 't', long = "type", default_value = "ed25519")]
+        key_type: String,
+
+        /// Private key format: openssh or pkcs8
+        #[arg(short, long, default_value = "openssh")]
+        format: String,
+
+        /// bcrypt-pbkdf rounds for an encrypted OpenSSH key
+        #[arg(short = 'a', long, default_value_t = crate::services::key_service::DEFAULT_KDF_ROUNDS)]
+        kdf_rounds: u32,
+
+        /// Key comment (default: the key name)
+        #[arg(short = 'C', long)]
+        comment: Option<String>,
+
+        /// Passphrase to encrypt the key with (prompted for if omitted)
+        #[arg(short = 'N', long)]
+        passphrase: Option<String>,
+    },
+
+    /// Copy a private key (OpenSSH or PKCS#8) and its public key into ~/.ssh
+    Import {
+        /// Key file name in ~/.ssh
+        name: String,
+
+        /// Path of the private key
+        path: PathBuf,
     },
 }
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use console::style;
+use rust_i18n::t;
+
+use crate::services::config_service::ConfigService;
+use crate::services::migration_service::{CURRENT_SCHEMA_VERSION, MigrationService};
+
+/// Run `config migrate`: upgrade config files, or only report with `--check`
+pub fn run_config_migrate(check: bool) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let config_dir = config_service.config_dir();
+
+    let statuses = if check {
+        MigrationService::check(config_dir)?
+    } else {
+        MigrationService::migrate_all(config_dir)?
+    };
+
+    if statuses.is_empty() {
+        println!(
+            "{}",
+            t!("migrate.no_files", path = config_dir.display().to_string())
+        );
+        return Ok(());
+    }
+
+    for status in &statuses {
+        let file = status.kind.file_name();
+        if !status.is_pending() {
+            println!(
+                "{} {}",
+                style("✓").green(),
+                t!("migrate.up_to_date", file = file, version = status.version)
+            );
+        } else if check {
+            println!(
+                "{} {}",
+                style("!").yellow().bold(),
+                t!(
+                    "migrate.pending",
+                    file = file,
+                    from = status.version,
+                    to = CURRENT_SCHEMA_VERSION
+                )
+            );
+        } else {
+            println!(
+                "{} {}",
+                style("✓").green(),
+                t!(
+                    "migrate.migrated",
+                    file = file,
+                    from = status.version,
+                    to = CURRENT_SCHEMA_VERSION
+                )
+            );
+        }
+    }
+
+    let pending = statuses.iter().filter(|s| s.is_pending()).count();
+    if check && pending > 0 {
+        println!(
+            "\n{}",
+            style(t!("migrate.run_migrate", count = pending)).yellow()
+        );
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use anyhow::{Result, bail};
+use console::{Style, style};
+use futures::future::join_all;
+use rust_i18n::t;
+use std::io::Write;
+
+use crate::cli::group::prompt_secrets;
+use crate::models::SshConnection;
+use crate::services::audit_service::AuditService;
+use crate::services::config_service::ConfigService;
+use crate::services::exec_service::{ExecSession, LineSplitter, exit_code};
+use crate::services::group_service::GroupService;
+use crate::services::log_service::LogService;
+use crate::services::session_manager::SessionManager;
+use crate::services::ssh_service::OutputStream;
+use std::sync::Arc;
+
+/// What `exec` runs a command on
+pub enum ExecTarget {
+    Connection(String),
+    Group(String),
+}
+
+/// Run `exec`: run a command on one connection or a whole group, exiting
+/// with the remote exit status
+///
+/// For a group the commands run in parallel and the highest exit status
+/// wins, so the exit code is 0 only if every connection succeeded.
+pub async fn run_exec(
+    target: ExecTarget,
+    password: Option<String>,
+    command: Vec<String>,
+) -> Result<()> {
+    let command = command.join(" ");
+    let config_service = ConfigService::new()?;
+    let connections = config_service.load_connections()?;
+    let settings = config_service.load_settings()?;
+
+    let members = match &target {
+        ExecTarget::Connection(name) => {
+            let Some(connection) = connections.iter().find(|c| &c.name == name) else {
+                bail!(t!("export.not_found", name = name));
+            };
+            vec![connection.clone()]
+        }
+        ExecTarget::Group(group) => {
+            let members = GroupService::members(&connections, group);
+            if members.is_empty() {
+                bail!(t!("group.empty", group = group));
+            }
+            members
+        }
+    };
+    let targets = match password {
+        Some(password) => members
+            .into_iter()
+            .map(|connection| (connection, Some(password.clone())))
+            .collect(),
+        None => prompt_secrets(members)?,
+    };
+
+    let session_manager =
+        Arc::new(SessionManager::new(300).with_profile(settings.active_profile().cloned()));
+    let log_service = Arc::new(LogService::for_app(&config_service, &settings));
+    let recorder = log_service.record(session_manager.events());
+    let auditor = AuditService::for_app(&config_service, &settings)
+        .map(|audit| audit.record(session_manager.events()));
+
+    let code = match target {
+        ExecTarget::Connection(_) => {
+            let (connection, password) = &targets[0];
+            exec_streaming(&session_manager, connection, password.as_deref(), &command).await
+        }
+        ExecTarget::Group(_) => Ok(exec_parallel(&session_manager, &targets, &command).await),
+    };
+
+    recorder.finish().await;
+    if let Some(auditor) = auditor {
+        auditor.finish().await;
+    }
+    let code = code.unwrap_or_else(|e| {
+        eprintln!("{} {}", style("✗").red(), e);
+        exit_code(None)
+    });
+    if code != 0 {
+        std::process::exit(code);
+    }
+    Ok(())
+}
+
+/// Run a command on one connection, passing its output straight through,
+/// and return the exit code
+pub(crate) async fn exec_streaming(
+    session_manager: &SessionManager,
+    connection: &SshConnection,
+    password: Option<&str>,
+    command: &str,
+) -> Result<i32> {
+    let session = ExecSession::open(session_manager, connection, password).await?;
+    let status = session
+        .run(command, |stream, data| {
+            let _ = match stream {
+                OutputStream::Stdout => write_flushed(&mut std::io::stdout(), data),
+                OutputStream::Stderr => write_flushed(&mut std::io::stderr(), data),
+            };
+        })
+        .await;
+    let _ = session.close().await;
+    Ok(exit_code(status?))
+}
+
+fn write_flushed(out: &mut impl Write, data: &[u8]) -> std::io::Result<()> {
+    out.write_all(data)?;
+    out.flush()
+}
+
+/// Run a command on several connections at once, prefixing every line with
+/// the connection name; returns the highest exit code
+async fn exec_parallel(
+    session_manager: &SessionManager,
+    targets: &[(SshConnection, Option<String>)],
+    command: &str,
+) -> i32 {
+    let palette = [
+        Style::new().cyan(),
+        Style::new().magenta(),
+        Style::new().yellow(),
+        Style::new().blue(),
+        Style::new().green(),
+    ];
+    let width = targets
+        .iter()
+        .map(|(c, _)| c.name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let codes = join_all(
+        targets
+            .iter()
+            .enumerate()
+            .map(|(index, (connection, password))| {
+                let prefix = palette[index % palette.len()]
+                    .apply_to(format!("{:width$} |", connection.name))
+                    .to_string();
+                async move {
+                    let print = |stream, line: &str| match stream {
+                        OutputStream::Stdout => println!("{} {}", prefix, line),
+                        OutputStream::Stderr => eprintln!("{} {}", prefix, line),
+                    };
+
+                    let session =
+                        match ExecSession::open(session_manager, connection, password.as_deref())
+                            .await
+                        {
+                            Ok(session) => session,
+                            Err(e) => {
+                                eprintln!("{} {} {}", prefix, style("✗").red(), e.user_message());
+                                return exit_code(None);
+                            }
+                        };
+
+                    let mut lines = LineSplitter::default();
+                    let status = session
+                        .run(command, |stream, data| {
+                            for line in lines.push(stream, data) {
+                                print(stream, &line);
+                            }
+                        })
+                        .await;
+                    for stream in [OutputStream::Stdout, OutputStream::Stderr] {
+                        if let Some(line) = lines.finish(stream) {
+                            print(stream, &line);
+                        }
+                    }
+                    let _ = session.close().await;
+
+                    match status {
+                        Ok(status) => exit_code(status),
+                        Err(e) => {
+                            eprintln!("{} {} {}", prefix, style("✗").red(), e.user_message());
+                            exit_code(None)
+                        }
+                    }
+                }
+            }),
+    )
+    .await;
+
+    let succeeded = codes.iter().filter(|&&code| code == 0).count();
+    let summary = t!("exec.summary", ok = succeeded, total = codes.len());
+    if succeeded == codes.len() {
+        eprintln!("{}", style(summary).green());
+    } else {
+        eprintln!("{}", style(summary).yellow());
+    }
+    codes.into_iter().max().unwrap_or(0)
+}
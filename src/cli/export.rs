@@ -0,0 +1,55 @@
+use anyhow::{Result, bail};
+use console::style;
+use rust_i18n::t;
+use std::path::PathBuf;
+
+use crate::services::config_service::ConfigService;
+use crate::utils::ssh_config_parser::SshConfigParser;
+
+/// Run `export ssh-config`: render the selected connections as Host blocks
+pub fn run_export_ssh_config(
+    names: Vec<String>,
+    output: Option<PathBuf>,
+    force: bool,
+) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let mut connections = config_service.load_connections()?;
+
+    if !names.is_empty() {
+        if let Some(missing) = names
+            .iter()
+            .find(|name| !connections.iter().any(|c| &c.name == *name))
+        {
+            bail!(t!("export.not_found", name = missing));
+        }
+        connections.retain(|c| names.contains(&c.name));
+    }
+
+    if connections.is_empty() {
+        println!("{}", style(t!("connection.no_connections")).yellow());
+        return Ok(());
+    }
+
+    let rendered = SshConfigParser::to_config(&connections);
+
+    match output {
+        Some(path) => {
+            if path.exists() && !force {
+                bail!(t!("export.file_exists", path = path.display().to_string()));
+            }
+            std::fs::write(&path, rendered)?;
+            println!(
+                "{}",
+                style(t!(
+                    "export.written",
+                    count = connections.len(),
+                    path = path.display().to_string()
+                ))
+                .green()
+            );
+        }
+        None => print!("{}", rendered),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use anyhow::{Result, anyhow, bail};
+use console::style;
+use rust_i18n::t;
+
+use crate::models::ForwardingConfig;
+use crate::services::config_service::ConfigService;
+
+/// Forward described by the `forward` command line arguments
+pub struct ForwardSpec {
+    /// local, remote or dynamic
+    pub kind: String,
+    pub local_port: u16,
+    pub remote_host: Option<String>,
+    pub remote_port: Option<u16>,
+    pub local_host: String,
+}
+
+impl ForwardSpec {
+    fn to_config(&self) -> Result<ForwardingConfig> {
+        let required_port = |port: Option<u16>, arg: &str| {
+            port.ok_or_else(|| anyhow!(t!("forward.missing_arg", arg = arg, kind = self.kind)))
+        };
+
+        match self.kind.as_str() {
+            "local" | "L" => {
+                let remote_host = self.remote_host.clone().ok_or_else(|| {
+                    anyhow!(t!(
+                        "forward.missing_arg",
+                        arg = "remote-host",
+                        kind = self.kind
+                    ))
+                })?;
+                Ok(ForwardingConfig::local(
+                    self.local_port,
+                    remote_host,
+                    required_port(self.remote_port, "remote-port")?,
+                ))
+            }
+            "remote" | "R" => Ok(ForwardingConfig::remote(
+                required_port(self.remote_port, "remote-port")?,
+                self.local_host.clone(),
+                self.local_port,
+            )),
+            "dynamic" | "D" => Ok(ForwardingConfig::dynamic(self.local_port)),
+            other => bail!(t!("forward.unknown_type", kind = other)),
+        }
+    }
+}
+
+/// Run `forward`: add or remove a forward on a saved connection
+///
+/// Live sessions pick the change up through their config watcher and start
+/// or stop the tunnel without dropping the SSH session.
+pub fn run_forward(name: &str, spec: ForwardSpec, remove: bool) -> Result<()> {
+    let forward = spec.to_config()?;
+    let description = forward.description();
+    let config_service = ConfigService::new()?;
+
+    let mut found = false;
+    let changed = config_service.update_connections(|connections| {
+        let Some(connection) = connections.iter_mut().find(|c| c.name == name) else {
+            return false;
+        };
+        found = true;
+
+        let existing = connection
+            .forwarding_configs
+            .iter()
+            .position(|f| *f == forward);
+        match (existing, remove) {
+            (None, false) => connection.forwarding_configs.push(forward.clone()),
+            (Some(pos), true) => {
+                connection.forwarding_configs.remove(pos);
+            }
+            _ => return false,
+        }
+        connection.touch();
+        true
+    })?;
+
+    if !found {
+        bail!(t!("export.not_found", name = name));
+    }
+    if !changed {
+        if remove {
+            bail!(t!(
+                "forward.not_configured",
+                name = name,
+                forward = description
+            ));
+        }
+        bail!(t!(
+            "forward.already_exists",
+            name = name,
+            forward = description
+        ));
+    }
+
+    let message = if remove {
+        t!("forward.removed", name = name, forward = description)
+    } else {
+        t!("forward.added", name = name, forward = description)
+    };
+    println!("{}", style(message).green());
+    println!("{}", style(t!("forward.live_hint")).dim());
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use anyhow::{Result, bail};
+use console::style;
+use dialoguer::{Password, theme::ColorfulTheme};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use rust_i18n::t;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::{AuthMethod, SshConnection};
+use crate::services::audit_service::AuditService;
+use crate::services::config_service::ConfigService;
+use crate::services::group_service::GroupService;
+use crate::services::log_service::LogService;
+use crate::services::metrics_service::MetricsService;
+use crate::services::notification_service::NotificationService;
+use crate::services::session_manager::{SessionManager, TRAFFIC_SAMPLE_INTERVAL};
+use crate::services::session_snapshot::SnapshotPublisher;
+
+/// Run `group list`: print every group with its number of connections
+pub fn run_group_list() -> Result<()> {
+    let connections = ConfigService::new()?.load_connections()?;
+    let groups = GroupService::groups(&connections);
+
+    if groups.is_empty() {
+        println!("{}", style(t!("group.no_groups")).yellow());
+        return Ok(());
+    }
+
+    for group in groups {
+        let depth = group.matches('/').count();
+        let name = group.rsplit('/').next().unwrap_or(&group);
+        println!(
+            "{}{} ({})",
+            "  ".repeat(depth),
+            style(name).bold(),
+            GroupService::members(&connections, &group).len()
+        );
+    }
+    Ok(())
+}
+
+/// Run `group connect`: connect a whole group and keep it up until Ctrl-C
+///
+/// Tunnels only live as long as this process, so the command stays in the
+/// foreground and disconnects everything when interrupted.
+pub async fn run_group_connect(group: &str) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let connections = config_service.load_connections()?;
+    let members = GroupService::members(&connections, group);
+    if members.is_empty() {
+        bail!(t!("group.empty", group = group));
+    }
+
+    let settings = config_service.load_settings()?;
+    let targets = prompt_secrets(members.clone())?;
+    let session_manager =
+        Arc::new(SessionManager::new(300).with_profile(settings.active_profile().cloned()));
+    let log_service = Arc::new(LogService::for_app(&config_service, &settings));
+    let recorder = log_service.record(session_manager.events());
+    let auditor = AuditService::for_app(&config_service, &settings)
+        .map(|audit| audit.record(session_manager.events()));
+    let _metrics = MetricsService::for_app(session_manager.clone(), &settings).await;
+    let _notifier =
+        NotificationService::for_app(&settings).map(|n| n.listen(session_manager.events()));
+    let _snapshots =
+        SnapshotPublisher::start(session_manager.clone(), &config_service.sessions_dir());
+    let connected = connect_with_progress(&session_manager, group, &targets).await;
+    if connected == 0 {
+        recorder.finish().await;
+        if let Some(auditor) = auditor {
+            auditor.finish().await;
+        }
+        return Ok(());
+    }
+
+    // Without the idle monitor, traffic for `sessions` is sampled here
+    let sampler = {
+        let session_manager = session_manager.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TRAFFIC_SAMPLE_INTERVAL);
+            loop {
+                interval.tick().await;
+                session_manager.sample_traffic().await;
+            }
+        })
+    };
+
+    println!("\n{}", style(t!("group.waiting")).cyan());
+    tokio::signal::ctrl_c().await?;
+    sampler.abort();
+
+    let closed = GroupService::disconnect_all(&session_manager, &members).await;
+    recorder.finish().await;
+    if let Some(auditor) = auditor {
+        auditor.finish().await;
+    }
+    println!(
+        "\n{}",
+        style(t!("group.disconnected", count = closed, group = group)).green()
+    );
+    Ok(())
+}
+
+/// Ask for the password or key passphrase of every connection that needs one
+pub(crate) fn prompt_secrets(
+    connections: Vec<SshConnection>,
+) -> Result<Vec<(SshConnection, Option<String>)>> {
+    connections
+        .into_iter()
+        .map(|connection| {
+            let secret = match &connection.auth_method {
+                AuthMethod::Password => Some(
+                    Password::with_theme(&ColorfulTheme::default())
+                        .with_prompt(t!("group.password_for", name = connection.name))
+                        .interact()?,
+                ),
+                AuthMethod::PublicKey {
+                    passphrase_required: true,
+                    ..
+                } => {
+                    let pwd = Password::with_theme(&ColorfulTheme::default())
+                        .with_prompt(t!("group.passphrase_for", name = connection.name))
+                        .allow_empty_password(true)
+                        .interact()?;
+                    if pwd.is_empty() { None } else { Some(pwd) }
+                }
+                AuthMethod::PublicKey { .. } => None,
+            };
+            Ok((connection, secret))
+        })
+        .collect()
+}
+
+/// Connect all targets concurrently with one spinner per connection,
+/// returning how many connected
+pub(crate) async fn connect_with_progress(
+    session_manager: &SessionManager,
+    group: &str,
+    targets: &[(SshConnection, Option<String>)],
+) -> usize {
+    println!(
+        "\n{}",
+        style(t!("group.connecting", count = targets.len(), group = group))
+            .cyan()
+            .bold()
+    );
+
+    let multi = MultiProgress::new();
+    let spinner_style = ProgressStyle::default_spinner()
+        .template("{spinner:.cyan} {prefix:.bold} {msg}")
+        .unwrap();
+    let bars: Vec<ProgressBar> = targets
+        .iter()
+        .map(|(connection, _)| {
+            let pb = multi.add(ProgressBar::new_spinner());
+            pb.set_style(spinner_style.clone());
+            pb.set_prefix(connection.name.clone());
+            pb.set_message(connection.display_name());
+            pb.enable_steady_tick(Duration::from_millis(100));
+            pb
+        })
+        .collect();
+
+    let results = GroupService::connect_all(session_manager, targets, |index, result| {
+        let connection = &targets[index].0;
+        match result {
+            Ok(_) => bars[index].finish_with_message(format!(
+                "{} {}",
+                style("✓").green(),
+                connection.display_name()
+            )),
+            Err(e) => bars[index].finish_with_message(format!(
+                "{} {}",
+                style("✗").red(),
+                e.user_message()
+            )),
+        }
+    })
+    .await;
+
+    let connected = results.iter().filter(|r| r.is_ok()).count();
+    let summary = t!(
+        "group.connected",
+        ok = connected,
+        total = targets.len(),
+        group = group
+    );
+    if connected == targets.len() {
+        println!("{}", style(summary).green());
+    } else {
+        println!("{}", style(summary).yellow());
+    }
+    connected
+}
@@ -0,0 +1,94 @@
+use anyhow::{Result, anyhow};
+use console::style;
+use rust_i18n::t;
+use std::path::PathBuf;
+
+use crate::services::config_service::ConfigService;
+use crate::services::import_service::{ImportPlan, ImportService};
+use crate::utils::ssh_config_parser::SshConfigParser;
+
+/// Run `import ssh-config`: show the plan and save new connections unless dry-run
+pub fn run_import_ssh_config(file: Option<PathBuf>, dry_run: bool) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let path = match file {
+        Some(path) => path,
+        None => SshConfigParser::default_path().ok_or_else(|| anyhow!(t!("import.no_home")))?,
+    };
+
+    println!(
+        "{}",
+        t!("import.reading", path = path.display().to_string())
+    );
+    let plan = ImportService::plan_ssh_config(&config_service, &path)?;
+    print_plan(&plan);
+
+    if dry_run {
+        println!("{}", style(t!("import.dry_run")).dim());
+        return Ok(());
+    }
+
+    let added = ImportService::apply(&config_service, &plan)?;
+    println!("{}", style(t!("import.imported", count = added)).green());
+    Ok(())
+}
+
+/// Print the diff between imported and saved connections
+pub fn print_plan(plan: &ImportPlan) {
+    for connection in &plan.to_add {
+        let mut details = Vec::new();
+        if !connection.forwarding_configs.is_empty() {
+            details.push(t!(
+                "import.forwards",
+                count = connection.forwarding_configs.len()
+            ));
+        }
+        if !connection.jump_hosts.is_empty() {
+            let via = connection
+                .jump_hosts
+                .iter()
+                .map(|j| j.host.as_str())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            details.push(t!("import.via", hosts = via));
+        }
+
+        println!(
+            "{} {} {}{}",
+            style("+").green().bold(),
+            style(&connection.name).green().bold(),
+            connection.display_name(),
+            if details.is_empty() {
+                String::new()
+            } else {
+                style(format!(" ({})", details.join(", ")))
+                    .dim()
+                    .to_string()
+            }
+        );
+    }
+
+    for duplicate in &plan.duplicates {
+        println!(
+            "{} {} {}",
+            style("=").dim(),
+            style(&duplicate.imported.name).bold(),
+            style(t!("import.matches", name = duplicate.existing_name)).dim()
+        );
+        for difference in &duplicate.differences {
+            println!("    {}", style(difference).yellow());
+        }
+    }
+
+    for warning in &plan.warnings {
+        println!("{} {}", style("!").yellow().bold(), warning);
+    }
+
+    println!(
+        "\n{}",
+        t!(
+            "import.summary",
+            new = plan.to_add.len(),
+            existing = plan.duplicates.len()
+        )
+    );
+}
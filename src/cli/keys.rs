@@ -0,0 +1,245 @@
+use anyhow::{Result, bail};
+use console::style;
+use dialoguer::{Confirm, Password, theme::ColorfulTheme};
+use rust_i18n::t;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::cli::group::prompt_secrets;
+use crate::models::{AuthMethod, SshConnection};
+use crate::services::audit_service::AuditService;
+use crate::services::config_service::ConfigService;
+use crate::services::exec_service::ExecSession;
+use crate::services::key_service::{DeployableKey, KeyFormat, KeyOptions, KeyService, KeyType};
+use crate::services::log_service::LogService;
+use crate::services::session_manager::SessionManager;
+use crate::services::ssh_service::SshService;
+
+/// Run `keys deploy`: install a public key on a connection's server, then
+/// offer to log in with it from now on
+pub async fn run_keys_deploy(
+    key: &str,
+    name: &str,
+    password: Option<String>,
+    yes: bool,
+) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let connections = config_service.load_connections()?;
+    let settings = config_service.load_settings()?;
+
+    let Some(connection) = connections.iter().find(|c| c.name == name) else {
+        bail!(t!("export.not_found", name = name));
+    };
+    let key = KeyService::new(KeyService::default_ssh_dir()?)?.deployable_key(key)?;
+    let (connection, password) = match password {
+        Some(password) => (connection.clone(), Some(password)),
+        None => prompt_secrets(vec![connection.clone()])?.remove(0),
+    };
+
+    let session_manager =
+        Arc::new(SessionManager::new(300).with_profile(settings.active_profile().cloned()));
+    let log_service = Arc::new(LogService::for_app(&config_service, &settings));
+    let recorder = log_service.record(session_manager.events());
+    let auditor = AuditService::for_app(&config_service, &settings)
+        .map(|audit| audit.record(session_manager.events()));
+
+    let result = async {
+        let added = deploy(&session_manager, &connection, password.as_deref(), &key).await?;
+        let message = if added {
+            t!("keys.deployed", fingerprint = key.fingerprint, name = name)
+        } else {
+            t!(
+                "keys.already_deployed",
+                fingerprint = key.fingerprint,
+                name = name
+            )
+        };
+        println!("{} {}", style("✓").green(), message);
+        offer_switch(&config_service, &session_manager, &connection, &key, yes).await
+    }
+    .await;
+
+    recorder.finish().await;
+    if let Some(auditor) = auditor {
+        auditor.finish().await;
+    }
+    result
+}
+
+/// Append the key to `authorized_keys` over SFTP; returns whether it was
+/// missing
+async fn deploy(
+    session_manager: &SessionManager,
+    connection: &SshConnection,
+    password: Option<&str>,
+    key: &DeployableKey,
+) -> Result<bool> {
+    let session = ExecSession::open(session_manager, connection, password).await?;
+    let result = async {
+        let sftp = session.sftp().await?;
+        let added = sftp.authorize_key(&key.public_key).await;
+        let _ = sftp.close().await;
+        added
+    }
+    .await;
+    let _ = session.close().await;
+    Ok(result?)
+}
+
+/// Switch the connection to the deployed key, once a login with it works
+async fn offer_switch(
+    config: &ConfigService,
+    session_manager: &SessionManager,
+    connection: &SshConnection,
+    key: &DeployableKey,
+    yes: bool,
+) -> Result<()> {
+    let Some(private_key_path) = key.private_key_path.clone() else {
+        println!(
+            "{}",
+            style(t!("keys.no_private_key", name = connection.name)).dim()
+        );
+        return Ok(());
+    };
+    let auth_method = AuthMethod::PublicKey {
+        private_key_path: private_key_path.clone(),
+        passphrase_required: key.has_passphrase,
+    };
+    if connection.auth_method == auth_method {
+        return Ok(());
+    }
+    let switch = yes
+        || Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("keys.switch_prompt", name = connection.name))
+            .default(true)
+            .interact()?;
+    if !switch {
+        return Ok(());
+    }
+
+    let passphrase = if key.has_passphrase {
+        Some(
+            Password::with_theme(&ColorfulTheme::default())
+                .with_prompt(t!(
+                    "keys.passphrase",
+                    path = private_key_path.display().to_string()
+                ))
+                .interact()?,
+        )
+    } else {
+        None
+    };
+    let mut candidate = connection.clone();
+    candidate.auth_method = auth_method.clone();
+    let resolved = session_manager.resolve(&candidate).await?;
+    match SshService::connect(&resolved, passphrase.as_deref()).await {
+        Ok(mut session) => {
+            let _ = SshService::disconnect(&mut session).await;
+        }
+        Err(e) => bail!(t!(
+            "keys.login_failed",
+            name = connection.name,
+            error = e.user_message()
+        )),
+    }
+
+    // Only the authentication changes, whatever else was edited meanwhile
+    config.update_connections(|connections| {
+        match connections.iter_mut().find(|c| c.id == connection.id) {
+            Some(saved) => {
+                saved.auth_method = auth_method.clone();
+                true
+            }
+            None => false,
+        }
+    })?;
+    println!(
+        "{} {}",
+        style("✓").green(),
+        t!(
+            "keys.switched",
+            name = connection.name,
+            path = private_key_path.display().to_string()
+        )
+    );
+    Ok(())
+}
+
+/// Run `keys generate`: write a new key pair to `~/.ssh`
+pub async fn run_keys_generate(
+    name: &str,
+    key_type: &str,
+    format: &str,
+    kdf_rounds: u32,
+    comment: Option<String>,
+    passphrase: Option<String>,
+) -> Result<()> {
+    let key_type = match key_type {
+        "ed25519" => KeyType::Ed25519,
+        "ecdsa-p256" | "ecdsa" => KeyType::EcdsaP256,
+        "ecdsa-p384" => KeyType::EcdsaP384,
+        "rsa-2048" => KeyType::Rsa2048,
+        "rsa-4096" | "rsa" => KeyType::Rsa4096,
+        other => bail!(t!("keys.unknown_type", kind = other)),
+    };
+    let format = match format {
+        "openssh" => KeyFormat::OpenSsh,
+        "pkcs8" => KeyFormat::Pkcs8,
+        other => bail!(t!("keys.unknown_format", format = other)),
+    };
+    let mut options = KeyOptions::default()
+        .with_format(format)
+        .with_kdf_rounds(kdf_rounds);
+    if let Some(comment) = comment {
+        options = options.with_comment(comment);
+    }
+
+    let passphrase = match passphrase {
+        Some(passphrase) => passphrase,
+        None => Password::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("keys.new_passphrase"))
+            .with_confirmation(
+                t!("keys.confirm_passphrase"),
+                t!("keys.passphrase_mismatch"),
+            )
+            .allow_empty_password(true)
+            .interact()?,
+    };
+    let passphrase = (!passphrase.is_empty()).then_some(passphrase);
+
+    let info = KeyService::new(KeyService::default_ssh_dir()?)?
+        .generate_key_with(name, key_type, passphrase.as_deref(), &options)
+        .await?;
+    println!(
+        "{} {}",
+        style("✓").green(),
+        t!(
+            "keys.generated",
+            kind = info.key_type,
+            path = info.path.display().to_string(),
+            format = format.as_str(),
+            fingerprint = info.fingerprint
+        )
+    );
+    Ok(())
+}
+
+/// Run `keys import`: copy a key pair into `~/.ssh`, saying which format the
+/// private key was in
+pub async fn run_keys_import(name: &str, path: &Path) -> Result<()> {
+    let info = KeyService::new(KeyService::default_ssh_dir()?)?
+        .import_key(name, path)
+        .await?;
+    println!(
+        "{} {}",
+        style("✓").green(),
+        t!(
+            "keys.imported",
+            format = info.format.map_or("PEM", |format| format.as_str()),
+            path = info.path.display().to_string(),
+            kind = info.key_type,
+            fingerprint = info.fingerprint
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use anyhow::{Result, bail};
+use chrono::{Local, Utc};
+use console::style;
+use rust_i18n::t;
+use std::time::Duration;
+
+use crate::models::{ConnectionEvent, ConnectionLog, LogLevel};
+use crate::services::config_service::ConfigService;
+use crate::services::log_query::LogQuery;
+use crate::services::log_service::{LogService, LogTail};
+
+/// How often `--follow` checks the log file for new entries
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Options of `logs`
+pub struct LogsArgs {
+    pub connection: Option<String>,
+    pub session: Option<String>,
+    pub level: Option<String>,
+    pub events: Vec<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub grep: Option<String>,
+    pub limit: usize,
+    pub reverse: bool,
+    pub format: String,
+    pub follow: bool,
+}
+
+/// Output format of `logs`
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Table,
+    /// One JSON object per line, as stored
+    Json,
+}
+
+/// Run `logs`: query the connection log, then optionally keep streaming
+pub async fn run_logs(args: LogsArgs) -> Result<()> {
+    let format = match args.format.as_str() {
+        "table" => Format::Table,
+        "json" => Format::Json,
+        other => bail!(t!("logs.invalid_format", format = other)),
+    };
+    let query = build_query(&args)?;
+
+    let config_service = ConfigService::new()?;
+    let log_path = config_service.log_file_path();
+    let service = LogService::new(0).with_file(log_path.clone());
+
+    let entries = service.query(&query).await;
+    let width = entries
+        .iter()
+        .map(|l| l.connection_name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(12);
+    if entries.is_empty() && format == Format::Table && !args.follow {
+        println!("{}", style(t!("logs.none")).yellow());
+    }
+    for entry in &entries {
+        print_entry(entry, format, width)?;
+    }
+
+    if !args.follow {
+        return Ok(());
+    }
+    if format == Format::Table {
+        println!("{}", style(t!("logs.following")).dim());
+    }
+
+    let mut tail = LogTail::new(log_path);
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            _ = tokio::time::sleep(FOLLOW_INTERVAL) => {
+                for entry in tail.poll().await.iter().filter(|l| query.matches(l)) {
+                    print_entry(entry, format, width)?;
+                }
+            }
+        }
+    }
+}
+
+fn build_query(args: &LogsArgs) -> Result<LogQuery> {
+    let mut query = LogQuery::new().with_limit(args.limit);
+    if let Some(connection) = &args.connection {
+        query = query.with_connection(connection);
+    }
+    if let Some(session) = &args.session {
+        let Ok(id) = session.parse() else {
+            bail!(t!("logs.invalid_session", id = session));
+        };
+        query = query.with_session(id);
+    }
+    if let Some(level) = &args.level {
+        let Some(level) = LogLevel::parse(level) else {
+            bail!(t!("logs.invalid_level", level = level));
+        };
+        query = query.with_min_level(level);
+    }
+    for event in &args.events {
+        if !ConnectionEvent::KINDS.contains(&event.as_str()) {
+            bail!(t!(
+                "logs.unknown_event",
+                event = event,
+                kinds = ConnectionEvent::KINDS.join(", ")
+            ));
+        }
+        query = query.with_event(event);
+    }
+
+    let now = Utc::now();
+    let parse_time = |text: &Option<String>| match text {
+        Some(text) => match LogQuery::parse_time(text, now) {
+            Some(time) => Ok(Some(time)),
+            None => bail!(t!("logs.invalid_time", time = text)),
+        },
+        None => Ok(None),
+    };
+    query = query.with_range(parse_time(&args.since)?, parse_time(&args.until)?);
+
+    if let Some(text) = &args.grep {
+        query = query.with_text(text);
+    }
+    if args.reverse {
+        query = query.newest_first();
+    }
+    Ok(query)
+}
+
+fn print_entry(entry: &ConnectionLog, format: Format, width: usize) -> Result<()> {
+    if format == Format::Json {
+        println!("{}", serde_json::to_string(entry)?);
+        return Ok(());
+    }
+
+    let level = match entry.level {
+        LogLevel::Info => style("INFO ").cyan(),
+        LogLevel::Warning => style("WARN ").yellow(),
+        LogLevel::Error => style("ERROR").red(),
+    };
+    println!(
+        "{}  {}  {:width$}  {}{}",
+        style(
+            entry
+                .timestamp
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+        )
+        .dim(),
+        level,
+        entry.connection_name,
+        entry.event.description(),
+        entry
+            .message
+            .as_ref()
+            .map(|m| format!(" - {}", m))
+            .unwrap_or_default(),
+        width = width
+    );
+    Ok(())
+}
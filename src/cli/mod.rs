@@ -1,5 +1,20 @@
+pub mod audit;
 pub mod commands;
+pub mod config;
+pub mod exec;
+pub mod export;
+pub mod forward;
+pub mod group;
+pub mod import;
 pub mod interactive;
+pub mod keys;
+pub mod logs;
+pub mod profile;
+pub mod serve;
+pub mod sessions;
+pub mod shell;
+pub mod template;
+pub mod transfer;
 
 pub use commands::Cli;
 pub use interactive::run_interactive;
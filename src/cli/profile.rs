@@ -0,0 +1,109 @@
+use anyhow::{Result, bail};
+use console::style;
+use rust_i18n::t;
+
+use crate::cli::commands::ProfileAction;
+use crate::services::config_service::ConfigService;
+use crate::services::validation_service::ValidationService;
+
+/// Run a `profile` subcommand
+///
+/// Sessions running in the GUI or interactive mode follow a profile switch
+/// through their config watcher.
+pub fn run_profile(action: ProfileAction) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let mut settings = config_service.load_settings()?;
+
+    match action {
+        ProfileAction::List => {
+            if settings.profiles.is_empty() {
+                println!("{}", style(t!("profile.no_profiles")).yellow());
+            }
+            for profile in &settings.profiles {
+                let active = settings.active_profile.as_deref() == Some(profile.name.as_str());
+                println!(
+                    "{} {}",
+                    if active {
+                        style("*").green()
+                    } else {
+                        style(" ")
+                    },
+                    style(&profile.name).bold()
+                );
+                for (name, value) in &profile.variables {
+                    println!("    {}={}", name, value);
+                }
+            }
+
+            // Report what would fail to resolve with the active profile
+            let validation = ValidationService::new();
+            let profile = settings.active_profile();
+            for connection in config_service.load_connections()? {
+                if let Err(e) = validation.validate_variables(&connection, profile) {
+                    println!(
+                        "{} {}: {}",
+                        style("!").yellow(),
+                        connection.name,
+                        e.user_message()
+                    );
+                }
+            }
+            return Ok(());
+        }
+        ProfileAction::Use { name } => {
+            if !settings.profiles.iter().any(|p| p.name == name) {
+                bail!(t!("profile.not_found", name = name));
+            }
+            println!("{}", style(t!("profile.switched", name = name)).green());
+            settings.active_profile = Some(name);
+        }
+        ProfileAction::Clear => {
+            settings.active_profile = None;
+            println!(
+                "{}",
+                style(t!("profile.switched", name = t!("profile.none"))).green()
+            );
+        }
+        ProfileAction::Set { profile, variables } => {
+            let mut assignments = Vec::new();
+            for variable in &variables {
+                match variable.split_once('=') {
+                    Some((name, value)) if !name.trim().is_empty() => {
+                        assignments.push((name.trim().to_string(), value.to_string()));
+                    }
+                    _ => bail!(t!("profile.invalid_assignment", value = variable)),
+                }
+            }
+
+            let target = settings.profile_mut(&profile);
+            for (name, value) in assignments {
+                target.variables.insert(name, value);
+            }
+            println!(
+                "{}",
+                style(t!(
+                    "profile.updated",
+                    name = profile,
+                    count = variables.len()
+                ))
+                .green()
+            );
+        }
+        ProfileAction::Unset { profile, names } => {
+            let Some(target) = settings.profiles.iter_mut().find(|p| p.name == profile) else {
+                bail!(t!("profile.not_found", name = profile));
+            };
+            let removed = names
+                .iter()
+                .filter(|name| target.variables.remove(name.as_str()).is_some())
+                .count();
+            println!(
+                "{}",
+                style(t!("profile.updated", name = profile, count = removed)).green()
+            );
+        }
+    }
+
+    config_service.save_settings(&settings)?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use console::style;
+use rust_i18n::t;
+
+use crate::cli::commands::ServeAction;
+use crate::services::config_service::ConfigService;
+use crate::services::key_service::KeyService;
+use crate::services::relay_server::{RelayConfig, RelayServer};
+
+/// Run the `serve` subcommand: manage relay keys or start the relay server
+pub async fn run_serve(bind: String, port: u16, action: Option<ServeAction>) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let relay_config = RelayConfig::new(config_service.config_dir())
+        .with_bind_address(bind)
+        .with_port(port);
+    let authorized_keys = relay_config.authorized_keys_path.clone();
+
+    match action {
+        Some(ServeAction::Authorize {
+            key,
+            permit_open,
+            permit_listen,
+        }) => {
+            let key_service = KeyService::new(KeyService::default_ssh_dir()?)?;
+            let entry =
+                key_service.authorize_key(&authorized_keys, &key, permit_open, permit_listen)?;
+            let fingerprint = KeyService::authorized_key_fingerprint(&entry)?;
+            println!(
+                "{}",
+                style(t!("serve.key_authorized", fingerprint = fingerprint)).green()
+            );
+            println!("  {}", entry.permissions_summary());
+        }
+        Some(ServeAction::Revoke { key }) => {
+            let removed = KeyService::revoke_key(&authorized_keys, &key)?;
+            if removed == 0 {
+                println!("{}", style(t!("serve.key_not_found", key = key)).yellow());
+            } else {
+                println!(
+                    "{}",
+                    style(t!("serve.key_revoked", count = removed)).green()
+                );
+            }
+        }
+        Some(ServeAction::Keys) => {
+            let keys = KeyService::load_authorized_keys(&authorized_keys)?;
+            println!(
+                "{}",
+                t!(
+                    "serve.authorized_keys",
+                    path = authorized_keys.display().to_string()
+                )
+            );
+            if keys.is_empty() {
+                println!("{}", t!("serve.no_keys"));
+            }
+            for entry in keys {
+                let fingerprint = KeyService::authorized_key_fingerprint(&entry)
+                    .unwrap_or_else(|_| entry.key_type.clone());
+                println!(
+                    "{} {} - {}",
+                    style(fingerprint).cyan(),
+                    entry.comment,
+                    entry.permissions_summary()
+                );
+            }
+        }
+        None => {
+            let server = RelayServer::new(relay_config)?;
+            let config = server.config();
+            println!(
+                "{}",
+                style(t!(
+                    "serve.listening",
+                    addr = format!("{}:{}", config.bind_address, config.port)
+                ))
+                .green()
+                .bold()
+            );
+            println!(
+                "{}",
+                t!(
+                    "serve.host_key",
+                    fingerprint = server.host_key_fingerprint()
+                )
+            );
+            println!(
+                "{}",
+                t!(
+                    "serve.authorized_keys",
+                    path = authorized_keys.display().to_string()
+                )
+            );
+            if KeyService::load_authorized_keys(&authorized_keys)?.is_empty() {
+                println!("{}", style(t!("serve.no_keys")).yellow());
+            }
+
+            tokio::select! {
+                result = server.run() => result?,
+                _ = tokio::signal::ctrl_c() => {
+                    tracing::info!("Relay server shutting down");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
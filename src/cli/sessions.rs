@@ -0,0 +1,90 @@
+use anyhow::Result;
+use console::{Term, style};
+use rust_i18n::t;
+
+use crate::models::ActiveSession;
+use crate::models::session::{format_bytes, format_rate};
+use crate::services::config_service::ConfigService;
+use crate::services::session_manager::TRAFFIC_SAMPLE_INTERVAL;
+use crate::services::session_snapshot::SessionSnapshot;
+
+/// Intervals shown in each sparkline
+const SPARKLINE_WIDTH: usize = 30;
+
+/// Run `sessions`: show the sessions of every running process, optionally
+/// refreshing until interrupted
+pub async fn run_sessions(watch: bool) -> Result<()> {
+    let dir = ConfigService::new()?.sessions_dir();
+
+    if !watch {
+        print_sessions(&SessionSnapshot::load_all(&dir));
+        return Ok(());
+    }
+
+    let term = Term::stdout();
+    let mut interval = tokio::time::interval(TRAFFIC_SAMPLE_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            _ = interval.tick() => {
+                term.clear_screen()?;
+                println!(
+                    "{}\n",
+                    style(t!(
+                        "sessions.watching",
+                        seconds = TRAFFIC_SAMPLE_INTERVAL.as_secs()
+                    ))
+                    .dim()
+                );
+                print_sessions(&SessionSnapshot::load_all(&dir));
+            }
+        }
+    }
+}
+
+fn print_sessions(snapshots: &[SessionSnapshot]) {
+    if snapshots.iter().all(|s| s.sessions.is_empty()) {
+        println!("{}", style(t!("sessions.none")).yellow());
+        return;
+    }
+
+    println!("{}", style(t!("app.active_sessions")).cyan().bold());
+    println!("{}", style("-".repeat(80)).dim());
+
+    for snapshot in snapshots {
+        for session in &snapshot.sessions {
+            print_session(session);
+            for tunnel in snapshot.tunnels_of(session.id) {
+                println!(
+                    "   {}  {}  {}  {}  {}",
+                    tunnel.config.description(),
+                    style(t!("sessions.channels", count = tunnel.active_channels)).dim(),
+                    format_bytes(tunnel.bytes_sent + tunnel.bytes_received),
+                    format_rate(tunnel.traffic.current_rate()),
+                    style(tunnel.traffic.sparkline(SPARKLINE_WIDTH)).cyan()
+                );
+            }
+        }
+    }
+}
+
+/// Print a session with its duration, traffic, rate and sparkline
+pub(crate) fn print_session(session: &ActiveSession) {
+    println!(
+        "{} {} - {}",
+        style(session.id.to_string()).dim(),
+        style(&session.connection_name).green().bold(),
+        style(session.status.display_str()).blue()
+    );
+    println!(
+        "   {} | {} | {}  {}",
+        t!("session.duration", duration = session.format_duration()),
+        t!(
+            "session.traffic_updown",
+            sent = format_bytes(session.bytes_sent),
+            received = format_bytes(session.bytes_received)
+        ),
+        t!("session.rate", rate = session.format_rate()),
+        style(session.traffic.sparkline(SPARKLINE_WIDTH)).cyan()
+    );
+}
@@ -0,0 +1,112 @@
+use anyhow::{Result, bail};
+use console::style;
+use russh::ChannelMsg;
+use rust_i18n::t;
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::cli::group::prompt_secrets;
+use crate::models::SshConnection;
+use crate::services::audit_service::AuditService;
+use crate::services::config_service::ConfigService;
+use crate::services::exec_service::{ExecSession, exit_code};
+use crate::services::log_service::LogService;
+use crate::services::session_manager::SessionManager;
+use crate::utils::terminal::{self, RawMode, ResizeSignal, StdinReader};
+
+/// Run `shell`: open an interactive shell on a connection, exiting with the
+/// shell's exit status
+pub async fn run_shell(name: &str, password: Option<String>) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let connections = config_service.load_connections()?;
+    let settings = config_service.load_settings()?;
+
+    let Some(connection) = connections.iter().find(|c| c.name == name) else {
+        bail!(t!("export.not_found", name = name));
+    };
+    let (connection, password) = match password {
+        Some(password) => (connection.clone(), Some(password)),
+        None => prompt_secrets(vec![connection.clone()])?.remove(0),
+    };
+
+    let session_manager =
+        Arc::new(SessionManager::new(300).with_profile(settings.active_profile().cloned()));
+    let log_service = Arc::new(LogService::for_app(&config_service, &settings));
+    let recorder = log_service.record(session_manager.events());
+    let auditor = AuditService::for_app(&config_service, &settings)
+        .map(|audit| audit.record(session_manager.events()));
+
+    let code = open_shell(&session_manager, &connection, password.as_deref()).await;
+
+    recorder.finish().await;
+    if let Some(auditor) = auditor {
+        auditor.finish().await;
+    }
+    let code = code.unwrap_or_else(|e| {
+        eprintln!("{} {}", style("✗").red(), e);
+        exit_code(None)
+    });
+    if code != 0 {
+        std::process::exit(code);
+    }
+    Ok(())
+}
+
+/// Open a shell on a connection, on its running session if there is one,
+/// and bridge it to the local terminal until it exits; returns the exit code
+pub(crate) async fn open_shell(
+    session_manager: &SessionManager,
+    connection: &SshConnection,
+    password: Option<&str>,
+) -> Result<i32> {
+    let session = ExecSession::open(session_manager, connection, password).await?;
+    let status = bridge(&session).await;
+    let _ = session.close().await;
+    Ok(exit_code(status?))
+}
+
+/// Copy keys to the remote shell and its output to the terminal, which is
+/// in raw mode meanwhile, passing on size changes
+async fn bridge(session: &ExecSession) -> Result<Option<u32>> {
+    let (cols, rows) = terminal::size();
+    let channel = session.shell(&terminal::term_type(), cols, rows).await?;
+    let (mut reader, writer) = channel.split();
+
+    let _raw = RawMode::enable()?;
+    let mut input = StdinReader::spawn();
+    let mut resize = ResizeSignal::new();
+    let mut stdout = std::io::stdout();
+    let mut stderr = std::io::stderr();
+    let mut input_open = true;
+    let mut status = None;
+
+    loop {
+        tokio::select! {
+            msg = reader.wait() => match msg {
+                Some(ChannelMsg::Data { data }) => {
+                    stdout.write_all(&data)?;
+                    stdout.flush()?;
+                }
+                Some(ChannelMsg::ExtendedData { data, .. }) => {
+                    stderr.write_all(&data)?;
+                    stderr.flush()?;
+                }
+                Some(ChannelMsg::ExitStatus { exit_status }) => status = Some(exit_status),
+                Some(ChannelMsg::Close) | None => break,
+                _ => {}
+            },
+            keys = input.recv(), if input_open => match keys {
+                Some(keys) => writer.data(&keys[..]).await?,
+                None => {
+                    input_open = false;
+                    writer.eof().await?;
+                }
+            },
+            _ = resize.recv() => {
+                let (cols, rows) = terminal::size();
+                writer.window_change(cols, rows, 0, 0).await?;
+            }
+        }
+    }
+    Ok(status)
+}
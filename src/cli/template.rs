@@ -0,0 +1,203 @@
+use anyhow::{Result, bail};
+use console::style;
+use dialoguer::{Input, theme::ColorfulTheme};
+use rust_i18n::t;
+use std::collections::HashMap;
+
+use crate::models::{ConnectionTemplate, SshConnection};
+use crate::services::config_service::ConfigService;
+use crate::services::template_service::TemplateService;
+
+/// Run `templates`: list templates with their parameters
+pub fn run_templates() -> Result<()> {
+    let templates = ConfigService::new()?.load_templates()?;
+
+    for template in &templates {
+        println!(
+            "{} - {}",
+            style(&template.name).green().bold(),
+            template.description
+        );
+        for parameter in &template.parameters {
+            println!(
+                "    {{{}}} {:?}{}",
+                parameter.name,
+                parameter.kind,
+                parameter
+                    .default
+                    .as_ref()
+                    .map(|d| format!(" = {}", d))
+                    .unwrap_or_default()
+            );
+        }
+        for forward in &template.forwards {
+            println!("    {}", style(forward).dim());
+        }
+    }
+    Ok(())
+}
+
+/// Run `template create --from-connection`
+pub fn run_template_create(
+    name: &str,
+    from_connection: &str,
+    description: Option<String>,
+) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let connection = config_service
+        .load_connections()?
+        .into_iter()
+        .find(|c| c.name == from_connection)
+        .ok_or_else(|| anyhow::anyhow!(t!("export.not_found", name = from_connection)))?;
+
+    let mut templates = config_service.load_templates()?;
+    if find_template(&templates, name).is_some() {
+        bail!(t!("template.exists", name = name));
+    }
+
+    let description = description
+        .unwrap_or_else(|| t!("template.from_connection", name = connection.name).to_string());
+    let template = TemplateService::from_connection(name, &description, &connection);
+    let count = template.parameters.len();
+    templates.push(template);
+    config_service.save_templates(&templates)?;
+
+    println!(
+        "{}",
+        style(t!("template.created", name = name, count = count)).green()
+    );
+    Ok(())
+}
+
+/// Run `template delete`
+pub fn run_template_delete(name: &str) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let mut templates = config_service.load_templates()?;
+    let Some(index) = templates
+        .iter()
+        .position(|t| t.name.eq_ignore_ascii_case(name))
+    else {
+        bail!(t!("template.not_found", name = name));
+    };
+
+    let removed = templates.remove(index);
+    config_service.save_templates(&templates)?;
+    println!(
+        "{}",
+        style(t!("template.deleted", name = removed.name)).green()
+    );
+    Ok(())
+}
+
+/// Options of `from-template`
+pub struct FromTemplateArgs {
+    pub template: String,
+    pub name: String,
+    pub host: Option<String>,
+    pub username: Option<String>,
+    /// Parameter values as NAME=VALUE
+    pub values: Vec<String>,
+    /// Take defaults instead of prompting for parameters that have one
+    pub accept_defaults: bool,
+}
+
+/// Run `from-template`: create and save a connection from a template
+pub fn run_from_template(args: FromTemplateArgs) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let templates = config_service.load_templates()?;
+    let template = find_template(&templates, &args.template)
+        .ok_or_else(|| anyhow::anyhow!(t!("template.not_found", name = args.template)))?;
+
+    let mut given = HashMap::new();
+    for value in &args.values {
+        match value.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                given.insert(name.trim().to_string(), value.to_string());
+            }
+            _ => bail!(t!("template.invalid_assignment", value = value)),
+        }
+    }
+
+    let host = match args.host {
+        Some(host) => host,
+        None => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("connection.host"))
+            .interact_text()?,
+    };
+    let username = match args.username {
+        Some(username) => username,
+        None => prompt_username(template)?,
+    };
+    let values = prompt_values(template, given, args.accept_defaults)?;
+
+    let connection = TemplateService::instantiate(template, &args.name, &host, &username, &values)?;
+    save_connection(&config_service, &connection, &template.name)
+}
+
+/// Find a template by name (case-insensitive)
+pub(crate) fn find_template<'a>(
+    templates: &'a [ConnectionTemplate],
+    name: &str,
+) -> Option<&'a ConnectionTemplate> {
+    templates.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+}
+
+/// Ask for the SSH username, offering the template's default
+pub(crate) fn prompt_username(template: &ConnectionTemplate) -> Result<String> {
+    let theme = ColorfulTheme::default();
+    let mut input = Input::<String>::with_theme(&theme).with_prompt(t!("connection.username"));
+    if !template.default_username.is_empty() {
+        input = input.default(template.default_username.clone());
+    }
+    Ok(input.interact_text()?)
+}
+
+/// Ask for every parameter not in `given`, then validate all values
+///
+/// With `accept_defaults`, parameters that have a default are not asked for.
+pub(crate) fn prompt_values(
+    template: &ConnectionTemplate,
+    mut given: HashMap<String, String>,
+    accept_defaults: bool,
+) -> Result<HashMap<String, String>> {
+    let theme = ColorfulTheme::default();
+    for parameter in &template.parameters {
+        if given.contains_key(&parameter.name) || (accept_defaults && parameter.default.is_some()) {
+            continue;
+        }
+
+        let mut input = Input::<String>::with_theme(&theme)
+            .with_prompt(parameter.prompt_text())
+            .validate_with(|value: &String| {
+                TemplateService::validate_parameter(parameter, value).map_err(|e| e.user_message())
+            });
+        if let Some(default) = &parameter.default {
+            input = input.default(default.clone());
+        }
+        given.insert(parameter.name.clone(), input.interact_text()?);
+    }
+
+    Ok(TemplateService::resolve_values(template, &given)?)
+}
+
+/// Save a connection created from a template and report it
+pub(crate) fn save_connection(
+    config_service: &ConfigService,
+    connection: &SshConnection,
+    template: &str,
+) -> Result<()> {
+    config_service.save_connection(connection)?;
+    println!(
+        "{}",
+        style(t!(
+            "template.connection_created",
+            name = connection.name,
+            template = template
+        ))
+        .green()
+    );
+    for forward in &connection.forwarding_configs {
+        println!("    {}", forward.description());
+    }
+    Ok(())
+}
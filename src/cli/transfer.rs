@@ -0,0 +1,152 @@
+use anyhow::{Result, bail};
+use console::style;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use rust_i18n::t;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::cli::group::prompt_secrets;
+use crate::models::SshConnection;
+use crate::services::audit_service::AuditService;
+use crate::services::config_service::ConfigService;
+use crate::services::exec_service::ExecSession;
+use crate::services::log_service::LogService;
+use crate::services::session_manager::SessionManager;
+use crate::services::sftp_service::{TransferOptions, TransferProgress, TransferSummary};
+
+/// Which way `get` and `put` copy
+pub enum Transfer {
+    /// Download a remote path
+    Get { remote: String, local: PathBuf },
+    /// Upload a local path
+    Put { local: PathBuf, remote: String },
+}
+
+/// Run `get` or `put`: copy files over SFTP, showing progress per file
+pub async fn run_transfer(
+    name: &str,
+    transfer: Transfer,
+    options: TransferOptions,
+    password: Option<String>,
+) -> Result<()> {
+    let config_service = ConfigService::new()?;
+    let connections = config_service.load_connections()?;
+    let settings = config_service.load_settings()?;
+
+    let Some(connection) = connections.iter().find(|c| c.name == name) else {
+        bail!(t!("export.not_found", name = name));
+    };
+    let (connection, password) = match password {
+        Some(password) => (connection.clone(), Some(password)),
+        None => prompt_secrets(vec![connection.clone()])?.remove(0),
+    };
+
+    let session_manager =
+        Arc::new(SessionManager::new(300).with_profile(settings.active_profile().cloned()));
+    let log_service = Arc::new(LogService::for_app(&config_service, &settings));
+    let recorder = log_service.record(session_manager.events());
+    let auditor = AuditService::for_app(&config_service, &settings)
+        .map(|audit| audit.record(session_manager.events()));
+
+    let result = copy_files(
+        &session_manager,
+        &connection,
+        password.as_deref(),
+        &transfer,
+        options,
+    )
+    .await;
+
+    recorder.finish().await;
+    if let Some(auditor) = auditor {
+        auditor.finish().await;
+    }
+    print_summary(&result?);
+    Ok(())
+}
+
+pub(crate) fn print_summary(summary: &TransferSummary) {
+    println!(
+        "{} {}",
+        style("✓").green(),
+        t!(
+            "transfer.done",
+            files = summary.files,
+            bytes = HumanBytes(summary.bytes).to_string()
+        )
+    );
+}
+
+/// Copy over an SFTP channel of the connection's running session, or of a
+/// temporary one
+pub(crate) async fn copy_files(
+    session_manager: &SessionManager,
+    connection: &SshConnection,
+    password: Option<&str>,
+    transfer: &Transfer,
+    options: TransferOptions,
+) -> Result<TransferSummary> {
+    let session = ExecSession::open(session_manager, connection, password).await?;
+    let result = async {
+        let sftp = session.sftp().await?;
+        let mut bar = FileProgress::default();
+        let summary = match transfer {
+            Transfer::Get { remote, local } => {
+                sftp.download(remote, local, options, |p| bar.update(p))
+                    .await
+            }
+            Transfer::Put { local, remote } => {
+                sftp.upload(local, remote, options, |p| bar.update(p)).await
+            }
+        };
+        bar.abandon();
+        let _ = sftp.close().await;
+        summary
+    }
+    .await;
+    let _ = session.close().await;
+    Ok(result?)
+}
+
+/// A bytes progress bar for the file being copied
+#[derive(Default)]
+struct FileProgress {
+    bar: Option<ProgressBar>,
+}
+
+impl FileProgress {
+    fn update(&mut self, progress: TransferProgress) {
+        match progress {
+            TransferProgress::Started { path, size, offset } => {
+                let bar = ProgressBar::new(size).with_position(offset);
+                bar.set_style(
+                    ProgressStyle::default_bar()
+                        .template(
+                            "{msg} [{bar:30.cyan/blue}] {bytes}/{total_bytes} {bytes_per_sec} {eta}",
+                        )
+                        .unwrap()
+                        .progress_chars("=> "),
+                );
+                bar.set_message(path);
+                self.bar = Some(bar);
+            }
+            TransferProgress::Copied(bytes) => {
+                if let Some(bar) = &self.bar {
+                    bar.inc(bytes);
+                }
+            }
+            TransferProgress::Finished => {
+                if let Some(bar) = self.bar.take() {
+                    bar.finish();
+                }
+            }
+        }
+    }
+
+    /// Leave the bar of a file whose copy failed where it stopped
+    fn abandon(&mut self) {
+        if let Some(bar) = self.bar.take() {
+            bar.abandon();
+        }
+    }
+}
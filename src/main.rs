@@ -52,30 +52,230 @@ async fn main() -> anyhow::Result<()> {
     } else {
         // Handle command-line commands
         match cli.command {
-            Some(cli::commands::Commands::List) => {
+            Some(cli::commands::Commands::List { tag, group }) => {
                 let config = services::config_service::ConfigService::new()?;
-                let connections = config.load_connections()?;
+                let all = config.load_connections()?;
+                let mut connections =
+                    services::group_service::GroupService::filter(&all, "", tag.as_deref());
+                if let Some(group) = &group {
+                    connections.retain(|c| c.in_group(group));
+                }
 
-                if connections.is_empty() {
+                if all.is_empty() {
                     println!("{}", rust_i18n::t!("connection.no_connections"));
+                } else if connections.is_empty() {
+                    println!("{}", rust_i18n::t!("connection.no_matching"));
                 } else {
                     for conn in connections {
+                        let mut labels = Vec::new();
+                        if let Some(group) = &conn.group {
+                            labels.push(format!("[{}]", group));
+                        }
+                        labels.extend(conn.tags.iter().map(|t| format!("#{}", t)));
                         println!(
-                            "{} - {}@{}:{}",
-                            conn.name, conn.username, conn.host, conn.port
+                            "{} - {}@{}:{}{}",
+                            conn.name,
+                            conn.username,
+                            conn.host,
+                            conn.port,
+                            if labels.is_empty() {
+                                String::new()
+                            } else {
+                                format!("  {}", labels.join(" "))
+                            }
                         );
                     }
                 }
             }
-            Some(cli::commands::Commands::Sessions) => {
-                println!("{}", rust_i18n::t!("message.feature_coming_soon"));
+            Some(cli::commands::Commands::Sessions { watch }) => {
+                cli::sessions::run_sessions(watch).await?;
+            }
+            Some(cli::commands::Commands::Exec {
+                connection,
+                group,
+                password,
+                command,
+            }) => {
+                let target = match (connection, group) {
+                    (_, Some(group)) => cli::exec::ExecTarget::Group(group),
+                    (connection, None) => {
+                        cli::exec::ExecTarget::Connection(connection.unwrap_or_default())
+                    }
+                };
+                cli::exec::run_exec(target, password, command).await?;
+            }
+            Some(cli::commands::Commands::Shell {
+                connection,
+                password,
+            }) => {
+                cli::shell::run_shell(&connection, password).await?;
             }
-            Some(cli::commands::Commands::Templates) => {
-                let templates = models::ConnectionTemplate::builtin_templates();
-                for template in templates {
-                    println!("{} - {}", template.name, template.description);
+            Some(cli::commands::Commands::Get {
+                connection,
+                remote,
+                local,
+                recursive,
+                resume,
+                password,
+            }) => {
+                let transfer = cli::transfer::Transfer::Get {
+                    remote,
+                    local: local.unwrap_or_else(|| std::path::PathBuf::from(".")),
+                };
+                let options = services::sftp_service::TransferOptions { recursive, resume };
+                cli::transfer::run_transfer(&connection, transfer, options, password).await?;
+            }
+            Some(cli::commands::Commands::Put {
+                connection,
+                local,
+                remote,
+                recursive,
+                resume,
+                password,
+            }) => {
+                let transfer = cli::transfer::Transfer::Put {
+                    local,
+                    remote: remote.unwrap_or_else(|| ".".to_string()),
+                };
+                let options = services::sftp_service::TransferOptions { recursive, resume };
+                cli::transfer::run_transfer(&connection, transfer, options, password).await?;
+            }
+            Some(cli::commands::Commands::Templates) => cli::template::run_templates()?,
+            Some(cli::commands::Commands::Template { action }) => match action {
+                cli::commands::TemplateAction::Create {
+                    name,
+                    from_connection,
+                    description,
+                } => cli::template::run_template_create(&name, &from_connection, description)?,
+                cli::commands::TemplateAction::Delete { name } => {
+                    cli::template::run_template_delete(&name)?;
                 }
+            },
+            Some(cli::commands::Commands::FromTemplate {
+                template,
+                name,
+                host,
+                username,
+                values,
+                yes,
+            }) => {
+                cli::template::run_from_template(cli::template::FromTemplateArgs {
+                    template,
+                    name,
+                    host,
+                    username,
+                    values,
+                    accept_defaults: yes,
+                })?;
+            }
+            Some(cli::commands::Commands::Serve { bind, port, action }) => {
+                cli::serve::run_serve(bind, port, action).await?;
             }
+            Some(cli::commands::Commands::Logs {
+                connection,
+                session,
+                level,
+                events,
+                since,
+                until,
+                grep,
+                limit,
+                reverse,
+                format,
+                follow,
+            }) => {
+                cli::logs::run_logs(cli::logs::LogsArgs {
+                    connection,
+                    session,
+                    level,
+                    events,
+                    since,
+                    until,
+                    grep,
+                    limit,
+                    reverse,
+                    format,
+                    follow,
+                })
+                .await?;
+            }
+            Some(cli::commands::Commands::Import { source }) => match source {
+                cli::commands::ImportSource::SshConfig { file, dry_run } => {
+                    cli::import::run_import_ssh_config(file, dry_run)?;
+                }
+            },
+            Some(cli::commands::Commands::Export { target }) => match target {
+                cli::commands::ExportTarget::SshConfig {
+                    connections,
+                    output,
+                    force,
+                } => {
+                    cli::export::run_export_ssh_config(connections, output, force)?;
+                }
+            },
+            Some(cli::commands::Commands::Forward {
+                connection,
+                r#type,
+                local_port,
+                remote_host,
+                remote_port,
+                local_host,
+                remove,
+            }) => {
+                let forward = cli::forward::ForwardSpec {
+                    kind: r#type,
+                    local_port,
+                    remote_host,
+                    remote_port,
+                    local_host,
+                };
+                cli::forward::run_forward(&connection, forward, remove)?;
+            }
+            Some(cli::commands::Commands::Group { action }) => match action {
+                cli::commands::GroupAction::List => cli::group::run_group_list()?,
+                cli::commands::GroupAction::Connect { group } => {
+                    cli::group::run_group_connect(&group).await?;
+                }
+            },
+            Some(cli::commands::Commands::Profile { action }) => {
+                cli::profile::run_profile(action)?;
+            }
+            Some(cli::commands::Commands::Config { action }) => match action {
+                cli::commands::ConfigAction::Migrate { check } => {
+                    cli::config::run_config_migrate(check)?;
+                }
+            },
+            Some(cli::commands::Commands::Audit { action }) => match action {
+                cli::commands::AuditAction::Verify { file } => {
+                    cli::audit::run_audit_verify(file)?;
+                }
+            },
+            Some(cli::commands::Commands::Keys { action }) => match action {
+                cli::commands::KeysAction::Deploy {
+                    key,
+                    connection,
+                    password,
+                    yes,
+                } => {
+                    cli::keys::run_keys_deploy(&key, &connection, password, yes).await?;
+                }
+                cli::commands::KeysAction::Generate {
+                    name,
+                    key_type,
+                    format,
+                    kdf_rounds,
+                    comment,
+                    passphrase,
+                } => {
+                    cli::keys::run_keys_generate(
+                        &name, &key_type, &format, kdf_rounds, comment, passphrase,
+                    )
+                    .await?;
+                }
+                cli::commands::KeysAction::Import { name, path } => {
+                    cli::keys::run_keys_import(&name, &path).await?;
+                }
+            },
             _ => {
                 println!("{}", rust_i18n::t!("message.feature_coming_soon"));
             }
@@ -0,0 +1,220 @@
+use crate::models::ConnectionLog;
+use crate::utils::error::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// `prev_hash` of the first entry in an audit trail
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Audit trail entry: a connection log entry, who caused it, and its link in
+/// the hash chain
+///
+/// `hash` covers everything else in the entry including `prev_hash`, so
+/// changing, inserting or removing an entry breaks the chain from there on.
+///
+/// The hash is taken over the exact bytes stored in the trail: the entry's
+/// line is its JSON without `hash`, with `,"hash":"<hex>"` inserted before
+/// the closing brace. Checking a line only needs that field cut back out
+/// (see [`AuditEntry::line_is_intact`]), so entries stay verifiable however
+/// the fields are serialized in later versions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the trail, starting at 1
+    pub seq: u64,
+
+    /// OS user running the app
+    pub user: String,
+
+    /// Host the app runs on
+    pub hostname: String,
+
+    pub log: ConnectionLog,
+
+    /// `hash` of the previous entry
+    pub prev_hash: String,
+
+    /// SHA-256 of this entry, hex encoded
+    pub hash: String,
+}
+
+/// Field added to the hashed bytes to form the stored line
+const HASH_FIELD: &str = ",\"hash\":\"";
+
+/// The fields covered by [`AuditEntry::hash`]
+#[derive(Serialize)]
+struct HashedFields<'a> {
+    seq: u64,
+    user: &'a str,
+    hostname: &'a str,
+    log: &'a ConnectionLog,
+    prev_hash: &'a str,
+}
+
+impl AuditEntry {
+    /// Entry following the one with `prev_hash`
+    pub fn new(
+        seq: u64,
+        user: impl Into<String>,
+        hostname: impl Into<String>,
+        log: ConnectionLog,
+        prev_hash: impl Into<String>,
+    ) -> Result<Self> {
+        let mut entry = Self {
+            seq,
+            user: user.into(),
+            hostname: hostname.into(),
+            log,
+            prev_hash: prev_hash.into(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash()?;
+        Ok(entry)
+    }
+
+    /// Hash of the entry's contents, as it should be stored in `hash`
+    pub fn compute_hash(&self) -> Result<String> {
+        Ok(sha256_hex(&self.hashed_json()?))
+    }
+
+    /// The line stored in the trail for this entry, without a newline
+    pub fn to_line(&self) -> Result<String> {
+        let json = self.hashed_json()?;
+        let body = json.strip_suffix('}').unwrap_or(&json);
+        Ok(format!("{}{}{}\"}}", body, HASH_FIELD, self.hash))
+    }
+
+    /// Whether the `hash` field of a stored line matches the rest of the
+    /// line's bytes
+    pub fn line_is_intact(line: &str) -> bool {
+        let Some((body, hash)) = line
+            .trim_end()
+            .strip_suffix("\"}")
+            .and_then(|line| line.rsplit_once(HASH_FIELD))
+        else {
+            return false;
+        };
+        sha256_hex(&format!("{}}}", body)) == hash
+    }
+
+    /// JSON of the fields covered by `hash`
+    fn hashed_json(&self) -> Result<String> {
+        let fields = HashedFields {
+            seq: self.seq,
+            user: &self.user,
+            hostname: &self.hostname,
+            log: &self.log,
+            prev_hash: &self.prev_hash,
+        };
+        Ok(serde_json::to_string(&fields).map_err(anyhow::Error::from)?)
+    }
+
+    /// Entry following this one
+    pub fn next(
+        &self,
+        user: impl Into<String>,
+        hostname: impl Into<String>,
+        log: ConnectionLog,
+    ) -> Result<Self> {
+        Self::new(self.seq + 1, user, hostname, log, self.hash.clone())
+    }
+}
+
+fn sha256_hex(data: &str) -> String {
+    Sha256::digest(data.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionEvent, LogLevel};
+    use uuid::Uuid;
+
+    fn log(event: ConnectionEvent) -> ConnectionLog {
+        ConnectionLog::new(Uuid::new_v4(), "prod-db", LogLevel::Info, event)
+    }
+
+    #[test]
+    fn test_audit_entries_chain() {
+        let first = AuditEntry::new(
+            1,
+            "alice",
+            "laptop",
+            log(ConnectionEvent::Connected),
+            GENESIS_HASH,
+        )
+        .unwrap();
+        let second = first
+            .next(
+                "alice",
+                "laptop",
+                log(ConnectionEvent::CommandExecuted {
+                    command: "uptime".to_string(),
+                }),
+            )
+            .unwrap();
+
+        assert_eq!(first.hash, first.compute_hash().unwrap());
+        assert!(AuditEntry::line_is_intact(&first.to_line().unwrap()));
+        assert!(AuditEntry::line_is_intact(&second.to_line().unwrap()));
+        assert_eq!(first.hash.len(), 64);
+        assert_eq!(second.seq, 2);
+        assert_eq!(second.prev_hash, first.hash);
+        assert_ne!(second.hash, first.hash);
+    }
+
+    #[test]
+    fn test_audit_entry_detects_changes() {
+        let entry = AuditEntry::new(
+            1,
+            "alice",
+            "laptop",
+            log(ConnectionEvent::Connected),
+            GENESIS_HASH,
+        )
+        .unwrap();
+
+        // The stored line reads back as the same entry
+        let line = entry.to_line().unwrap();
+        let stored: AuditEntry = serde_json::from_str(&line).unwrap();
+        assert_eq!(stored.hash, entry.hash);
+        assert_eq!(stored.to_line().unwrap(), line);
+
+        let mut changed = stored.clone();
+        changed.user = "mallory".to_string();
+        assert!(!AuditEntry::line_is_intact(&changed.to_line().unwrap()));
+
+        let mut changed = stored;
+        changed.log.message = Some("nothing to see".to_string());
+        assert!(!AuditEntry::line_is_intact(&changed.to_line().unwrap()));
+
+        assert!(!AuditEntry::line_is_intact(
+            &line.replace("laptop", "desktop")
+        ));
+        assert!(!AuditEntry::line_is_intact(
+            &line.replace(",\"hash\":", ",\"sum\":")
+        ));
+    }
+
+    #[test]
+    fn test_fixed_audit_line_verifies() {
+        // Written by an earlier build; must keep verifying byte for byte
+        let line = concat!(
+            r#"{"seq":1,"user":"alice","hostname":"laptop","log":{"id":"#,
+            r#""0b9e8d7c-6a5f-4e3d-8c2b-1a0f9e8d7c6b","session_id":null,"#,
+            r#""connection_id":"6f1c2a4e-8d3b-4c5a-9e7f-0a1b2c3d4e5f","#,
+            r#""connection_name":"prod-db","level":"Info","#,
+            r#""event":{"CommandExecuted":{"command":"uptime"}},"message":null,"#,
+            r#""timestamp":"2026-03-01T09:30:00Z","metadata":null},"#,
+            r#""prev_hash":"0000000000000000000000000000000000000000000000000000000000000000","#,
+            r#""hash":"5746bc563cd5ce8a2c4b177f3e008716a9fed0ed77b62ddc981cfba4f5463c54"}"#,
+        );
+        let entry: AuditEntry = serde_json::from_str(line).unwrap();
+        assert_eq!(entry.seq, 1);
+        assert_eq!(entry.prev_hash, GENESIS_HASH);
+        assert!(AuditEntry::line_is_intact(line));
+        assert!(!AuditEntry::line_is_intact(&line.replace("alice", "alicf")));
+    }
+}
@@ -25,6 +25,17 @@ impl AuthMethod {
         matches!(self, Self::PublicKey { .. })
     }
 
+    /// Whether connecting needs a password or key passphrase from the user
+    pub fn requires_secret(&self) -> bool {
+        match self {
+            Self::Password => true,
+            Self::PublicKey {
+                passphrase_required,
+                ..
+            } => *passphrase_required,
+        }
+    }
+
     pub fn public_key(path: impl Into<PathBuf>, passphrase_required: bool) -> Self {
         Self::PublicKey {
             private_key_path: path.into(),
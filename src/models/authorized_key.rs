@@ -0,0 +1,353 @@
+use crate::utils::error::{Result, SshToolError};
+
+/// An entry in the relay server's authorized_keys file
+///
+/// Entries are stored in OpenSSH syntax, so the same file also works with sshd:
+/// `restrict,port-forwarding,permitopen="db:5432",permitlisten="8080" ssh-ed25519 AAAA... alice`
+///
+/// Forwarding is denied unless explicitly permitted: an empty `permit_open`
+/// list allows no `-L`/`-D` destinations and an empty `permit_listen` list
+/// allows no `-R` listen ports. Use `*` as host or port to match anything.
+///
+/// As with sshd's default `GatewayPorts no`, a `permitlisten` entry without a
+/// host only allows listening on loopback; other addresses must be named,
+/// e.g. `0.0.0.0:8080` or `*:8080`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorizedKey {
+    /// Key algorithm, e.g. "ssh-ed25519"
+    pub key_type: String,
+    /// Base64-encoded public key blob
+    pub key_data: String,
+    /// Free-form comment (usually user@host)
+    pub comment: String,
+    /// Allowed direct-tcpip destinations as "host:port"
+    pub permit_open: Vec<String>,
+    /// Allowed tcpip-forward listen addresses as "port" (loopback only) or
+    /// "host:port"
+    pub permit_listen: Vec<String>,
+    /// Other options (e.g. `from="10.0.0.0/8"`), kept as written so
+    /// rewriting the file does not drop them
+    pub options: Vec<String>,
+}
+
+impl AuthorizedKey {
+    pub fn new(key_type: impl Into<String>, key_data: impl Into<String>) -> Self {
+        Self {
+            key_type: key_type.into(),
+            key_data: key_data.into(),
+            comment: String::new(),
+            permit_open: vec![],
+            permit_listen: vec![],
+            options: vec![],
+        }
+    }
+
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = comment.into();
+        self
+    }
+
+    pub fn with_permit_open(mut self, destination: impl Into<String>) -> Self {
+        self.permit_open.push(destination.into());
+        self
+    }
+
+    pub fn with_permit_listen(mut self, listen: impl Into<String>) -> Self {
+        self.permit_listen.push(listen.into());
+        self
+    }
+
+    /// Parse a single authorized_keys line
+    pub fn parse(line: &str) -> Result<Self> {
+        let line = line.trim();
+        let (options, rest) = if is_key_type(line.split_whitespace().next().unwrap_or("")) {
+            ("", line)
+        } else {
+            split_options(line)
+        };
+
+        let mut fields = rest.split_whitespace();
+        let key_type = fields.next().filter(|t| is_key_type(t)).ok_or_else(|| {
+            SshToolError::ConfigError(format!("Invalid authorized key: {}", line))
+        })?;
+        let key_data = fields.next().ok_or_else(|| {
+            SshToolError::ConfigError(format!("Missing key data in authorized key: {}", line))
+        })?;
+        let comment = fields.collect::<Vec<_>>().join(" ");
+
+        let mut entry = Self::new(key_type, key_data).with_comment(comment);
+        for option in split_unquoted(options, ',') {
+            let (name, value) = option.split_once('=').unwrap_or((option, ""));
+            let value = value.trim_matches('"').to_string();
+            match name.to_ascii_lowercase().as_str() {
+                "permitopen" => entry.permit_open.push(value),
+                "permitlisten" => entry.permit_listen.push(value),
+                // Written by `to_line` anyway
+                "restrict" | "port-forwarding" => {}
+                _ => entry.options.push(option.to_string()),
+            }
+        }
+
+        Ok(entry)
+    }
+
+    /// Format as an authorized_keys line restricted to port forwarding
+    pub fn to_line(&self) -> String {
+        let mut options = vec!["restrict".to_string(), "port-forwarding".to_string()];
+        options.extend(self.options.iter().cloned());
+        options.extend(
+            self.permit_open
+                .iter()
+                .map(|d| format!("permitopen=\"{}\"", d)),
+        );
+        options.extend(
+            self.permit_listen
+                .iter()
+                .map(|l| format!("permitlisten=\"{}\"", l)),
+        );
+
+        let mut line = format!("{} {} {}", options.join(","), self.key_type, self.key_data);
+        if !self.comment.is_empty() {
+            line.push(' ');
+            line.push_str(&self.comment);
+        }
+        line
+    }
+
+    /// The public key in OpenSSH format ("type base64")
+    pub fn public_key(&self) -> String {
+        format!("{} {}", self.key_type, self.key_data)
+    }
+
+    /// Whether a direct-tcpip channel to host:port is permitted
+    pub fn allows_open(&self, host: &str, port: u16) -> bool {
+        self.permit_open.iter().any(|entry| {
+            let (allowed_host, allowed_port) = split_host_port(entry);
+            allowed_host.is_some_and(|h| host_matches(h, host)) && port_matches(allowed_port, port)
+        })
+    }
+
+    /// Whether a tcpip-forward listener on address:port is permitted
+    pub fn allows_listen(&self, address: &str, port: u16) -> bool {
+        self.permit_listen.iter().any(|entry| {
+            let (allowed_host, allowed_port) = split_host_port(entry);
+            let host_allowed = match allowed_host {
+                Some(h) => host_matches(h, address),
+                None => is_loopback(address),
+            };
+            host_allowed && port_matches(allowed_port, port)
+        })
+    }
+
+    /// Human-readable summary of the permissions
+    pub fn permissions_summary(&self) -> String {
+        let open = if self.permit_open.is_empty() {
+            "-".to_string()
+        } else {
+            self.permit_open.join(",")
+        };
+        let listen = if self.permit_listen.is_empty() {
+            "-".to_string()
+        } else {
+            self.permit_listen.join(",")
+        };
+        format!("open: {} | listen: {}", open, listen)
+    }
+}
+
+fn is_key_type(token: &str) -> bool {
+    token.starts_with("ssh-") || token.starts_with("ecdsa-") || token.starts_with("sk-")
+}
+
+/// Split a line into its leading options field and the remainder
+fn split_options(line: &str) -> (&str, &str) {
+    let mut in_quotes = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => return (&line[..i], line[i..].trim_start()),
+            _ => {}
+        }
+    }
+    (line, "")
+}
+
+/// Split on `sep`, ignoring separators inside double quotes
+fn split_unquoted(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == sep && !in_quotes {
+            parts.push(&s[start..i]);
+            start = i + 1;
+        }
+    }
+    if start < s.len() {
+        parts.push(&s[start..]);
+    }
+    parts
+}
+
+/// Split "host:port", "[v6]:port" or "port" into (host, port)
+fn split_host_port(entry: &str) -> (Option<&str>, &str) {
+    match entry.rsplit_once(':') {
+        Some((host, port)) => (
+            Some(host.trim_start_matches('[').trim_end_matches(']')),
+            port,
+        ),
+        None => (None, entry),
+    }
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    pattern == "*" || pattern.eq_ignore_ascii_case(host)
+}
+
+/// Whether a listen address only reaches this machine (`""` is what clients
+/// send for "localhost")
+fn is_loopback(address: &str) -> bool {
+    matches!(address, "" | "localhost")
+        || address
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+fn port_matches(pattern: &str, port: u16) -> bool {
+    pattern == "*" || pattern.parse::<u16>().is_ok_and(|p| p == port && p != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIKc5bV0eMAxs2Fp9E/5tCYZmfiq3wQjGzKdjeCgHRRtU";
+
+    #[test]
+    fn test_parse_plain_key() {
+        let entry = AuthorizedKey::parse(&format!("ssh-ed25519 {} alice@laptop", KEY)).unwrap();
+        assert_eq!(entry.key_type, "ssh-ed25519");
+        assert_eq!(entry.key_data, KEY);
+        assert_eq!(entry.comment, "alice@laptop");
+        assert!(entry.permit_open.is_empty());
+        assert!(entry.permit_listen.is_empty());
+    }
+
+    #[test]
+    fn test_parse_with_options() {
+        let line = format!(
+            "restrict,port-forwarding,permitopen=\"db:5432\",permitopen=\"*:80\",permitlisten=\"8080\" ssh-ed25519 {} bob",
+            KEY
+        );
+        let entry = AuthorizedKey::parse(&line).unwrap();
+        assert_eq!(entry.permit_open, vec!["db:5432", "*:80"]);
+        assert_eq!(entry.permit_listen, vec!["8080"]);
+        assert_eq!(entry.comment, "bob");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(AuthorizedKey::parse("not a key").is_err());
+        assert!(AuthorizedKey::parse("ssh-ed25519").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let entry = AuthorizedKey::new("ssh-ed25519", KEY)
+            .with_comment("carol")
+            .with_permit_open("localhost:3000")
+            .with_permit_listen("127.0.0.1:9000");
+        let parsed = AuthorizedKey::parse(&entry.to_line()).unwrap();
+        assert_eq!(parsed, entry);
+        assert!(entry.to_line().starts_with("restrict,port-forwarding,"));
+    }
+
+    #[test]
+    fn test_round_trip_keeps_other_options() {
+        let line = format!(
+            "from=\"10.0.0.0/8\",expiry-time=\"20301231\",permitopen=\"db:5432\" ssh-ed25519 {} dave",
+            KEY
+        );
+        let entry = AuthorizedKey::parse(&line).unwrap();
+        assert_eq!(
+            entry.options,
+            vec!["from=\"10.0.0.0/8\"", "expiry-time=\"20301231\""]
+        );
+        assert_eq!(entry.permit_open, vec!["db:5432"]);
+
+        let rewritten = entry.to_line();
+        assert_eq!(
+            rewritten,
+            format!(
+                "restrict,port-forwarding,from=\"10.0.0.0/8\",expiry-time=\"20301231\",permitopen=\"db:5432\" ssh-ed25519 {} dave",
+                KEY
+            )
+        );
+        assert_eq!(AuthorizedKey::parse(&rewritten).unwrap(), entry);
+    }
+
+    #[test]
+    fn test_allows_open() {
+        let entry = AuthorizedKey::new("ssh-ed25519", KEY)
+            .with_permit_open("db.internal:5432")
+            .with_permit_open("localhost:*");
+        assert!(entry.allows_open("db.internal", 5432));
+        assert!(entry.allows_open("DB.INTERNAL", 5432));
+        assert!(!entry.allows_open("db.internal", 5433));
+        assert!(entry.allows_open("localhost", 22));
+        assert!(!entry.allows_open("example.com", 80));
+    }
+
+    #[test]
+    fn test_allows_open_denied_by_default() {
+        let entry = AuthorizedKey::new("ssh-ed25519", KEY);
+        assert!(!entry.allows_open("localhost", 80));
+        assert!(!entry.allows_listen("127.0.0.1", 8080));
+    }
+
+    #[test]
+    fn test_allows_listen() {
+        let entry = AuthorizedKey::new("ssh-ed25519", KEY)
+            .with_permit_listen("8080")
+            .with_permit_listen("127.0.0.1:9000");
+        assert!(entry.allows_listen("localhost", 8080));
+        assert!(entry.allows_listen("127.0.0.1", 8080));
+        assert!(entry.allows_listen("::1", 8080));
+        assert!(entry.allows_listen("127.0.0.1", 9000));
+        assert!(!entry.allows_listen("0.0.0.0", 9000));
+        assert!(!entry.allows_listen("0.0.0.0", 0));
+    }
+
+    #[test]
+    fn test_port_only_listen_is_loopback_only() {
+        let entry = AuthorizedKey::new("ssh-ed25519", KEY).with_permit_listen("8080");
+        assert!(!entry.allows_listen("0.0.0.0", 8080));
+        assert!(!entry.allows_listen("::", 8080));
+        assert!(!entry.allows_listen("192.168.1.10", 8080));
+
+        let exposed = AuthorizedKey::new("ssh-ed25519", KEY)
+            .with_permit_listen("0.0.0.0:8080")
+            .with_permit_listen("*:9000");
+        assert!(exposed.allows_listen("0.0.0.0", 8080));
+        assert!(!exposed.allows_listen("192.168.1.10", 8080));
+        assert!(exposed.allows_listen("192.168.1.10", 9000));
+    }
+
+    #[test]
+    fn test_allows_listen_wildcard_port() {
+        let entry = AuthorizedKey::new("ssh-ed25519", KEY).with_permit_listen("*");
+        assert!(entry.allows_listen("127.0.0.1", 0));
+        assert!(entry.allows_listen("localhost", 12345));
+        assert!(!entry.allows_listen("0.0.0.0", 0));
+    }
+
+    #[test]
+    fn test_ipv6_destination() {
+        let entry = AuthorizedKey::new("ssh-ed25519", KEY).with_permit_open("[::1]:8080");
+        assert!(entry.allows_open("::1", 8080));
+    }
+}
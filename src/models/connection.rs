@@ -1,10 +1,12 @@
-use super::{AuthMethod, ForwardingConfig};
+use super::{AuthMethod, ConnectionHooks, EnvProfile, ForwardingConfig, Hook, HookStage};
+use crate::utils::error::{Result, SshToolError};
+use crate::utils::interpolate::interpolate;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 /// SSH connection configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SshConnection {
     /// Unique identifier
     #[serde(default = "Uuid::new_v4")]
@@ -57,6 +59,19 @@ pub struct SshConnection {
     #[serde(default)]
     pub quiet_mode: bool,
 
+    /// Group path with levels separated by `/` (e.g. "staging/eu")
+    #[serde(default)]
+    pub group: Option<String>,
+
+    /// Free-form labels for filtering (e.g. "db", "critical")
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Commands run before connecting, after connecting and once tunnels
+    /// are up
+    #[serde(default, skip_serializing_if = "ConnectionHooks::is_empty")]
+    pub hooks: ConnectionHooks,
+
     /// Creation timestamp
     #[serde(default = "Utc::now")]
     pub created_at: DateTime<Utc>,
@@ -67,7 +82,7 @@ pub struct SshConnection {
 }
 
 /// Jump host configuration for multi-level SSH
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct JumpHost {
     pub host: String,
     #[serde(default = "default_ssh_port")]
@@ -118,6 +133,9 @@ impl SshConnection {
             verify_host_key: default_verify_host_key(),
             compression: default_compression(),
             quiet_mode: false,
+            group: None,
+            tags: vec![],
+            hooks: ConnectionHooks::default(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -148,6 +166,36 @@ impl SshConnection {
         self
     }
 
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn with_hook(mut self, stage: HookStage, hook: Hook) -> Self {
+        self.hooks.add(stage, hook);
+        self
+    }
+
+    /// Whether the connection carries `tag` (case-insensitive)
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Whether the connection is in `group` or one of its subgroups
+    /// (case-insensitive)
+    pub fn in_group(&self, group: &str) -> bool {
+        let wanted = group.trim_matches('/').to_lowercase();
+        self.group.as_deref().is_some_and(|g| {
+            let g = g.trim_matches('/').to_lowercase();
+            g == wanted || g.starts_with(&format!("{}/", wanted))
+        })
+    }
+
     /// Update the last modified timestamp
     pub fn touch(&mut self) {
         self.updated_at = Utc::now();
@@ -157,6 +205,63 @@ impl SshConnection {
     pub fn display_name(&self) -> String {
         format!("{}@{}:{}", self.username, self.host, self.port)
     }
+
+    /// Copy of the connection with `${VAR}` placeholders replaced
+    ///
+    /// Values come from `profile`, then the process environment. Host,
+    /// username, key path, jump hosts and forwarding targets are resolved;
+    /// every variable that cannot be resolved is reported in the error.
+    pub fn resolve(&self, profile: Option<&EnvProfile>) -> Result<SshConnection> {
+        let mut resolved = self.clone();
+        let mut missing: Vec<String> = Vec::new();
+        let mut resolve =
+            |text: &mut String| match interpolate(text, |name| EnvProfile::lookup(profile, name)) {
+                Ok(value) => *text = value,
+                Err(names) => missing.extend(names),
+            };
+
+        resolve(&mut resolved.host);
+        resolve(&mut resolved.username);
+        resolve_key_path(&mut resolved.auth_method, &mut resolve);
+        for jump in &mut resolved.jump_hosts {
+            resolve(&mut jump.host);
+            resolve(&mut jump.username);
+            resolve_key_path(&mut jump.auth_method, &mut resolve);
+        }
+        for forward in &mut resolved.forwarding_configs {
+            match forward {
+                ForwardingConfig::Local(local) => {
+                    resolve(&mut local.remote_host);
+                    resolve(&mut local.bind_address);
+                }
+                ForwardingConfig::Remote(remote) => resolve(&mut remote.local_host),
+                ForwardingConfig::Dynamic(dynamic) => resolve(&mut dynamic.bind_address),
+            }
+        }
+
+        if missing.is_empty() {
+            Ok(resolved)
+        } else {
+            let mut names: Vec<String> = Vec::new();
+            for name in missing {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            Err(SshToolError::UnresolvedVariables(names))
+        }
+    }
+}
+
+fn resolve_key_path(auth_method: &mut AuthMethod, resolve: &mut impl FnMut(&mut String)) {
+    if let AuthMethod::PublicKey {
+        private_key_path, ..
+    } = auth_method
+    {
+        let mut path = private_key_path.to_string_lossy().to_string();
+        resolve(&mut path);
+        *private_key_path = path.into();
+    }
 }
 
 #[allow(dead_code)]
@@ -296,4 +401,56 @@ mod tests {
         assert_eq!(deserialized.host, "host.com");
         assert_eq!(deserialized.port, 2222);
     }
+
+    #[test]
+    fn test_connection_groups_and_tags() {
+        let conn = SshConnection::new("Test", "host.com", "user")
+            .with_group("staging/eu")
+            .with_tag("DB");
+
+        assert!(conn.in_group("staging"));
+        assert!(conn.in_group("Staging/EU/"));
+        assert!(!conn.in_group("stag"));
+        assert!(!conn.in_group("staging/eu/west"));
+        assert!(conn.has_tag("db"));
+        assert!(!SshConnection::new("Test", "host.com", "user").in_group("staging"));
+    }
+
+    #[test]
+    fn test_resolve_placeholders_from_profile() {
+        let profile = EnvProfile::new("staging")
+            .with_var("ENV", "staging")
+            .with_var("USER_NAME", "deploy");
+        let conn = SshConnection::new("DB", "bastion.${ENV}.example.com", "${USER_NAME}")
+            .with_auth_method(AuthMethod::public_key("~/.ssh/${ENV}_ed25519", false))
+            .with_forwarding(ForwardingConfig::local(5432, "db.${ENV}.internal", 5432));
+
+        let resolved = conn.resolve(Some(&profile)).unwrap();
+        assert_eq!(resolved.host, "bastion.staging.example.com");
+        assert_eq!(resolved.username, "deploy");
+        assert!(matches!(
+            &resolved.auth_method,
+            AuthMethod::PublicKey { private_key_path, .. }
+                if private_key_path == &PathBuf::from("~/.ssh/staging_ed25519")
+        ));
+        assert!(matches!(
+            &resolved.forwarding_configs[0],
+            ForwardingConfig::Local(local) if local.remote_host == "db.staging.internal"
+        ));
+        // The saved connection keeps its placeholders
+        assert_eq!(conn.host, "bastion.${ENV}.example.com");
+    }
+
+    #[test]
+    fn test_resolve_reports_unresolved_variables() {
+        let conn = SshConnection::new("DB", "${SSH_TM_HOST}", "${SSH_TM_USER}")
+            .with_jump_host(JumpHost::new("${SSH_TM_HOST}", "jump"));
+
+        match conn.resolve(None) {
+            Err(SshToolError::UnresolvedVariables(names)) => {
+                assert_eq!(names, vec!["SSH_TM_HOST", "SSH_TM_USER"]);
+            }
+            other => panic!("expected unresolved variables, got {:?}", other),
+        }
+    }
 }
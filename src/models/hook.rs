@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+
+/// When a hook runs in the life of a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookStage {
+    /// Locally, before connecting (e.g. refresh a certificate, wait for VPN)
+    PreConnect,
+
+    /// On the server, once the session is up (e.g. start a forward target)
+    PostConnect,
+
+    /// Locally, once the tunnels are bound (e.g. open a browser)
+    PostTunnel,
+}
+
+impl HookStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PreConnect => "pre_connect",
+            Self::PostConnect => "post_connect",
+            Self::PostTunnel => "post_tunnel",
+        }
+    }
+
+    /// Whether hooks of this stage run on the server rather than locally
+    pub fn is_remote(&self) -> bool {
+        matches!(self, Self::PostConnect)
+    }
+}
+
+/// What happens to the connection when a hook fails or times out
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HookFailure {
+    /// Give up connecting (and disconnect if already connected)
+    #[default]
+    Abort,
+
+    /// Log the failure and carry on
+    Continue,
+}
+
+/// A command run at one stage of connecting
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Hook {
+    /// Shell command line; local hooks run through `sh -c` (`cmd /C` on
+    /// Windows), remote ones through the login shell on the server
+    pub command: String,
+
+    /// Seconds before the hook is stopped and counted as failed
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_seconds: u64,
+
+    #[serde(default)]
+    pub on_failure: HookFailure,
+}
+
+fn default_hook_timeout() -> u64 {
+    30
+}
+
+impl Hook {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            timeout_seconds: default_hook_timeout(),
+            on_failure: HookFailure::default(),
+        }
+    }
+
+    pub fn with_timeout(mut self, seconds: u64) -> Self {
+        self.timeout_seconds = seconds;
+        self
+    }
+
+    pub fn with_failure(mut self, on_failure: HookFailure) -> Self {
+        self.on_failure = on_failure;
+        self
+    }
+}
+
+/// Hooks of a connection, run in order within each stage
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ConnectionHooks {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_connect: Vec<Hook>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_connect: Vec<Hook>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_tunnel: Vec<Hook>,
+}
+
+impl ConnectionHooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre_connect.is_empty() && self.post_connect.is_empty() && self.post_tunnel.is_empty()
+    }
+
+    /// Hooks of one stage
+    pub fn stage(&self, stage: HookStage) -> &[Hook] {
+        match stage {
+            HookStage::PreConnect => &self.pre_connect,
+            HookStage::PostConnect => &self.post_connect,
+            HookStage::PostTunnel => &self.post_tunnel,
+        }
+    }
+
+    pub fn add(&mut self, stage: HookStage, hook: Hook) {
+        match stage {
+            HookStage::PreConnect => self.pre_connect.push(hook),
+            HookStage::PostConnect => self.post_connect.push(hook),
+            HookStage::PostTunnel => self.post_tunnel.push(hook),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hooks_toml() {
+        let hooks: ConnectionHooks = toml::from_str(
+            r#"
+            [[pre_connect]]
+            command = "vpn-up --wait"
+            timeout_seconds = 60
+
+            [[post_tunnel]]
+            command = "open http://localhost:8080"
+            on_failure = "continue"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(hooks.pre_connect[0].timeout_seconds, 60);
+        assert_eq!(hooks.pre_connect[0].on_failure, HookFailure::Abort);
+        assert!(hooks.stage(HookStage::PostConnect).is_empty());
+        assert_eq!(
+            hooks.stage(HookStage::PostTunnel)[0],
+            Hook::new("open http://localhost:8080").with_failure(HookFailure::Continue)
+        );
+
+        let written = toml::to_string(&hooks).unwrap();
+        assert!(!written.contains("post_connect"));
+        assert_eq!(toml::from_str::<ConnectionHooks>(&written).unwrap(), hooks);
+        assert!(ConnectionHooks::default().is_empty());
+    }
+}
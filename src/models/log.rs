@@ -36,11 +36,14 @@ pub struct ConnectionLog {
     pub metadata: Option<serde_json::Value>,
 }
 
-/// Log level
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+/// Log level, ordered by severity
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LogLevel {
+    #[serde(alias = "info")]
     Info,
+    #[serde(alias = "warning", alias = "warn")]
     Warning,
+    #[serde(alias = "error")]
     Error,
 }
 
@@ -68,6 +71,12 @@ pub enum ConnectionEvent {
     /// Tunnel failed
     TunnelFailed { tunnel_type: String },
 
+    /// Channel opened through a tunnel
+    ChannelOpened { target: String },
+
+    /// Channel through a tunnel closed, with an error message if it failed
+    ChannelClosed { target: String },
+
     /// Session disconnected normally
     Disconnected,
 
@@ -80,11 +89,22 @@ pub enum ConnectionEvent {
     /// SSH command executed
     CommandExecuted { command: String },
 
+    /// Interactive shell opened on a pseudo-terminal of type `term`
+    ShellOpened { term: String },
+
     /// Port forwarding activity
     ForwardingActivity {
         bytes_sent: u64,
         bytes_received: u64,
     },
+
+    /// Connection hook finished; no exit code if it timed out or could not
+    /// be started
+    HookExecuted {
+        stage: String,
+        command: String,
+        exit_code: Option<i32>,
+    },
 }
 
 impl ConnectionLog {
@@ -131,35 +151,7 @@ impl ConnectionLog {
             LogLevel::Error => "ERROR",
         };
 
-        let event_desc = match &self.event {
-            ConnectionEvent::ConnectAttempt => "Connection attempt".to_string(),
-            ConnectionEvent::Connected => "Connected".to_string(),
-            ConnectionEvent::ConnectionFailed => "Connection failed".to_string(),
-            ConnectionEvent::AuthSuccess => "Authentication successful".to_string(),
-            ConnectionEvent::AuthFailed => "Authentication failed".to_string(),
-            ConnectionEvent::TunnelCreated { tunnel_type } => {
-                format!("Tunnel created: {}", tunnel_type)
-            }
-            ConnectionEvent::TunnelFailed { tunnel_type } => {
-                format!("Tunnel failed: {}", tunnel_type)
-            }
-            ConnectionEvent::Disconnected => "Disconnected".to_string(),
-            ConnectionEvent::IdleTimeout => "Idle timeout".to_string(),
-            ConnectionEvent::ErrorDisconnect => "Error disconnect".to_string(),
-            ConnectionEvent::CommandExecuted { command } => {
-                format!("Executed: {}", command)
-            }
-            ConnectionEvent::ForwardingActivity {
-                bytes_sent,
-                bytes_received,
-            } => {
-                format!(
-                    "Traffic: sent {}, received {}",
-                    Self::format_bytes(*bytes_sent),
-                    Self::format_bytes(*bytes_received)
-                )
-            }
-        };
+        let event_desc = self.event.description();
 
         let msg_part = self
             .message
@@ -197,6 +189,107 @@ impl ConnectionLog {
     }
 }
 
+impl ConnectionEvent {
+    /// Names accepted by [`ConnectionEvent::kind`] filters
+    pub const KINDS: [&'static str; 16] = [
+        "connect_attempt",
+        "connected",
+        "connection_failed",
+        "auth_success",
+        "auth_failed",
+        "tunnel_created",
+        "tunnel_failed",
+        "channel_opened",
+        "channel_closed",
+        "disconnected",
+        "idle_timeout",
+        "error_disconnect",
+        "command_executed",
+        "shell_opened",
+        "forwarding_activity",
+        "hook_executed",
+    ];
+
+    /// Short snake_case name of the variant
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::ConnectAttempt => "connect_attempt",
+            Self::Connected => "connected",
+            Self::ConnectionFailed => "connection_failed",
+            Self::AuthSuccess => "auth_success",
+            Self::AuthFailed => "auth_failed",
+            Self::TunnelCreated { .. } => "tunnel_created",
+            Self::TunnelFailed { .. } => "tunnel_failed",
+            Self::ChannelOpened { .. } => "channel_opened",
+            Self::ChannelClosed { .. } => "channel_closed",
+            Self::Disconnected => "disconnected",
+            Self::IdleTimeout => "idle_timeout",
+            Self::ErrorDisconnect => "error_disconnect",
+            Self::CommandExecuted { .. } => "command_executed",
+            Self::ShellOpened { .. } => "shell_opened",
+            Self::ForwardingActivity { .. } => "forwarding_activity",
+            Self::HookExecuted { .. } => "hook_executed",
+        }
+    }
+
+    /// Level this event is logged at
+    pub fn level(&self) -> LogLevel {
+        match self {
+            Self::ConnectionFailed
+            | Self::AuthFailed
+            | Self::TunnelFailed { .. }
+            | Self::ErrorDisconnect => LogLevel::Error,
+            Self::IdleTimeout => LogLevel::Warning,
+            Self::HookExecuted { exit_code, .. } if *exit_code != Some(0) => LogLevel::Warning,
+            _ => LogLevel::Info,
+        }
+    }
+
+    /// Human-readable description
+    pub fn description(&self) -> String {
+        match self {
+            Self::ConnectAttempt => "Connection attempt".to_string(),
+            Self::Connected => "Connected".to_string(),
+            Self::ConnectionFailed => "Connection failed".to_string(),
+            Self::AuthSuccess => "Authentication successful".to_string(),
+            Self::AuthFailed => "Authentication failed".to_string(),
+            Self::TunnelCreated { tunnel_type } => {
+                format!("Tunnel created: {}", tunnel_type)
+            }
+            Self::TunnelFailed { tunnel_type } => {
+                format!("Tunnel failed: {}", tunnel_type)
+            }
+            Self::ChannelOpened { target } => format!("Channel opened to {}", target),
+            Self::ChannelClosed { target } => format!("Channel closed to {}", target),
+            Self::Disconnected => "Disconnected".to_string(),
+            Self::IdleTimeout => "Idle timeout".to_string(),
+            Self::ErrorDisconnect => "Error disconnect".to_string(),
+            Self::CommandExecuted { command } => {
+                format!("Executed: {}", command)
+            }
+            Self::ShellOpened { term } => format!("Opened interactive shell ({})", term),
+            Self::ForwardingActivity {
+                bytes_sent,
+                bytes_received,
+            } => {
+                format!(
+                    "Traffic: sent {}, received {}",
+                    ConnectionLog::format_bytes(*bytes_sent),
+                    ConnectionLog::format_bytes(*bytes_received)
+                )
+            }
+            Self::HookExecuted {
+                stage,
+                command,
+                exit_code,
+            } => match exit_code {
+                Some(code) => format!("{} hook exited with {}: {}", stage, code, command),
+                None => format!("{} hook did not finish: {}", stage, command),
+            },
+        }
+    }
+}
+
 impl LogLevel {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -205,6 +298,16 @@ impl LogLevel {
             Self::Error => "error",
         }
     }
+
+    /// Parse a level name as printed by [`LogLevel::as_str`]
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "info" => Some(Self::Info),
+            "warning" | "warn" => Some(Self::Warning),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -264,6 +367,41 @@ mod tests {
         assert_eq!(log.metadata, Some(metadata));
     }
 
+    #[test]
+    fn test_log_level_parse_and_order() {
+        for level in [LogLevel::Info, LogLevel::Warning, LogLevel::Error] {
+            assert_eq!(LogLevel::parse(level.as_str()), Some(level));
+        }
+        assert_eq!(LogLevel::parse("WARN"), Some(LogLevel::Warning));
+        assert!(LogLevel::parse("debug").is_none());
+        assert!(LogLevel::Info < LogLevel::Warning && LogLevel::Warning < LogLevel::Error);
+    }
+
+    #[test]
+    fn test_event_kinds_are_listed() {
+        let events = [
+            ConnectionEvent::Connected,
+            ConnectionEvent::TunnelFailed {
+                tunnel_type: "local".to_string(),
+            },
+            ConnectionEvent::ForwardingActivity {
+                bytes_sent: 1,
+                bytes_received: 2,
+            },
+            ConnectionEvent::HookExecuted {
+                stage: "pre_connect".to_string(),
+                command: "vpn-up".to_string(),
+                exit_code: None,
+            },
+            ConnectionEvent::ShellOpened {
+                term: "xterm-256color".to_string(),
+            },
+        ];
+        for event in events {
+            assert!(ConnectionEvent::KINDS.contains(&event.kind()));
+        }
+    }
+
     #[test]
     fn test_log_level_as_str() {
         assert_eq!(LogLevel::Info.as_str(), "info");
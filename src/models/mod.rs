@@ -1,14 +1,24 @@
+pub mod audit;
 pub mod auth;
+pub mod authorized_key;
 pub mod connection;
 pub mod forwarding;
+pub mod hook;
 pub mod log;
+pub mod notification;
+pub mod profile;
 pub mod session;
 pub mod template;
 
 // Re-export main types
+pub use audit::AuditEntry;
 pub use auth::AuthMethod;
+pub use authorized_key::AuthorizedKey;
 pub use connection::{JumpHost, SshConnection};
 pub use forwarding::{DynamicForwarding, ForwardingConfig, LocalForwarding, RemoteForwarding};
+pub use hook::{ConnectionHooks, Hook, HookFailure, HookStage};
 pub use log::{ConnectionEvent, ConnectionLog, LogLevel};
-pub use session::{ActiveSession, SessionStatus};
-pub use template::ConnectionTemplate;
+pub use notification::{NotificationSink, NotificationTarget};
+pub use profile::EnvProfile;
+pub use session::{ActiveSession, SessionStatus, TrafficHistory, TrafficRate, TrafficSample};
+pub use template::{ConnectionTemplate, ParameterType, TemplateParameter};
@@ -0,0 +1,167 @@
+use crate::models::{ConnectionLog, LogLevel};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Where a notification is delivered
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationTarget {
+    /// POST the log entry as JSON to an `http://` or `https://` URL
+    Webhook {
+        url: String,
+        /// Extra request headers, e.g. an authorization token
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+
+    /// Run a program with the event in `SSH_TUNNEL_*` environment variables
+    Command {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+/// Notification sink: a target and the events that reach it
+///
+/// Configured as `[[notifications]]` in `settings.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NotificationSink {
+    #[serde(flatten)]
+    pub target: NotificationTarget,
+
+    /// Lowest level that is sent
+    #[serde(default = "default_min_level")]
+    pub min_level: LogLevel,
+
+    /// Event kinds that are sent (see [`crate::models::ConnectionEvent::KINDS`]);
+    /// empty means all
+    #[serde(default)]
+    pub events: Vec<String>,
+
+    /// Seconds before the same event of the same connection is sent again
+    /// (0 = no limit)
+    #[serde(default = "default_rate_limit_seconds")]
+    pub rate_limit_seconds: u64,
+}
+
+fn default_min_level() -> LogLevel {
+    LogLevel::Warning
+}
+
+fn default_rate_limit_seconds() -> u64 {
+    300
+}
+
+impl NotificationSink {
+    pub fn new(target: NotificationTarget) -> Self {
+        Self {
+            target,
+            min_level: default_min_level(),
+            events: Vec::new(),
+            rate_limit_seconds: default_rate_limit_seconds(),
+        }
+    }
+
+    pub fn webhook(url: impl Into<String>) -> Self {
+        Self::new(NotificationTarget::Webhook {
+            url: url.into(),
+            headers: BTreeMap::new(),
+        })
+    }
+
+    pub fn command(command: impl Into<String>, args: Vec<String>) -> Self {
+        Self::new(NotificationTarget::Command {
+            command: command.into(),
+            args,
+        })
+    }
+
+    pub fn with_min_level(mut self, level: LogLevel) -> Self {
+        self.min_level = level;
+        self
+    }
+
+    pub fn with_event(mut self, kind: impl Into<String>) -> Self {
+        self.events.push(kind.into());
+        self
+    }
+
+    pub fn with_rate_limit(mut self, seconds: u64) -> Self {
+        self.rate_limit_seconds = seconds;
+        self
+    }
+
+    /// Whether a log entry passes the level and event filters
+    pub fn accepts(&self, log: &ConnectionLog) -> bool {
+        log.level >= self.min_level
+            && (self.events.is_empty() || self.events.iter().any(|e| e == log.event.kind()))
+    }
+
+    /// Short description for messages, e.g. `webhook http://…`
+    pub fn describe(&self) -> String {
+        match &self.target {
+            NotificationTarget::Webhook { url, .. } => format!("webhook {}", url),
+            NotificationTarget::Command { command, .. } => format!("command {}", command),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionEvent;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_sink_filters() {
+        let sink = NotificationSink::webhook("http://127.0.0.1/hook").with_event("idle_timeout");
+        let log = |level, event| ConnectionLog::new(Uuid::new_v4(), "db", level, event);
+
+        assert!(sink.accepts(&log(LogLevel::Warning, ConnectionEvent::IdleTimeout)));
+        assert!(!sink.accepts(&log(LogLevel::Info, ConnectionEvent::IdleTimeout)));
+        assert!(!sink.accepts(&log(LogLevel::Error, ConnectionEvent::AuthFailed)));
+
+        let all = NotificationSink::webhook("http://127.0.0.1/hook").with_min_level(LogLevel::Info);
+        assert!(all.accepts(&log(LogLevel::Info, ConnectionEvent::Connected)));
+    }
+
+    #[test]
+    fn test_sink_toml() {
+        let settings: BTreeMap<String, Vec<NotificationSink>> = toml::from_str(
+            r#"
+            [[notifications]]
+            type = "webhook"
+            url = "http://hooks.internal/ssh"
+            headers = { Authorization = "Bearer token" }
+            events = ["error_disconnect"]
+
+            [[notifications]]
+            type = "command"
+            command = "notify-send"
+            args = ["SSH tunnel"]
+            min_level = "error"
+            rate_limit_seconds = 0
+            "#,
+        )
+        .unwrap();
+        let sinks = &settings["notifications"];
+
+        assert_eq!(sinks[0].min_level, LogLevel::Warning);
+        assert_eq!(sinks[0].rate_limit_seconds, 300);
+        assert!(matches!(
+            &sinks[0].target,
+            NotificationTarget::Webhook { headers, .. } if headers["Authorization"] == "Bearer token"
+        ));
+        assert_eq!(
+            sinks[1],
+            NotificationSink::command("notify-send", vec!["SSH tunnel".to_string()])
+                .with_min_level(LogLevel::Error)
+                .with_rate_limit(0)
+        );
+
+        let round_trip: NotificationSink =
+            toml::from_str(&toml::to_string(&sinks[0]).unwrap()).unwrap();
+        assert_eq!(round_trip, sinks[0]);
+    }
+}
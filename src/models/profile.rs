@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Named set of values for `${VAR}` placeholders in connections
+///
+/// Switching the active profile re-targets every connection that uses the
+/// placeholders, e.g. `db.${ENV}.example.com` for dev, staging and prod.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct EnvProfile {
+    /// Profile name
+    pub name: String,
+
+    /// Variable values by name
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+}
+
+impl EnvProfile {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            variables: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.variables.insert(name.into(), value.into());
+        self
+    }
+
+    /// Value of a variable from `profile`, falling back to the process
+    /// environment
+    pub fn lookup(profile: Option<&EnvProfile>, name: &str) -> Option<String> {
+        profile
+            .and_then(|p| p.variables.get(name).cloned())
+            .or_else(|| std::env::var(name).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_prefers_profile_over_environment() {
+        let profile = EnvProfile::new("staging").with_var("PATH", "profile");
+
+        assert_eq!(
+            EnvProfile::lookup(Some(&profile), "PATH").as_deref(),
+            Some("profile")
+        );
+        assert_eq!(EnvProfile::lookup(None, "PATH"), std::env::var("PATH").ok());
+        assert!(EnvProfile::lookup(Some(&profile), "SSH_TM_SURELY_UNSET").is_none());
+    }
+
+    #[test]
+    fn test_profile_serialization() {
+        let profile = EnvProfile::new("prod").with_var("ENV", "prod");
+        let toml = toml::to_string(&profile).unwrap();
+        let parsed: EnvProfile = toml::from_str(&toml).unwrap();
+
+        assert_eq!(parsed, profile);
+    }
+}
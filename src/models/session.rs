@@ -1,7 +1,14 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use uuid::Uuid;
 
+/// Samples kept per session or tunnel by default
+pub const TRAFFIC_HISTORY_LEN: usize = 60;
+
+/// Levels of a sparkline, lowest first
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
 /// Active SSH session state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActiveSession {
@@ -33,6 +40,120 @@ pub struct ActiveSession {
     /// Total bytes received (optional)
     #[serde(default)]
     pub bytes_received: u64,
+
+    /// The saved connection was edited or removed since the session started;
+    /// reconnect to apply the new settings
+    #[serde(default)]
+    pub config_changed: bool,
+
+    /// Recent traffic samples
+    #[serde(default)]
+    pub traffic: TrafficHistory,
+}
+
+/// Cumulative traffic at one point in time
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TrafficSample {
+    pub timestamp: DateTime<Utc>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+/// Send and receive throughput in bytes per second
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TrafficRate {
+    pub sent: f64,
+    pub received: f64,
+}
+
+impl TrafficRate {
+    pub fn total(&self) -> f64 {
+        self.sent + self.received
+    }
+}
+
+/// Ring buffer of the most recent traffic samples, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrafficHistory {
+    capacity: usize,
+    samples: VecDeque<TrafficSample>,
+}
+
+impl Default for TrafficHistory {
+    fn default() -> Self {
+        Self::new(TRAFFIC_HISTORY_LEN)
+    }
+}
+
+impl TrafficHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(2),
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Record the totals at `timestamp`, dropping the oldest sample when full
+    pub fn record(&mut self, timestamp: DateTime<Utc>, bytes_sent: u64, bytes_received: u64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(TrafficSample {
+            timestamp,
+            bytes_sent,
+            bytes_received,
+        });
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &TrafficSample> {
+        self.samples.iter()
+    }
+
+    /// Throughput between each pair of consecutive samples
+    ///
+    /// Totals that went down (a tunnel was removed) count as no traffic.
+    pub fn rates(&self) -> Vec<TrafficRate> {
+        self.samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .map(|(before, after)| {
+                let seconds =
+                    (after.timestamp - before.timestamp).num_milliseconds() as f64 / 1000.0;
+                if seconds <= 0.0 {
+                    return TrafficRate::default();
+                }
+                TrafficRate {
+                    sent: after.bytes_sent.saturating_sub(before.bytes_sent) as f64 / seconds,
+                    received: after.bytes_received.saturating_sub(before.bytes_received) as f64
+                        / seconds,
+                }
+            })
+            .collect()
+    }
+
+    /// Throughput over the last sample interval
+    pub fn current_rate(&self) -> TrafficRate {
+        self.rates().last().copied().unwrap_or_default()
+    }
+
+    /// Total throughput of the last `width` intervals as a sparkline,
+    /// scaled to the busiest interval shown
+    pub fn sparkline(&self, width: usize) -> String {
+        let rates = self.rates();
+        let shown = &rates[rates.len().saturating_sub(width)..];
+        let max = shown.iter().map(TrafficRate::total).fold(0.0, f64::max);
+
+        shown
+            .iter()
+            .map(|rate| {
+                if max <= 0.0 {
+                    return SPARK_LEVELS[0];
+                }
+                let level = (rate.total() / max * (SPARK_LEVELS.len() - 1) as f64).round();
+                SPARK_LEVELS[level as usize]
+            })
+            .collect()
+    }
 }
 
 /// Session status
@@ -64,6 +185,8 @@ impl ActiveSession {
             idle_timeout_seconds,
             bytes_sent: 0,
             bytes_received: 0,
+            config_changed: false,
+            traffic: TrafficHistory::default(),
         }
     }
 
@@ -106,25 +229,45 @@ impl ActiveSession {
 
     /// Format traffic for display
     pub fn format_traffic(&self) -> String {
-        fn format_bytes(bytes: u64) -> String {
-            const KB: u64 = 1024;
-            const MB: u64 = KB * 1024;
-            const GB: u64 = MB * 1024;
-
-            if bytes >= GB {
-                format!("{:.2} GB", bytes as f64 / GB as f64)
-            } else if bytes >= MB {
-                format!("{:.2} MB", bytes as f64 / MB as f64)
-            } else if bytes >= KB {
-                format!("{:.2} KB", bytes as f64 / KB as f64)
-            } else {
-                format!("{} B", bytes)
-            }
-        }
-
         let total = self.bytes_sent + self.bytes_received;
         format_bytes(total)
     }
+
+    /// Throughput over the last sample interval
+    pub fn rate(&self) -> TrafficRate {
+        self.traffic.current_rate()
+    }
+
+    /// Format current throughput for display, e.g. `↑ 1.20 KB/s ↓ 300 B/s`
+    pub fn format_rate(&self) -> String {
+        format_rate(self.rate())
+    }
+}
+
+/// Format a byte count for display
+pub fn format_bytes(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+
+    if bytes >= GB {
+        format!("{:.2} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.2} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Format a throughput for display
+pub fn format_rate(rate: TrafficRate) -> String {
+    format!(
+        "↑ {}/s ↓ {}/s",
+        format_bytes(rate.sent.round() as u64),
+        format_bytes(rate.received.round() as u64)
+    )
 }
 
 #[allow(dead_code)]
@@ -257,4 +400,50 @@ mod tests {
         session.idle_timeout_seconds = 86400; // 1 day
         assert!(!session.is_idle());
     }
+
+    #[test]
+    fn test_traffic_history_rates() {
+        let start = Utc::now();
+        let mut history = TrafficHistory::new(3);
+        assert_eq!(history.current_rate(), TrafficRate::default());
+
+        history.record(start, 0, 0);
+        history.record(start + chrono::Duration::seconds(2), 2048, 512);
+        history.record(start + chrono::Duration::seconds(4), 2048, 1024);
+        assert_eq!(
+            history.current_rate(),
+            TrafficRate {
+                sent: 0.0,
+                received: 256.0
+            }
+        );
+
+        // Full: the oldest sample goes, and shrinking totals count as idle
+        history.record(start + chrono::Duration::seconds(6), 1024, 1024);
+        assert_eq!(history.samples().count(), 3);
+        assert_eq!(history.rates().len(), 2);
+        assert_eq!(history.current_rate().total(), 0.0);
+    }
+
+    #[test]
+    fn test_traffic_history_sparkline() {
+        let start = Utc::now();
+        let mut history = TrafficHistory::default();
+        assert_eq!(history.sparkline(10), "");
+
+        for (i, total) in [0, 0, 350, 1050, 1050].into_iter().enumerate() {
+            history.record(start + chrono::Duration::seconds(i as i64), total, 0);
+        }
+        assert_eq!(history.sparkline(10), "▁▅█▁");
+        assert_eq!(history.sparkline(2), "█▁");
+    }
+
+    #[test]
+    fn test_format_rate() {
+        let rate = TrafficRate {
+            sent: 1536.0,
+            received: 300.0,
+        };
+        assert_eq!(format_rate(rate), "↑ 1.50 KB/s ↓ 300 B/s");
+    }
 }
@@ -30,6 +30,73 @@ pub struct ConnectionTemplate {
     /// Preset forwarding configurations
     #[serde(default)]
     pub forwarding_presets: Vec<ForwardingConfig>,
+
+    /// Values asked for when creating a connection from the template
+    #[serde(default)]
+    pub parameters: Vec<TemplateParameter>,
+
+    /// Forwards in `ssh` option syntax with `{parameter}` placeholders,
+    /// e.g. `-L {local_port}:{db_host}:3306`
+    #[serde(default)]
+    pub forwards: Vec<String>,
+}
+
+/// Kind of value a template parameter accepts
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterType {
+    /// Any non-empty text
+    #[default]
+    Text,
+    /// Hostname or IP address
+    Host,
+    /// Port number (1-65535)
+    Port,
+}
+
+/// Template parameter, referenced as `{name}` in forward specs
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TemplateParameter {
+    /// Parameter name
+    pub name: String,
+
+    /// Kind of value, used for validation
+    #[serde(rename = "type", default)]
+    pub kind: ParameterType,
+
+    /// Value used when none is given
+    #[serde(default)]
+    pub default: Option<String>,
+
+    /// Question asked when prompting (defaults to the name)
+    #[serde(default)]
+    pub prompt: Option<String>,
+}
+
+impl TemplateParameter {
+    pub fn new(name: impl Into<String>, kind: ParameterType) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            default: None,
+            prompt: None,
+        }
+    }
+
+    pub fn with_default(mut self, default: impl Into<String>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+
+    /// Text to show when asking for the value
+    pub fn prompt_text(&self) -> &str {
+        self.prompt.as_deref().unwrap_or(&self.name)
+    }
 }
 
 fn default_ssh_port() -> u16 {
@@ -46,6 +113,8 @@ impl ConnectionTemplate {
             default_username: String::new(),
             default_auth_method: AuthMethod::default(),
             forwarding_presets: vec![],
+            parameters: vec![],
+            forwards: vec![],
         }
     }
 
@@ -71,6 +140,16 @@ impl ConnectionTemplate {
         self
     }
 
+    pub fn with_parameter(mut self, parameter: TemplateParameter) -> Self {
+        self.parameters.push(parameter);
+        self
+    }
+
+    pub fn with_forward_spec(mut self, spec: impl Into<String>) -> Self {
+        self.forwards.push(spec.into());
+        self
+    }
+
     /// Built-in MySQL template
     pub fn mysql() -> Self {
         Self::new(
@@ -154,4 +233,23 @@ mod tests {
         let templates = ConnectionTemplate::builtin_templates();
         assert_eq!(templates.len(), 5);
     }
+
+    #[test]
+    fn test_template_parameters_serialization() {
+        let template = ConnectionTemplate::new("DB", "Database via bastion")
+            .with_parameter(
+                TemplateParameter::new("db_host", ParameterType::Host)
+                    .with_default("db.internal")
+                    .with_prompt("Database host"),
+            )
+            .with_forward_spec("-L {local_port}:{db_host}:5432");
+
+        let toml = toml::to_string(&template).unwrap();
+        assert!(toml.contains("type = \"host\""));
+
+        let parsed: ConnectionTemplate = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed.parameters, template.parameters);
+        assert_eq!(parsed.forwards, template.forwards);
+        assert_eq!(parsed.parameters[0].prompt_text(), "Database host");
+    }
 }
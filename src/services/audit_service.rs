@@ -0,0 +1,411 @@
+use crate::models::ConnectionLog;
+use crate::models::audit::{AuditEntry, GENESIS_HASH};
+use crate::services::config_service::{AppSettings, ConfigService};
+use crate::services::event_bus::EventBus;
+use crate::services::log_service::{EventRecorder, EventSink};
+use crate::utils::atomic_file::FileLock;
+use crate::utils::error::Result;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Bytes read from the end of the trail at a time when looking for the
+/// last entry
+const TAIL_CHUNK: u64 = 8 * 1024;
+
+/// Append-only, hash-chained audit trail of who did what over which
+/// connection
+///
+/// Kept apart from the connection log: it is never rotated or pruned, and
+/// every entry is synced to disk before the next one is written. Appends
+/// hold the file's advisory lock and chain to the last entry in the file, so
+/// the GUI and CLI processes running side by side extend the same chain.
+pub struct AuditService {
+    path: PathBuf,
+    user: String,
+    hostname: String,
+}
+
+/// Outcome of [`AuditService::verify`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditReport {
+    /// Entries checked before the first problem, or all of them
+    pub entries: u64,
+
+    /// Hash of the last good entry
+    pub last_hash: Option<String>,
+
+    /// First problem found, if any
+    pub problem: Option<AuditProblem>,
+}
+
+impl AuditReport {
+    pub fn is_intact(&self) -> bool {
+        self.problem.is_none()
+    }
+}
+
+/// Where and how the chain breaks
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditProblem {
+    /// 1-based line in the trail file
+    pub line: usize,
+    pub fault: AuditFault,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuditFault {
+    /// The line is not an audit entry
+    Unreadable,
+    /// Entries are missing or were inserted
+    Sequence { expected: u64, found: u64 },
+    /// `prev_hash` does not match the entry before
+    BrokenLink,
+    /// The entry was changed after it was written
+    Modified,
+}
+
+impl std::fmt::Display for AuditFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unreadable => write!(f, "not a valid audit entry"),
+            Self::Sequence { expected, found } => {
+                write!(f, "expected entry {} but found {}", expected, found)
+            }
+            Self::BrokenLink => write!(f, "does not link to the previous entry"),
+            Self::Modified => write!(f, "contents do not match the entry hash"),
+        }
+    }
+}
+
+impl AuditService {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            user: current_user(),
+            hostname: current_hostname(),
+        }
+    }
+
+    /// Record entries as `user` on `hostname` rather than the current ones
+    pub fn with_identity(mut self, user: impl Into<String>, hostname: impl Into<String>) -> Self {
+        self.user = user.into();
+        self.hostname = hostname.into();
+        self
+    }
+
+    /// The app's audit trail, unless disabled in the settings
+    pub fn for_app(config_service: &ConfigService, settings: &AppSettings) -> Option<Arc<Self>> {
+        settings
+            .audit_enabled
+            .then(|| Arc::new(Self::new(config_service.audit_file_path())))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry for `log` to the trail
+    pub async fn append(&self, log: ConnectionLog) -> Result<AuditEntry> {
+        let path = self.path.clone();
+        let user = self.user.clone();
+        let hostname = self.hostname.clone();
+        tokio::task::spawn_blocking(move || append_locked(&path, &user, &hostname, log))
+            .await
+            .map_err(anyhow::Error::from)?
+    }
+
+    /// Audit every event published on `bus` until the returned recorder is
+    /// finished or dropped
+    pub fn record(self: &Arc<Self>, bus: &EventBus) -> EventRecorder {
+        EventRecorder::start(bus, self.clone())
+    }
+
+    /// Check the hash chain of the trail at `path`, stopping at the first
+    /// problem
+    ///
+    /// A missing file is an empty, intact trail.
+    pub fn verify(path: &Path) -> Result<AuditReport> {
+        let mut report = AuditReport {
+            entries: 0,
+            last_hash: None,
+            problem: None,
+        };
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report),
+            Err(e) => return Err(e.into()),
+        };
+
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fault = match serde_json::from_str::<AuditEntry>(&line) {
+                Err(_) => Some(AuditFault::Unreadable),
+                Ok(entry) if entry.seq != report.entries + 1 => Some(AuditFault::Sequence {
+                    expected: report.entries + 1,
+                    found: entry.seq,
+                }),
+                Ok(entry)
+                    if entry.prev_hash != report.last_hash.as_deref().unwrap_or(GENESIS_HASH) =>
+                {
+                    Some(AuditFault::BrokenLink)
+                }
+                Ok(_) if !AuditEntry::line_is_intact(&line) => Some(AuditFault::Modified),
+                Ok(entry) => {
+                    report.entries = entry.seq;
+                    report.last_hash = Some(entry.hash);
+                    None
+                }
+            };
+            if let Some(fault) = fault {
+                report.problem = Some(AuditProblem {
+                    line: index + 1,
+                    fault,
+                });
+                break;
+            }
+        }
+        Ok(report)
+    }
+}
+
+impl EventSink for AuditService {
+    async fn record_event(&self, log_entry: ConnectionLog) {
+        if let Err(e) = self.append(log_entry).await {
+            tracing::warn!("Failed to write audit entry: {}", e);
+        }
+    }
+
+    async fn flush(&self) {
+        // Every append is synced before it returns
+    }
+}
+
+/// Append an entry chained to the last one in the file, holding the
+/// file's lock so concurrent writers cannot fork the chain
+fn append_locked(
+    path: &Path,
+    user: &str,
+    hostname: &str,
+    log: ConnectionLog,
+) -> Result<AuditEntry> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let _lock = FileLock::exclusive(path)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)?;
+
+    let entry = match last_line(&mut file)? {
+        Some(line) => serde_json::from_str::<AuditEntry>(&line)
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "The last entry of {} is damaged; run `audit verify`",
+                    path.display()
+                )
+            })?
+            .next(user, hostname, log)?,
+        None => AuditEntry::new(1, user, hostname, log, GENESIS_HASH)?,
+    };
+
+    let mut line = entry.to_line()?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    file.sync_data()?;
+    Ok(entry)
+}
+
+/// Last non-empty line of a file, read backwards from the end
+fn last_line(file: &mut File) -> std::io::Result<Option<String>> {
+    let mut start = file.metadata()?.len();
+    let mut tail = Vec::new();
+
+    while start > 0 {
+        let chunk = TAIL_CHUNK.min(start);
+        start -= chunk;
+        file.seek(SeekFrom::Start(start))?;
+        let mut buf = vec![0u8; chunk as usize];
+        file.read_exact(&mut buf)?;
+        buf.extend_from_slice(&tail);
+        tail = buf;
+
+        let content = tail.trim_ascii_end();
+        if let Some(newline) = content.iter().rposition(|&b| b == b'\n') {
+            return Ok(Some(
+                String::from_utf8_lossy(&content[newline + 1..]).to_string(),
+            ));
+        }
+    }
+
+    let content = tail.trim_ascii_end();
+    Ok((!content.is_empty()).then(|| String::from_utf8_lossy(content).to_string()))
+}
+
+/// Login name of the OS user running the app
+fn current_user() -> String {
+    ["USER", "LOGNAME", "USERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Name of the machine the app runs on
+fn current_hostname() -> String {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| {
+            std::process::Command::new("hostname")
+                .output()
+                .ok()
+                .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionEvent, LogLevel, SshConnection};
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn log(event: ConnectionEvent) -> ConnectionLog {
+        ConnectionLog::new(Uuid::new_v4(), "prod-db", LogLevel::Info, event)
+    }
+
+    async fn trail(dir: &TempDir, entries: usize) -> AuditService {
+        let audit =
+            AuditService::new(dir.path().join("audit.jsonl")).with_identity("alice", "laptop");
+        for i in 0..entries {
+            audit
+                .append(log(ConnectionEvent::CommandExecuted {
+                    command: format!("echo {}", i),
+                }))
+                .await
+                .unwrap();
+        }
+        audit
+    }
+
+    fn rewrite(path: &Path, edit: impl FnOnce(&mut Vec<String>)) {
+        let mut lines: Vec<String> = std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        edit(&mut lines);
+        std::fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_audit_trail_chains_across_writers() {
+        let dir = TempDir::new().unwrap();
+        let first = trail(&dir, 2).await;
+
+        // Another process appending later continues the same chain
+        let second = AuditService::new(first.path().to_path_buf()).with_identity("bob", "server");
+        let entry = second
+            .append(log(ConnectionEvent::Disconnected))
+            .await
+            .unwrap();
+        assert_eq!(entry.seq, 3);
+        assert_eq!(entry.user, "bob");
+
+        let report = AuditService::verify(first.path()).unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.entries, 3);
+        assert_eq!(report.last_hash, Some(entry.hash));
+
+        // Concurrent writers take turns on the file lock
+        let appends = (0..10).map(|i| {
+            let writer = if i % 2 == 0 { &first } else { &second };
+            writer.append(log(ConnectionEvent::Connected))
+        });
+        for result in futures::future::join_all(appends).await {
+            result.unwrap();
+        }
+        let report = AuditService::verify(first.path()).unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.entries, 13);
+
+        let missing = AuditService::verify(&dir.path().join("missing.jsonl")).unwrap();
+        assert!(missing.is_intact() && missing.entries == 0);
+    }
+
+    #[tokio::test]
+    async fn test_audit_verify_detects_tampering() {
+        let dir = TempDir::new().unwrap();
+        let path = trail(&dir, 4).await.path().to_path_buf();
+        let fault = |path: &Path| AuditService::verify(path).unwrap().problem.unwrap();
+
+        let original = std::fs::read_to_string(&path).unwrap();
+        rewrite(&path, |lines| {
+            lines[1] = lines[1].replace("echo 1", "echo 9")
+        });
+        assert_eq!(
+            fault(&path),
+            AuditProblem {
+                line: 2,
+                fault: AuditFault::Modified
+            }
+        );
+
+        std::fs::write(&path, &original).unwrap();
+        rewrite(&path, |lines| {
+            lines.remove(2);
+        });
+        assert_eq!(
+            fault(&path).fault,
+            AuditFault::Sequence {
+                expected: 3,
+                found: 4
+            }
+        );
+
+        // Re-sequencing after a removal still breaks the hash links
+        std::fs::write(&path, &original).unwrap();
+        rewrite(&path, |lines| {
+            let mut entry: AuditEntry = serde_json::from_str(&lines[3]).unwrap();
+            entry.seq = 3;
+            entry.hash = entry.compute_hash().unwrap();
+            lines[3] = entry.to_line().unwrap();
+            lines.remove(2);
+        });
+        assert_eq!(fault(&path).fault, AuditFault::BrokenLink);
+
+        std::fs::write(&path, &original).unwrap();
+        rewrite(&path, |lines| lines[0] = "{".to_string());
+        assert_eq!(fault(&path).fault, AuditFault::Unreadable);
+    }
+
+    #[tokio::test]
+    async fn test_audit_records_bus_events() {
+        let dir = TempDir::new().unwrap();
+        let audit = Arc::new(trail(&dir, 0).await);
+        let bus = EventBus::new();
+        let recorder = audit.record(&bus);
+
+        let source = bus.source(&SshConnection::new("prod-db", "bastion", "deploy"));
+        source.publish(ConnectionEvent::ConnectAttempt, None);
+        source.publish(
+            ConnectionEvent::TunnelCreated {
+                tunnel_type: "L 5432 -> db:5432".to_string(),
+            },
+            None,
+        );
+        recorder.finish().await;
+
+        assert_eq!(AuditService::verify(audit.path()).unwrap().entries, 2);
+    }
+}
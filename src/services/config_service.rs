@@ -1,12 +1,64 @@
-use crate::models::{ConnectionTemplate, SshConnection};
+use crate::models::{ConnectionTemplate, EnvProfile, NotificationSink, SshConnection};
+use crate::services::log_service::LogRotation;
+use crate::services::migration_service::{
+    CURRENT_SCHEMA_VERSION, ConfigFileKind, MigrationService,
+};
+use crate::utils::atomic_file::{self, FileLock};
 use crate::utils::error::{Result, SshToolError};
 use directories::ProjectDirs;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Number of rotated `.bak` copies kept for each config file
+const BACKUP_COUNT: usize = 3;
+
+/// Content digest of a config file, `None` if it did not exist
+type FileDigest = Option<[u8; 32]>;
+
+/// How often [`ConfigService::watch`] checks the files for changes
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A config file was changed on disk by something other than this service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigChange {
+    pub kind: ConfigFileKind,
+}
+
+/// Running config file watcher; stops when dropped
+pub struct ConfigWatcher {
+    sender: broadcast::Sender<ConfigChange>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl ConfigWatcher {
+    /// Receive change events from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ConfigChange> {
+        self.sender.subscribe()
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
 
 /// Service for managing configuration persistence
+///
+/// Every read and write holds an advisory lock on the file, writes are atomic
+/// and keep rotating backups, and a write is refused if the file changed on
+/// disk since this service last read it.
 pub struct ConfigService {
     config_dir: PathBuf,
+    /// Digest of each file as this service last read or wrote it
+    digests: Mutex<HashMap<ConfigFileKind, FileDigest>>,
 }
 
 #[allow(dead_code)]
@@ -30,7 +82,10 @@ impl ConfigService {
             }
         }
 
-        Ok(Self { config_dir })
+        Ok(Self {
+            config_dir,
+            digests: Mutex::new(HashMap::new()),
+        })
     }
 
     /// Create a config service with custom directory
@@ -38,7 +93,10 @@ impl ConfigService {
         if !config_dir.exists() {
             fs::create_dir_all(&config_dir)?;
         }
-        Ok(Self { config_dir })
+        Ok(Self {
+            config_dir,
+            digests: Mutex::new(HashMap::new()),
+        })
     }
 
     /// Get default config directory
@@ -48,86 +106,95 @@ impl ConfigService {
             .ok_or_else(|| SshToolError::ConfigError("Failed to get config directory".to_string()))
     }
 
-    /// Get path to connections config file
-    fn connections_file(&self) -> PathBuf {
-        self.config_dir.join("connections.toml")
-    }
-
-    /// Get path to templates config file
-    fn templates_file(&self) -> PathBuf {
-        self.config_dir.join("templates.toml")
-    }
-
-    /// Get path to app settings file
-    fn settings_file(&self) -> PathBuf {
-        self.config_dir.join("settings.toml")
+    /// Get path to a config file
+    fn file_path(&self, kind: ConfigFileKind) -> PathBuf {
+        self.config_dir.join(kind.file_name())
     }
 
     /// Load all connections
     pub fn load_connections(&self) -> Result<Vec<SshConnection>> {
-        let path = self.connections_file();
-
-        if !path.exists() {
+        let Some(connections) =
+            self.read_versioned::<ConnectionsConfig>(ConfigFileKind::Connections)?
+        else {
             tracing::info!("No connections file found, returning empty list");
             return Ok(Vec::new());
-        }
-
-        let content = fs::read_to_string(&path)?;
-        let connections: ConnectionsConfig = toml::from_str(&content).map_err(|e| {
-            SshToolError::ConfigError(format!("Failed to parse connections: {}", e))
-        })?;
+        };
 
         tracing::info!("Loaded {} connections", connections.connections.len());
         Ok(connections.connections)
     }
 
     /// Save all connections
+    ///
+    /// Fails with [`SshToolError::ConfigModified`] if the file was changed by
+    /// another process since this service loaded it; use
+    /// [`update_connections`](Self::update_connections) to edit the current
+    /// contents instead.
     pub fn save_connections(&self, connections: &[SshConnection]) -> Result<()> {
         let config = ConnectionsConfig {
             connections: connections.to_vec(),
         };
 
-        let content = toml::to_string_pretty(&config).map_err(|e| {
-            SshToolError::ConfigError(format!("Failed to serialize connections: {}", e))
-        })?;
-
-        let path = self.connections_file();
-        fs::write(&path, content)?;
+        self.write_versioned(ConfigFileKind::Connections, &config)?;
 
-        tracing::info!("Saved {} connections to {:?}", connections.len(), path);
+        tracing::info!("Saved {} connections", connections.len());
         Ok(())
     }
 
-    /// Save a single connection (update or create)
-    pub fn save_connection(&self, connection: &SshConnection) -> Result<()> {
-        let mut connections = self.load_connections()?;
-
-        // Find and update existing connection, or add new one
-        if let Some(pos) = connections.iter().position(|c| c.id == connection.id) {
-            connections[pos] = connection.clone();
-            tracing::info!("Updated connection: {}", connection.name);
-        } else {
-            connections.push(connection.clone());
-            tracing::info!("Added new connection: {}", connection.name);
+    /// Load, modify and save the connections under a single lock
+    ///
+    /// The closure returns whether the list changed; nothing is written
+    /// otherwise.
+    pub fn update_connections<F>(&self, update: F) -> Result<bool>
+    where
+        F: FnOnce(&mut Vec<SshConnection>) -> bool,
+    {
+        let kind = ConfigFileKind::Connections;
+        let path = self.file_path(kind);
+        let _lock = FileLock::exclusive(&path)?;
+
+        let mut config = self
+            .read_locked::<ConnectionsConfig>(kind, &path)?
+            .unwrap_or(ConnectionsConfig {
+                connections: Vec::new(),
+            });
+        if !update(&mut config.connections) {
+            return Ok(false);
         }
 
-        self.save_connections(&connections)
+        self.write_locked(kind, &path, &config)?;
+        tracing::info!("Saved {} connections", config.connections.len());
+        Ok(true)
+    }
+
+    /// Save a single connection (update or create)
+    pub fn save_connection(&self, connection: &SshConnection) -> Result<()> {
+        self.update_connections(|connections| {
+            // Find and update existing connection, or add new one
+            if let Some(pos) = connections.iter().position(|c| c.id == connection.id) {
+                connections[pos] = connection.clone();
+                tracing::info!("Updated connection: {}", connection.name);
+            } else {
+                connections.push(connection.clone());
+                tracing::info!("Added new connection: {}", connection.name);
+            }
+            true
+        })?;
+        Ok(())
     }
 
     /// Delete a connection by ID
     pub fn delete_connection(&self, id: uuid::Uuid) -> Result<bool> {
-        let mut connections = self.load_connections()?;
-        let original_len = connections.len();
-
-        connections.retain(|c| c.id != id);
+        let deleted = self.update_connections(|connections| {
+            let original_len = connections.len();
+            connections.retain(|c| c.id != id);
+            connections.len() < original_len
+        })?;
 
-        if connections.len() < original_len {
-            self.save_connections(&connections)?;
+        if deleted {
             tracing::info!("Deleted connection with ID: {}", id);
-            Ok(true)
-        } else {
-            Ok(false)
         }
+        Ok(deleted)
     }
 
     /// Get a connection by ID
@@ -138,16 +205,11 @@ impl ConfigService {
 
     /// Load all templates
     pub fn load_templates(&self) -> Result<Vec<ConnectionTemplate>> {
-        let path = self.templates_file();
-
-        if !path.exists() {
+        let Some(templates) = self.read_versioned::<TemplatesConfig>(ConfigFileKind::Templates)?
+        else {
             tracing::info!("No templates file found, returning built-in templates");
             return Ok(ConnectionTemplate::builtin_templates());
-        }
-
-        let content = fs::read_to_string(&path)?;
-        let templates: TemplatesConfig = toml::from_str(&content)
-            .map_err(|e| SshToolError::ConfigError(format!("Failed to parse templates: {}", e)))?;
+        };
 
         tracing::info!("Loaded {} templates", templates.templates.len());
         Ok(templates.templates)
@@ -159,29 +221,18 @@ impl ConfigService {
             templates: templates.to_vec(),
         };
 
-        let content = toml::to_string_pretty(&config).map_err(|e| {
-            SshToolError::ConfigError(format!("Failed to serialize templates: {}", e))
-        })?;
+        self.write_versioned(ConfigFileKind::Templates, &config)?;
 
-        let path = self.templates_file();
-        fs::write(&path, content)?;
-
-        tracing::info!("Saved {} templates to {:?}", templates.len(), path);
+        tracing::info!("Saved {} templates", templates.len());
         Ok(())
     }
 
     /// Load application settings
     pub fn load_settings(&self) -> Result<AppSettings> {
-        let path = self.settings_file();
-
-        if !path.exists() {
+        let Some(settings) = self.read_versioned::<AppSettings>(ConfigFileKind::Settings)? else {
             tracing::info!("No settings file found, using defaults");
             return Ok(AppSettings::default());
-        }
-
-        let content = fs::read_to_string(&path)?;
-        let settings: AppSettings = toml::from_str(&content)
-            .map_err(|e| SshToolError::ConfigError(format!("Failed to parse settings: {}", e)))?;
+        };
 
         tracing::info!("Loaded settings: language={}", settings.language);
         Ok(settings)
@@ -189,21 +240,155 @@ impl ConfigService {
 
     /// Save application settings
     pub fn save_settings(&self, settings: &AppSettings) -> Result<()> {
-        let content = toml::to_string_pretty(settings).map_err(|e| {
-            SshToolError::ConfigError(format!("Failed to serialize settings: {}", e))
-        })?;
-
-        let path = self.settings_file();
-        fs::write(&path, content)?;
+        self.write_versioned(ConfigFileKind::Settings, settings)?;
 
-        tracing::info!("Saved settings to {:?}", path);
+        tracing::info!("Saved settings");
         Ok(())
     }
 
+    /// Watch the config files for changes made outside this service
+    ///
+    /// Files are polled every `interval` and compared by content, so hand
+    /// edits and files replaced by sync tools are both noticed. Writes made
+    /// through this service do not produce events.
+    pub fn watch(self: &Arc<Self>, interval: Duration) -> ConfigWatcher {
+        let (sender, _) = broadcast::channel(16);
+        let service = self.clone();
+        let events = sender.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut seen = HashMap::new();
+            service.poll_changes(&mut seen);
+
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                for change in service.poll_changes(&mut seen) {
+                    tracing::info!("{} changed on disk", change.kind.file_name());
+                    let _ = events.send(change);
+                }
+            }
+        });
+
+        ConfigWatcher { sender, handle }
+    }
+
+    /// Compare the files with the digests seen by the previous poll
+    fn poll_changes(&self, seen: &mut HashMap<ConfigFileKind, FileDigest>) -> Vec<ConfigChange> {
+        let mut changes = Vec::new();
+        for kind in ConfigFileKind::ALL {
+            // Unreadable files are retried on the next poll
+            let Ok(current) = file_digest(&self.file_path(kind)) else {
+                continue;
+            };
+            // The first poll only records the starting state
+            match seen.insert(kind, current) {
+                Some(previous) if previous != current => {}
+                _ => continue,
+            }
+
+            let own = self.digests.lock().ok().and_then(|d| d.get(&kind).copied());
+            if own != Some(current) {
+                changes.push(ConfigChange { kind });
+            }
+        }
+        changes
+    }
+
     /// Get the config directory path
     pub fn config_dir(&self) -> &Path {
         &self.config_dir
     }
+
+    /// Path of the connection log (JSON Lines, rotated alongside)
+    pub fn log_file_path(&self) -> PathBuf {
+        self.config_dir.join("logs").join("connections.jsonl")
+    }
+
+    /// Path of the append-only audit trail
+    pub fn audit_file_path(&self) -> PathBuf {
+        self.config_dir.join("audit").join("audit.jsonl")
+    }
+
+    /// Directory where running processes publish their sessions
+    pub fn sessions_dir(&self) -> PathBuf {
+        self.config_dir.join("sessions")
+    }
+
+    /// Load a config file, migrating it to the current schema first
+    fn read_versioned<T: DeserializeOwned>(&self, kind: ConfigFileKind) -> Result<Option<T>> {
+        let path = self.file_path(kind);
+        let _lock = FileLock::exclusive(&path)?;
+        self.read_locked(kind, &path)
+    }
+
+    /// Write a config file, refusing to overwrite changes made by others
+    fn write_versioned<T: Serialize>(&self, kind: ConfigFileKind, value: &T) -> Result<()> {
+        let path = self.file_path(kind);
+        let _lock = FileLock::exclusive(&path)?;
+
+        let expected = self.digests.lock().ok().and_then(|d| d.get(&kind).copied());
+        if let Some(expected) = expected
+            && file_digest(&path)? != expected
+        {
+            return Err(SshToolError::ConfigModified(kind.file_name().to_string()));
+        }
+
+        self.write_locked(kind, &path, value)
+    }
+
+    /// Read a config file while holding its lock
+    fn read_locked<T: DeserializeOwned>(
+        &self,
+        kind: ConfigFileKind,
+        path: &Path,
+    ) -> Result<Option<T>> {
+        let table = MigrationService::load(kind, path)?;
+        // Taken after loading, since migration may have rewritten the file
+        self.remember(kind, file_digest(path)?);
+
+        let Some(table) = table else {
+            return Ok(None);
+        };
+        toml::Value::Table(table).try_into().map(Some).map_err(|e| {
+            SshToolError::ConfigError(format!("Failed to parse {}: {}", kind.file_name(), e))
+        })
+    }
+
+    /// Write a config file stamped with the current schema version while
+    /// holding its lock
+    fn write_locked<T: Serialize>(
+        &self,
+        kind: ConfigFileKind,
+        path: &Path,
+        value: &T,
+    ) -> Result<()> {
+        let body = toml::to_string_pretty(value).map_err(|e| {
+            SshToolError::ConfigError(format!("Failed to serialize {}: {}", kind.file_name(), e))
+        })?;
+
+        // Written as a header so the version is the first thing in the file
+        let content = format!("schema_version = {}\n\n{}", CURRENT_SCHEMA_VERSION, body);
+        atomic_file::rotate_backups(path, BACKUP_COUNT)?;
+        atomic_file::write_atomic(path, content.as_bytes())?;
+        self.remember(kind, Some(Sha256::digest(content.as_bytes()).into()));
+        Ok(())
+    }
+
+    fn remember(&self, kind: ConfigFileKind, digest: FileDigest) {
+        if let Ok(mut digests) = self.digests.lock() {
+            digests.insert(kind, digest);
+        }
+    }
+}
+
+/// Digest of a file's current contents, `None` if it does not exist
+fn file_digest(path: &Path) -> Result<FileDigest> {
+    match fs::read(path) {
+        Ok(content) => Ok(Some(Sha256::digest(&content).into())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
 }
 
 impl Default for ConfigService {
@@ -243,6 +428,81 @@ pub struct AppSettings {
     /// Default bind address for port forwarding
     #[serde(default = "default_bind_address")]
     pub default_bind_address: String,
+
+    /// Profile whose variables fill `${VAR}` placeholders in connections
+    #[serde(default)]
+    pub active_profile: Option<String>,
+
+    /// Variable profiles (e.g. dev, staging, prod)
+    #[serde(default)]
+    pub profiles: Vec<EnvProfile>,
+
+    /// Rotate the connection log once it reaches this many bytes (0 = never)
+    #[serde(default = "default_log_max_file_bytes")]
+    pub log_max_file_bytes: u64,
+
+    /// Start a new connection log file every day
+    #[serde(default = "default_log_rotate_daily")]
+    pub log_rotate_daily: bool,
+
+    /// Days to keep rotated connection logs (0 = forever)
+    #[serde(default = "default_log_retention_days")]
+    pub log_retention_days: u32,
+
+    /// Serve Prometheus metrics over HTTP
+    #[serde(default)]
+    pub metrics_enabled: bool,
+
+    /// Address the metrics endpoint listens on
+    #[serde(default = "default_bind_address")]
+    pub metrics_bind_address: String,
+
+    /// Port the metrics endpoint listens on
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: u16,
+
+    /// Keep a hash-chained audit trail of sessions, forwards and commands
+    #[serde(default = "default_audit_enabled")]
+    pub audit_enabled: bool,
+
+    /// Webhooks and commands told about session events
+    #[serde(default)]
+    pub notifications: Vec<NotificationSink>,
+}
+
+impl AppSettings {
+    /// The active profile, if one is selected and exists
+    pub fn active_profile(&self) -> Option<&EnvProfile> {
+        let name = self.active_profile.as_deref()?;
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// Profile by name, created empty if it does not exist yet
+    pub fn profile_mut(&mut self, name: &str) -> &mut EnvProfile {
+        let index = match self.profiles.iter().position(|p| p.name == name) {
+            Some(index) => index,
+            None => {
+                self.profiles.push(EnvProfile::new(name));
+                self.profiles.len() - 1
+            }
+        };
+        &mut self.profiles[index]
+    }
+
+    /// Rotation and retention of the connection log
+    pub fn log_rotation(&self) -> LogRotation {
+        LogRotation {
+            max_file_bytes: self.log_max_file_bytes,
+            daily: self.log_rotate_daily,
+            retention_days: self.log_retention_days,
+        }
+    }
+
+    /// Where to serve metrics, if enabled
+    pub fn metrics_address(&self) -> Option<String> {
+        self.metrics_enabled
+            .then(|| format!("{}:{}", self.metrics_bind_address, self.metrics_port))
+    }
 }
 
 #[allow(dead_code)]
@@ -265,6 +525,26 @@ fn default_bind_address() -> String {
     "127.0.0.1".to_string()
 }
 
+fn default_log_max_file_bytes() -> u64 {
+    LogRotation::default().max_file_bytes
+}
+
+fn default_log_rotate_daily() -> bool {
+    LogRotation::default().daily
+}
+
+fn default_log_retention_days() -> u32 {
+    LogRotation::default().retention_days
+}
+
+fn default_metrics_port() -> u16 {
+    9465
+}
+
+fn default_audit_enabled() -> bool {
+    true
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
@@ -272,6 +552,16 @@ impl Default for AppSettings {
             idle_timeout_seconds: default_idle_timeout(),
             check_interval_seconds: default_check_interval(),
             default_bind_address: default_bind_address(),
+            active_profile: None,
+            profiles: Vec::new(),
+            log_max_file_bytes: default_log_max_file_bytes(),
+            log_rotate_daily: default_log_rotate_daily(),
+            log_retention_days: default_log_retention_days(),
+            metrics_enabled: false,
+            metrics_bind_address: default_bind_address(),
+            metrics_port: default_metrics_port(),
+            audit_enabled: default_audit_enabled(),
+            notifications: Vec::new(),
         }
     }
 }
@@ -321,15 +611,22 @@ mod tests {
     fn test_settings() {
         let (service, _temp) = create_test_service();
 
-        let mut settings = AppSettings::default();
-        settings.language = "zh-CN".to_string();
-        settings.idle_timeout_seconds = 600;
+        let settings = AppSettings {
+            language: "zh-CN".to_string(),
+            idle_timeout_seconds: 600,
+            active_profile: Some("staging".to_string()),
+            profiles: vec![EnvProfile::new("staging").with_var("ENV", "staging")],
+            ..Default::default()
+        };
 
         service.save_settings(&settings).unwrap();
         let loaded = service.load_settings().unwrap();
 
         assert_eq!(loaded.language, "zh-CN");
         assert_eq!(loaded.idle_timeout_seconds, 600);
+        assert_eq!(loaded.active_profile(), Some(&settings.profiles[0]));
+        assert_eq!(loaded.log_rotation(), LogRotation::default());
+        assert_eq!(loaded.metrics_address(), None);
     }
 
     #[test]
@@ -342,6 +639,148 @@ mod tests {
         assert!(templates.iter().any(|t| t.name.contains("SOCKS")));
     }
 
+    #[test]
+    fn test_saved_files_carry_schema_version() {
+        let (service, temp) = create_test_service();
+        service
+            .save_connection(&SshConnection::new("Test", "example.com", "user"))
+            .unwrap();
+        service.save_settings(&AppSettings::default()).unwrap();
+
+        for file in ["connections.toml", "settings.toml"] {
+            let content = fs::read_to_string(temp.path().join(file)).unwrap();
+            assert!(content.starts_with(&format!("schema_version = {}", CURRENT_SCHEMA_VERSION)));
+        }
+        assert_eq!(service.load_settings().unwrap().language, "en");
+    }
+
+    #[test]
+    fn test_load_migrates_unversioned_connections() {
+        let (service, temp) = create_test_service();
+        fs::write(
+            temp.path().join("connections.toml"),
+            "[[connections]]\nname = \"Legacy\"\nhost = \"example.com\"\nusername = \"user\"\n",
+        )
+        .unwrap();
+
+        let first = service.load_connections().unwrap();
+        let second = service.load_connections().unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].name, "Legacy");
+        // Ids used to be regenerated on every load; now they are persisted
+        assert_eq!(first[0].id, second[0].id);
+    }
+
+    #[test]
+    fn test_save_refuses_external_changes() {
+        let (service, temp) = create_test_service();
+        service
+            .save_connections(&[SshConnection::new("Mine", "example.com", "user")])
+            .unwrap();
+
+        // Another process edits the file behind this service's back
+        let other = ConfigService::with_dir(temp.path().to_path_buf()).unwrap();
+        other
+            .save_connection(&SshConnection::new("Theirs", "other.com", "user"))
+            .unwrap();
+
+        let mine = vec![SshConnection::new("Mine", "example.com", "user")];
+        let err = service.save_connections(&mine).unwrap_err();
+        assert!(matches!(err, SshToolError::ConfigModified(_)));
+        assert_eq!(service.load_connections().unwrap().len(), 2);
+
+        // Once reloaded the service may write again
+        service.save_connections(&mine).unwrap();
+    }
+
+    #[test]
+    fn test_rotating_backups() {
+        let (service, temp) = create_test_service();
+        for n in 0..5 {
+            service
+                .save_connection(&SshConnection::new(
+                    format!("C{}", n),
+                    "example.com",
+                    "user",
+                ))
+                .unwrap();
+        }
+
+        let path = temp.path().join("connections.toml");
+        for n in 1..=BACKUP_COUNT {
+            assert!(atomic_file::backup_path(&path, n).exists());
+        }
+        assert!(!atomic_file::backup_path(&path, BACKUP_COUNT + 1).exists());
+
+        // The newest backup holds the state before the last save
+        let backup = fs::read_to_string(atomic_file::backup_path(&path, 1)).unwrap();
+        assert!(backup.contains("C3") && !backup.contains("C4"));
+    }
+
+    #[test]
+    fn test_concurrent_updates_are_not_lost() {
+        let temp = TempDir::new().unwrap();
+        let handles: Vec<_> = (0..8)
+            .map(|n| {
+                let dir = temp.path().to_path_buf();
+                std::thread::spawn(move || {
+                    let service = ConfigService::with_dir(dir).unwrap();
+                    service
+                        .save_connection(&SshConnection::new(format!("C{}", n), "h", "u"))
+                        .unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let service = ConfigService::with_dir(temp.path().to_path_buf()).unwrap();
+        assert_eq!(service.load_connections().unwrap().len(), 8);
+    }
+
+    #[test]
+    fn test_poll_ignores_own_writes() {
+        let (service, temp) = create_test_service();
+        let mut seen = HashMap::new();
+        assert!(service.poll_changes(&mut seen).is_empty());
+
+        service
+            .save_connection(&SshConnection::new("Mine", "example.com", "user"))
+            .unwrap();
+        assert!(service.poll_changes(&mut seen).is_empty());
+
+        let other = ConfigService::with_dir(temp.path().to_path_buf()).unwrap();
+        other.save_settings(&AppSettings::default()).unwrap();
+        assert_eq!(
+            service.poll_changes(&mut seen),
+            vec![ConfigChange {
+                kind: ConfigFileKind::Settings
+            }]
+        );
+        assert!(service.poll_changes(&mut seen).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_watch_emits_external_changes() {
+        let temp = TempDir::new().unwrap();
+        let service = Arc::new(ConfigService::with_dir(temp.path().to_path_buf()).unwrap());
+        let watcher = service.watch(Duration::from_millis(20));
+        let mut events = watcher.subscribe();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let other = ConfigService::with_dir(temp.path().to_path_buf()).unwrap();
+        other
+            .save_connection(&SshConnection::new("Theirs", "other.com", "user"))
+            .unwrap();
+
+        let change = tokio::time::timeout(Duration::from_secs(2), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.kind, ConfigFileKind::Connections);
+    }
+
     #[test]
     fn test_load_connections_empty() {
         let (service, _temp) = create_test_service();
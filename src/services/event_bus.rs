@@ -0,0 +1,175 @@
+use crate::models::{ConnectionEvent, ConnectionLog, SshConnection};
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Events buffered for subscribers that fall behind
+const EVENT_CAPACITY: usize = 256;
+
+/// Session or tunnel lifecycle event
+#[derive(Debug, Clone)]
+pub struct SessionEvent {
+    /// Session, once one exists
+    pub session_id: Option<Uuid>,
+    pub connection_id: Uuid,
+    pub connection_name: String,
+    pub event: ConnectionEvent,
+    pub message: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl SessionEvent {
+    /// Log entry recording this event
+    pub fn to_log(&self) -> ConnectionLog {
+        let mut log = ConnectionLog::new(
+            self.connection_id,
+            self.connection_name.clone(),
+            self.event.level(),
+            self.event.clone(),
+        );
+        log.session_id = self.session_id;
+        log.message = self.message.clone();
+        log.timestamp = self.timestamp;
+        log
+    }
+}
+
+/// Broadcast bus for [`SessionEvent`]s
+///
+/// Publishing never waits for subscribers; one that falls more than
+/// `EVENT_CAPACITY` events behind misses the oldest ones.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<SessionEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+        Self { sender }
+    }
+
+    /// Receive every event published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<SessionEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, event: SessionEvent) {
+        // Nobody listening is fine
+        let _ = self.sender.send(event);
+    }
+
+    /// Source publishing on behalf of a connection
+    pub fn source(&self, connection: &SshConnection) -> EventSource {
+        EventSource {
+            bus: self.clone(),
+            connection_id: connection.id,
+            connection_name: connection.name.clone(),
+            session_id: None,
+        }
+    }
+}
+
+/// Publishes events for one connection and, once connected, its session
+#[derive(Clone)]
+pub struct EventSource {
+    bus: EventBus,
+    connection_id: Uuid,
+    connection_name: String,
+    session_id: Option<Uuid>,
+}
+
+impl EventSource {
+    /// The same source, tagging events with a session
+    pub fn for_session(&self, session_id: Uuid) -> Self {
+        Self {
+            session_id: Some(session_id),
+            ..self.clone()
+        }
+    }
+
+    pub fn publish(&self, event: ConnectionEvent, message: Option<String>) {
+        self.bus.publish(SessionEvent {
+            session_id: self.session_id,
+            connection_id: self.connection_id,
+            connection_name: self.connection_name.clone(),
+            event,
+            message,
+            timestamp: Utc::now(),
+        });
+    }
+}
+
+/// Event source of an SSH session, shared with its handler and tunnels
+///
+/// Empty until the session manager takes over the session; publishing to an
+/// empty slot does nothing.
+#[derive(Clone, Default)]
+pub struct EventSlot(Arc<RwLock<Option<EventSource>>>);
+
+impl EventSlot {
+    pub fn set(&self, source: EventSource) {
+        if let Ok(mut slot) = self.0.write() {
+            *slot = Some(source);
+        }
+    }
+
+    pub fn publish(&self, event: ConnectionEvent, message: Option<String>) {
+        if let Ok(slot) = self.0.read()
+            && let Some(source) = slot.as_ref()
+        {
+            source.publish(event, message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::LogLevel;
+
+    #[tokio::test]
+    async fn test_source_publishes_to_subscribers() {
+        let bus = EventBus::new();
+        let mut events = bus.subscribe();
+        let connection = SshConnection::new("db", "bastion", "deploy");
+        let session_id = Uuid::new_v4();
+
+        let source = bus.source(&connection);
+        source.publish(ConnectionEvent::ConnectAttempt, None);
+        source
+            .for_session(session_id)
+            .publish(ConnectionEvent::IdleTimeout, Some("idle".to_string()));
+
+        let first = events.recv().await.unwrap();
+        assert_eq!(first.event, ConnectionEvent::ConnectAttempt);
+        assert!(first.session_id.is_none());
+
+        let log = events.recv().await.unwrap().to_log();
+        assert_eq!(log.session_id, Some(session_id));
+        assert_eq!(log.connection_id, connection.id);
+        assert_eq!(log.level, LogLevel::Warning);
+        assert_eq!(log.message.as_deref(), Some("idle"));
+    }
+
+    #[tokio::test]
+    async fn test_empty_slot_publishes_nothing() {
+        let bus = EventBus::new();
+        let mut events = bus.subscribe();
+        let slot = EventSlot::default();
+
+        slot.publish(ConnectionEvent::Connected, None);
+        assert!(events.try_recv().is_err());
+
+        slot.set(bus.source(&SshConnection::new("db", "bastion", "deploy")));
+        slot.publish(ConnectionEvent::Connected, None);
+        assert_eq!(events.try_recv().unwrap().event, ConnectionEvent::Connected);
+    }
+}
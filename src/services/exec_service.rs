@@ -0,0 +1,171 @@
+use crate::models::SshConnection;
+use crate::services::session_manager::SessionManager;
+use crate::services::sftp_service::SftpService;
+use crate::services::ssh_service::{OutputStream, SshService, SshSession};
+use crate::utils::error::Result;
+use russh::Channel;
+use russh::client::Msg;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Session a remote command or shell runs on
+pub struct ExecSession {
+    session: Arc<Mutex<SshSession>>,
+    /// Opened just for the command, and closed after it
+    temporary: bool,
+}
+
+impl ExecSession {
+    /// Reuse a running session of `connection`, or open a temporary one
+    ///
+    /// A temporary session runs the connection's pre-connect hooks but
+    /// starts no forwards. Its commands are published on the events of
+    /// `session_manager` all the same.
+    pub async fn open(
+        session_manager: &SessionManager,
+        connection: &SshConnection,
+        password: Option<&str>,
+    ) -> Result<Self> {
+        let running = session_manager
+            .list_sessions()
+            .await
+            .into_iter()
+            .find(|s| s.connection_id == connection.id);
+        if let Some(running) = running {
+            tracing::info!("Using running session {}", running.id);
+            return Ok(Self {
+                session: session_manager.get_ssh_session(running.id).await?,
+                temporary: false,
+            });
+        }
+
+        let connection = session_manager.resolve(connection).await?;
+        session_manager.run_pre_connect_hooks(&connection).await?;
+        let session = SshService::connect(&connection, password).await?;
+        session
+            .events()
+            .set(session_manager.events().source(&connection));
+        Ok(Self {
+            session: Arc::new(Mutex::new(session)),
+            temporary: true,
+        })
+    }
+
+    /// Whether the session was opened for the command
+    pub fn is_temporary(&self) -> bool {
+        self.temporary
+    }
+
+    /// Run `command`, passing its output to `on_output` as it arrives, and
+    /// return its exit status
+    ///
+    /// The session is only locked while the command starts, so a session
+    /// shared with running tunnels keeps serving them.
+    pub async fn run<F>(&self, command: &str, mut on_output: F) -> Result<Option<u32>>
+    where
+        F: FnMut(OutputStream, &[u8]),
+    {
+        let mut remote = {
+            let session = self.session.lock().await;
+            SshService::start_command(&session, command).await?
+        };
+        while let Some((stream, data)) = remote.next().await {
+            on_output(stream, &data);
+        }
+        Ok(remote.exit_status())
+    }
+
+    /// Start an interactive shell on a pseudo-terminal of type `term` and
+    /// size `cols` x `rows`
+    pub async fn shell(&self, term: &str, cols: u32, rows: u32) -> Result<Channel<Msg>> {
+        let session = self.session.lock().await;
+        SshService::open_shell(&session, term, cols, rows).await
+    }
+
+    /// Start an SFTP subsystem for file transfer
+    pub async fn sftp(&self) -> Result<SftpService> {
+        let session = self.session.lock().await;
+        SftpService::open(&session).await
+    }
+
+    /// Disconnect the session if it was opened for the command
+    pub async fn close(self) -> Result<()> {
+        if self.temporary {
+            SshService::disconnect(&mut *self.session.lock().await).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits the output of a command into whole lines, so the output of
+/// several commands can be interleaved line by line
+#[derive(Debug, Default)]
+pub struct LineSplitter {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+impl LineSplitter {
+    /// Lines completed by `data`, without their line endings; the rest is
+    /// kept until the next call
+    pub fn push(&mut self, stream: OutputStream, data: &[u8]) -> Vec<String> {
+        let buffer = self.buffer(stream);
+        buffer.extend_from_slice(data);
+
+        let Some(end) = buffer.iter().rposition(|&b| b == b'\n') else {
+            return Vec::new();
+        };
+        let rest = buffer.split_off(end + 1);
+        let complete = std::mem::replace(buffer, rest);
+        String::from_utf8_lossy(&complete)
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Unfinished last line of `stream`, once the command has finished
+    pub fn finish(&mut self, stream: OutputStream) -> Option<String> {
+        let rest = std::mem::take(self.buffer(stream));
+        (!rest.is_empty()).then(|| String::from_utf8_lossy(&rest).into_owned())
+    }
+
+    fn buffer(&mut self, stream: OutputStream) -> &mut Vec<u8> {
+        match stream {
+            OutputStream::Stdout => &mut self.stdout,
+            OutputStream::Stderr => &mut self.stderr,
+        }
+    }
+}
+
+/// Process exit code for the exit status of a remote command, 255 if the
+/// server did not report one (as `ssh` does)
+pub fn exit_code(status: Option<u32>) -> i32 {
+    status.map_or(255, |status| status.min(255) as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_splitter() {
+        let mut lines = LineSplitter::default();
+        assert!(lines.push(OutputStream::Stdout, b"par").is_empty());
+        assert_eq!(
+            lines.push(OutputStream::Stdout, b"tial\r\nnext\nla"),
+            vec!["partial", "next"]
+        );
+        assert_eq!(lines.push(OutputStream::Stderr, b"oops\n"), vec!["oops"]);
+        assert_eq!(lines.finish(OutputStream::Stdout), Some("la".to_string()));
+        assert_eq!(lines.finish(OutputStream::Stdout), None);
+        assert_eq!(lines.finish(OutputStream::Stderr), None);
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(Some(0)), 0);
+        assert_eq!(exit_code(Some(3)), 3);
+        assert_eq!(exit_code(Some(300)), 255);
+        assert_eq!(exit_code(None), 255);
+    }
+}
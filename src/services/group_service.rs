@@ -0,0 +1,215 @@
+use crate::models::SshConnection;
+use crate::services::session_manager::SessionManager;
+use crate::utils::error::Result;
+use futures::future::join_all;
+use std::collections::BTreeSet;
+
+/// Service for working with connection groups and tags
+///
+/// Groups are `/`-separated paths stored on each connection; a group
+/// includes the connections of all of its subgroups.
+pub struct GroupService;
+
+impl GroupService {
+    /// Every group path in use, including parent groups, sorted
+    pub fn groups(connections: &[SshConnection]) -> Vec<String> {
+        let mut groups = BTreeSet::new();
+        for group in connections.iter().filter_map(|c| c.group.as_deref()) {
+            let mut path = String::new();
+            for level in group.split('/').filter(|l| !l.is_empty()) {
+                if !path.is_empty() {
+                    path.push('/');
+                }
+                path.push_str(level);
+                groups.insert(path.clone());
+            }
+        }
+        groups.into_iter().collect()
+    }
+
+    /// Every tag in use, sorted and de-duplicated case-insensitively
+    pub fn tags(connections: &[SshConnection]) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in connections.iter().flat_map(|c| &c.tags) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.clone());
+            }
+        }
+        tags.sort_by_key(|t| t.to_lowercase());
+        tags
+    }
+
+    /// Connections in `group` or one of its subgroups
+    pub fn members(connections: &[SshConnection], group: &str) -> Vec<SshConnection> {
+        connections
+            .iter()
+            .filter(|c| c.in_group(group))
+            .cloned()
+            .collect()
+    }
+
+    /// Connections matching a search text and, if given, a tag
+    ///
+    /// The text is matched case-insensitively against name, host, username,
+    /// group and tags.
+    pub fn filter(
+        connections: &[SshConnection],
+        text: &str,
+        tag: Option<&str>,
+    ) -> Vec<SshConnection> {
+        let text = text.to_lowercase();
+        connections
+            .iter()
+            .filter(|c| tag.is_none_or(|tag| c.has_tag(tag)))
+            .filter(|c| {
+                text.is_empty()
+                    || c.name.to_lowercase().contains(&text)
+                    || c.host.to_lowercase().contains(&text)
+                    || c.username.to_lowercase().contains(&text)
+                    || c.group
+                        .as_deref()
+                        .is_some_and(|g| g.to_lowercase().contains(&text))
+                    || c.tags.iter().any(|t| t.to_lowercase().contains(&text))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Connect several connections concurrently, each with its password
+    ///
+    /// `on_done` is called with the index of each target as soon as it
+    /// finishes; results are returned in the order of `targets`.
+    pub async fn connect_all<F>(
+        session_manager: &SessionManager,
+        targets: &[(SshConnection, Option<String>)],
+        on_done: F,
+    ) -> Vec<Result<uuid::Uuid>>
+    where
+        F: Fn(usize, &Result<uuid::Uuid>),
+    {
+        let on_done = &on_done;
+        join_all(
+            targets
+                .iter()
+                .enumerate()
+                .map(|(index, (connection, password))| async move {
+                    let result = session_manager
+                        .connect(connection, password.as_deref())
+                        .await;
+                    on_done(index, &result);
+                    result
+                }),
+        )
+        .await
+    }
+
+    /// Disconnect every session of the given connections concurrently,
+    /// returning how many sessions were closed
+    pub async fn disconnect_all(
+        session_manager: &SessionManager,
+        connections: &[SshConnection],
+    ) -> usize {
+        let session_ids: Vec<uuid::Uuid> = session_manager
+            .list_sessions()
+            .await
+            .into_iter()
+            .filter(|s| connections.iter().any(|c| c.id == s.connection_id))
+            .map(|s| s.id)
+            .collect();
+
+        let results = join_all(
+            session_ids
+                .iter()
+                .map(|id| session_manager.disconnect_session(*id)),
+        )
+        .await;
+
+        for error in results.iter().filter_map(|r| r.as_ref().err()) {
+            tracing::warn!("Failed to disconnect session: {}", error);
+        }
+        results.iter().filter(|r| r.is_ok()).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn connections() -> Vec<SshConnection> {
+        vec![
+            SshConnection::new("api", "api.staging", "deploy")
+                .with_group("staging/eu")
+                .with_tag("web"),
+            SshConnection::new("db", "db.staging", "deploy")
+                .with_group("staging")
+                .with_tag("DB"),
+            SshConnection::new("prod-db", "db.prod", "admin")
+                .with_group("prod")
+                .with_tag("db"),
+            SshConnection::new("laptop", "localhost", "me"),
+        ]
+    }
+
+    #[test]
+    fn test_groups_include_parents() {
+        assert_eq!(
+            GroupService::groups(&connections()),
+            vec!["prod", "staging", "staging/eu"]
+        );
+    }
+
+    #[test]
+    fn test_tags_deduplicated() {
+        assert_eq!(GroupService::tags(&connections()), vec!["DB", "web"]);
+    }
+
+    #[test]
+    fn test_members_include_subgroups() {
+        let names: Vec<_> = GroupService::members(&connections(), "staging")
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, vec!["api", "db"]);
+        assert_eq!(GroupService::members(&connections(), "staging/eu").len(), 1);
+    }
+
+    #[test]
+    fn test_filter_by_text_and_tag() {
+        let all = connections();
+        assert_eq!(GroupService::filter(&all, "", None).len(), 4);
+        assert_eq!(GroupService::filter(&all, "", Some("db")).len(), 2);
+        assert_eq!(GroupService::filter(&all, "prod", Some("db")).len(), 1);
+        // Groups and tags are searchable too
+        assert_eq!(GroupService::filter(&all, "eu", None).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_connect_all_reports_each_result() {
+        let manager = SessionManager::new(60);
+        // Nothing listens on port 1, so both attempts fail fast
+        let targets: Vec<_> = ["a", "b"]
+            .iter()
+            .map(|name| {
+                (
+                    SshConnection::new(*name, "127.0.0.1", "user").with_port(1),
+                    None,
+                )
+            })
+            .collect();
+
+        let done = Mutex::new(Vec::new());
+        let results = GroupService::connect_all(&manager, &targets, |index, result| {
+            assert!(result.is_err());
+            done.lock().unwrap().push(index);
+        })
+        .await;
+
+        assert_eq!(results.len(), 2);
+        let mut done = done.into_inner().unwrap();
+        done.sort_unstable();
+        assert_eq!(done, vec![0, 1]);
+        assert_eq!(manager.session_count().await, 0);
+        assert_eq!(GroupService::disconnect_all(&manager, &[]).await, 0);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::models::{
+    ConnectionEvent, ForwardingConfig, Hook, HookFailure, HookStage, SshConnection,
+};
+use crate::services::event_bus::EventSource;
+use crate::services::ssh_service::{CommandOutput, OutputStream, SshService, SshSession};
+use crate::utils::error::{Result, SshToolError};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Characters of hook output kept in the log
+const MAX_LOGGED_OUTPUT: usize = 4096;
+
+/// How a hook ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookOutcome {
+    /// Exit code, unless the hook timed out or could not be started
+    pub exit_code: Option<i32>,
+
+    /// Standard output followed by standard error, or why the hook did not
+    /// finish
+    pub output: String,
+}
+
+impl HookOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    fn not_finished(reason: impl Into<String>) -> Self {
+        Self {
+            exit_code: None,
+            output: reason.into(),
+        }
+    }
+
+    fn finished(exit_code: Option<i32>, stdout: &str, stderr: &str) -> Self {
+        let output = [stdout.trim_end(), stderr.trim_end()]
+            .iter()
+            .filter(|s| !s.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join("\n");
+        Self { exit_code, output }
+    }
+}
+
+/// Runs the hooks of a connection
+pub struct HookService;
+
+impl HookService {
+    /// Run the hooks of `stage` in order, publishing each outcome as a
+    /// [`ConnectionEvent::HookExecuted`] event
+    ///
+    /// `session` is where remote hooks run. A failing hook set to abort
+    /// stops the stage with [`SshToolError::HookFailed`].
+    pub async fn run_stage(
+        connection: &SshConnection,
+        stage: HookStage,
+        session: Option<&Arc<Mutex<SshSession>>>,
+        events: &EventSource,
+    ) -> Result<()> {
+        for hook in connection.hooks.stage(stage) {
+            tracing::info!(
+                "Running {} hook for {}: {}",
+                stage.as_str(),
+                connection.name,
+                hook.command
+            );
+            let outcome = match (stage.is_remote(), session) {
+                (true, Some(session)) => Self::run_remote(hook, session).await,
+                (true, None) => HookOutcome::not_finished("no session to run it on"),
+                (false, _) => Self::run_local(hook, &hook_env(connection, stage)).await,
+            };
+
+            let logged = truncate(&outcome.output);
+            if outcome.succeeded() {
+                tracing::info!("{} hook succeeded: {}", stage.as_str(), logged);
+            } else {
+                tracing::warn!(
+                    "{} hook failed ({:?}): {}",
+                    stage.as_str(),
+                    outcome.exit_code,
+                    logged
+                );
+            }
+            events.publish(
+                ConnectionEvent::HookExecuted {
+                    stage: stage.as_str().to_string(),
+                    command: hook.command.clone(),
+                    exit_code: outcome.exit_code,
+                },
+                (!logged.is_empty()).then_some(logged),
+            );
+
+            if !outcome.succeeded() && hook.on_failure == HookFailure::Abort {
+                let reason = match outcome.exit_code {
+                    Some(code) => format!("exited with {}", code),
+                    None => outcome.output,
+                };
+                return Err(SshToolError::HookFailed(format!(
+                    "{} hook `{}` {}",
+                    stage.as_str(),
+                    hook.command,
+                    reason
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a hook on this machine through the shell
+    pub async fn run_local(hook: &Hook, env: &[(&str, String)]) -> HookOutcome {
+        let mut command = if cfg!(windows) {
+            let mut command = tokio::process::Command::new("cmd");
+            command.arg("/C");
+            command
+        } else {
+            let mut command = tokio::process::Command::new("sh");
+            command.arg("-c");
+            command
+        };
+        command
+            .arg(&hook.command)
+            .envs(env.iter().map(|(name, value)| (*name, value)))
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true);
+
+        let timeout = Duration::from_secs(hook.timeout_seconds);
+        match tokio::time::timeout(timeout, command.output()).await {
+            Ok(Ok(output)) => HookOutcome::finished(
+                output.status.code(),
+                &String::from_utf8_lossy(&output.stdout),
+                &String::from_utf8_lossy(&output.stderr),
+            ),
+            Ok(Err(e)) => HookOutcome::not_finished(format!("could not be started: {}", e)),
+            Err(_) => {
+                HookOutcome::not_finished(format!("timed out after {}s", hook.timeout_seconds))
+            }
+        }
+    }
+
+    /// Run a hook on the server of `session`
+    ///
+    /// The session is only locked while the command starts, so a slow hook
+    /// does not hold up others using it.
+    pub async fn run_remote(hook: &Hook, session: &Arc<Mutex<SshSession>>) -> HookOutcome {
+        let timeout = Duration::from_secs(hook.timeout_seconds);
+        let run = async {
+            let mut remote = {
+                let session = session.lock().await;
+                SshService::start_command(&session, &hook.command).await?
+            };
+            let mut output = CommandOutput::default();
+            while let Some((stream, data)) = remote.next().await {
+                let text = String::from_utf8_lossy(&data);
+                match stream {
+                    OutputStream::Stdout => output.stdout.push_str(&text),
+                    OutputStream::Stderr => output.stderr.push_str(&text),
+                }
+            }
+            output.exit_status = remote.exit_status();
+            Ok::<_, SshToolError>(output)
+        };
+        match tokio::time::timeout(timeout, run).await {
+            Ok(Ok(output)) => HookOutcome::finished(
+                output.exit_status.map(|status| status as i32),
+                &output.stdout,
+                &output.stderr,
+            ),
+            Ok(Err(e)) => HookOutcome::not_finished(format!("could not be started: {}", e)),
+            Err(_) => {
+                HookOutcome::not_finished(format!("timed out after {}s", hook.timeout_seconds))
+            }
+        }
+    }
+}
+
+/// Environment describing the connection to a local hook
+fn hook_env(connection: &SshConnection, stage: HookStage) -> Vec<(&'static str, String)> {
+    let local_ports: Vec<String> = connection
+        .forwarding_configs
+        .iter()
+        .filter_map(|config| match config {
+            ForwardingConfig::Local(local) => Some(local.local_port.to_string()),
+            ForwardingConfig::Dynamic(dynamic) => Some(dynamic.local_port.to_string()),
+            ForwardingConfig::Remote(_) => None,
+        })
+        .collect();
+
+    vec![
+        ("SSH_TUNNEL_HOOK", stage.as_str().to_string()),
+        ("SSH_TUNNEL_CONNECTION", connection.name.clone()),
+        ("SSH_TUNNEL_HOST", connection.host.clone()),
+        ("SSH_TUNNEL_PORT", connection.port.to_string()),
+        ("SSH_TUNNEL_USER", connection.username.clone()),
+        (
+            "SSH_TUNNEL_LOCAL_PORT",
+            local_ports.first().cloned().unwrap_or_default(),
+        ),
+        ("SSH_TUNNEL_LOCAL_PORTS", local_ports.join(",")),
+    ]
+}
+
+fn truncate(output: &str) -> String {
+    match output.char_indices().nth(MAX_LOGGED_OUTPUT) {
+        Some((end, _)) => format!("{}...", &output[..end]),
+        None => output.to_string(),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::models::LocalForwarding;
+    use crate::services::event_bus::EventBus;
+
+    fn connection() -> SshConnection {
+        SshConnection::new("web", "bastion", "deploy").with_forwarding(ForwardingConfig::Local(
+            LocalForwarding::new(8080, "app.internal", 80),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_local_hook_output_and_env() {
+        let env = hook_env(&connection(), HookStage::PostTunnel);
+        let hook = Hook::new(
+            "echo \"$SSH_TUNNEL_CONNECTION:$SSH_TUNNEL_LOCAL_PORT\"; echo oops >&2; exit 4",
+        );
+
+        let outcome = HookService::run_local(&hook, &env).await;
+        assert_eq!(outcome.exit_code, Some(4));
+        assert_eq!(outcome.output, "web:8080\noops");
+
+        let outcome = HookService::run_local(&Hook::new("sleep 5").with_timeout(0), &env).await;
+        assert_eq!(outcome.exit_code, None);
+        assert!(outcome.output.contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_stage_failure_policy() {
+        let bus = EventBus::new();
+        let mut events = bus.subscribe();
+        let connection = connection()
+            .with_hook(
+                HookStage::PreConnect,
+                Hook::new("exit 1").with_failure(HookFailure::Continue),
+            )
+            .with_hook(HookStage::PreConnect, Hook::new("true"))
+            .with_hook(HookStage::PreConnect, Hook::new("false"))
+            .with_hook(HookStage::PreConnect, Hook::new("echo never"));
+        let source = bus.source(&connection);
+
+        let result =
+            HookService::run_stage(&connection, HookStage::PreConnect, None, &source).await;
+        assert!(
+            matches!(result, Err(SshToolError::HookFailed(ref m)) if m.contains("`false` exited with 1"))
+        );
+
+        let mut codes = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let ConnectionEvent::HookExecuted { exit_code, .. } = event.event {
+                codes.push(exit_code);
+            }
+        }
+        assert_eq!(codes, vec![Some(1), Some(0), Some(1)]);
+
+        // Remote hooks cannot run without a session
+        let remote = SshConnection::new("web", "bastion", "deploy")
+            .with_hook(HookStage::PostConnect, Hook::new("uptime"));
+        assert!(
+            HookService::run_stage(&remote, HookStage::PostConnect, None, &source)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        assert_eq!(truncate("short"), "short");
+        let long = "é".repeat(MAX_LOGGED_OUTPUT + 10);
+        assert_eq!(truncate(&long).chars().count(), MAX_LOGGED_OUTPUT + 3);
+    }
+}
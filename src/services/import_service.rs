@@ -0,0 +1,245 @@
+use crate::models::{AuthMethod, SshConnection};
+use crate::services::config_service::ConfigService;
+use crate::utils::error::Result;
+use crate::utils::ssh_config_parser::{SshConfigImport, SshConfigParser};
+use std::path::Path;
+
+/// An imported connection that matches one already saved
+#[derive(Debug, Clone)]
+pub struct DuplicateConnection {
+    /// The connection as read from the import source
+    pub imported: SshConnection,
+    /// Name of the saved connection with the same host, user and port
+    pub existing_name: String,
+    /// Human-readable list of fields that differ from the saved connection
+    pub differences: Vec<String>,
+}
+
+/// Dry-run result of an import: what would be added and what is already known
+#[derive(Debug, Clone, Default)]
+pub struct ImportPlan {
+    /// Connections not present in the saved configuration
+    pub to_add: Vec<SshConnection>,
+    /// Connections whose host/user/port already exist (left untouched)
+    pub duplicates: Vec<DuplicateConnection>,
+    /// Problems reported while reading the source
+    pub warnings: Vec<String>,
+}
+
+impl ImportPlan {
+    pub fn is_empty(&self) -> bool {
+        self.to_add.is_empty()
+    }
+}
+
+/// Service for importing connections from external sources
+pub struct ImportService;
+
+impl ImportService {
+    /// Read an OpenSSH config file and diff it against the saved connections
+    pub fn plan_ssh_config(config_service: &ConfigService, path: &Path) -> Result<ImportPlan> {
+        let import = SshConfigParser::parse_file(path)?;
+        let existing = config_service.load_connections()?;
+        Ok(Self::plan(&existing, import))
+    }
+
+    /// Split imported connections into new ones and duplicates
+    ///
+    /// Connections are considered the same when host, username and port
+    /// match. Duplicates within the import itself keep the first occurrence.
+    pub fn plan(existing: &[SshConnection], import: SshConfigImport) -> ImportPlan {
+        let mut plan = ImportPlan {
+            warnings: import.warnings,
+            ..Default::default()
+        };
+
+        for connection in import.connections {
+            if let Some(saved) = existing.iter().find(|c| same_endpoint(c, &connection)) {
+                plan.duplicates.push(DuplicateConnection {
+                    differences: differences(saved, &connection),
+                    existing_name: saved.name.clone(),
+                    imported: connection,
+                });
+            } else if let Some(first) = plan.to_add.iter().find(|c| same_endpoint(c, &connection)) {
+                plan.warnings.push(format!(
+                    "{} has the same host, user and port as {}, skipped",
+                    connection.name, first.name
+                ));
+            } else {
+                plan.to_add.push(connection);
+            }
+        }
+
+        plan
+    }
+
+    /// Save the new connections from a plan, returning how many were added
+    pub fn apply(config_service: &ConfigService, plan: &ImportPlan) -> Result<usize> {
+        if plan.to_add.is_empty() {
+            return Ok(0);
+        }
+
+        config_service.update_connections(|connections| {
+            connections.extend(plan.to_add.iter().cloned());
+            true
+        })?;
+
+        tracing::info!("Imported {} connections", plan.to_add.len());
+        Ok(plan.to_add.len())
+    }
+}
+
+fn same_endpoint(a: &SshConnection, b: &SshConnection) -> bool {
+    a.host.eq_ignore_ascii_case(&b.host) && a.username == b.username && a.port == b.port
+}
+
+/// Describe the settings that differ between a saved and an imported connection
+fn differences(saved: &SshConnection, imported: &SshConnection) -> Vec<String> {
+    let mut diffs = Vec::new();
+
+    if saved.name != imported.name {
+        diffs.push(format!("name: {} -> {}", saved.name, imported.name));
+    }
+    if saved.auth_method != imported.auth_method {
+        diffs.push(format!(
+            "auth: {} -> {}",
+            describe_auth(&saved.auth_method),
+            describe_auth(&imported.auth_method)
+        ));
+    }
+
+    let saved_jumps = describe_jumps(saved);
+    let imported_jumps = describe_jumps(imported);
+    if saved_jumps != imported_jumps {
+        diffs.push(format!("jump hosts: {} -> {}", saved_jumps, imported_jumps));
+    }
+
+    if saved.forwarding_configs != imported.forwarding_configs {
+        let describe = |c: &SshConnection| {
+            if c.forwarding_configs.is_empty() {
+                "-".to_string()
+            } else {
+                c.forwarding_configs
+                    .iter()
+                    .map(|f| f.description())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        };
+        diffs.push(format!(
+            "forwards: {} -> {}",
+            describe(saved),
+            describe(imported)
+        ));
+    }
+    if saved.compression != imported.compression {
+        diffs.push(format!(
+            "compression: {} -> {}",
+            saved.compression, imported.compression
+        ));
+    }
+    if saved.verify_host_key != imported.verify_host_key {
+        diffs.push(format!(
+            "verify host key: {} -> {}",
+            saved.verify_host_key, imported.verify_host_key
+        ));
+    }
+
+    diffs
+}
+
+fn describe_auth(auth: &AuthMethod) -> String {
+    match auth {
+        AuthMethod::Password => "password".to_string(),
+        AuthMethod::PublicKey {
+            private_key_path, ..
+        } => format!("key {}", private_key_path.display()),
+    }
+}
+
+fn describe_jumps(connection: &SshConnection) -> String {
+    if connection.jump_hosts.is_empty() {
+        return "-".to_string();
+    }
+    connection
+        .jump_hosts
+        .iter()
+        .map(|j| format!("{}@{}:{}", j.username, j.host, j.port))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn import(content: &str) -> SshConfigImport {
+        SshConfigParser::parse_str(content, Path::new("/nonexistent"))
+    }
+
+    #[test]
+    fn test_plan_new_connections() {
+        let plan = ImportService::plan(&[], import("Host a\n  User x\n\nHost b\n  User y\n"));
+        assert_eq!(plan.to_add.len(), 2);
+        assert!(plan.duplicates.is_empty());
+    }
+
+    #[test]
+    fn test_plan_detects_duplicates() {
+        let existing = vec![SshConnection::new("Saved", "db.example.com", "admin").with_port(2222)];
+        let plan = ImportService::plan(
+            &existing,
+            import(
+                "Host db\n  HostName DB.example.com\n  User admin\n  Port 2222\n  IdentityFile /k\n",
+            ),
+        );
+
+        assert!(plan.to_add.is_empty());
+        assert_eq!(plan.duplicates.len(), 1);
+        let dup = &plan.duplicates[0];
+        assert_eq!(dup.existing_name, "Saved");
+        assert!(dup.differences.iter().any(|d| d.starts_with("name:")));
+        assert!(dup.differences.iter().any(|d| d.starts_with("auth:")));
+    }
+
+    #[test]
+    fn test_plan_dedups_within_import() {
+        let plan = ImportService::plan(
+            &[],
+            import("Host a\n  HostName same\n  User u\n\nHost b\n  HostName same\n  User u\n"),
+        );
+        assert_eq!(plan.to_add.len(), 1);
+        assert_eq!(plan.to_add[0].name, "a");
+        assert_eq!(plan.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_apply_saves_only_new() {
+        let temp_dir = tempdir().unwrap();
+        let service = ConfigService::with_dir(temp_dir.path().to_path_buf()).unwrap();
+        service
+            .save_connection(&SshConnection::new("Saved", "one", "u"))
+            .unwrap();
+
+        let config_path = temp_dir.path().join("ssh_config");
+        std::fs::write(
+            &config_path,
+            "Host one\n  User u\n\nHost two\n  User u\n  DynamicForward 1080\n",
+        )
+        .unwrap();
+
+        let plan = ImportService::plan_ssh_config(&service, &config_path).unwrap();
+        assert_eq!(plan.to_add.len(), 1);
+        assert_eq!(plan.duplicates.len(), 1);
+
+        // Planning alone does not touch the saved connections
+        assert_eq!(service.load_connections().unwrap().len(), 1);
+
+        assert_eq!(ImportService::apply(&service, &plan).unwrap(), 1);
+        let saved = service.load_connections().unwrap();
+        assert_eq!(saved.len(), 2);
+        assert_eq!(saved[1].name, "two");
+        assert_eq!(saved[1].forwarding_configs.len(), 1);
+    }
+}
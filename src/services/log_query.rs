@@ -0,0 +1,258 @@
+use crate::models::{ConnectionLog, LogLevel};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use uuid::Uuid;
+
+/// Filter over connection logs, combining any of its criteria
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    /// Connection name (case-insensitive) or ID
+    pub connection: Option<String>,
+
+    /// Session ID
+    pub session_id: Option<Uuid>,
+
+    /// Lowest level to include
+    pub min_level: Option<LogLevel>,
+
+    /// Event kinds to include (see [`crate::models::ConnectionEvent::KINDS`]);
+    /// empty means all
+    pub events: Vec<String>,
+
+    /// Only entries at or after this time
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only entries at or before this time
+    pub until: Option<DateTime<Utc>>,
+
+    /// Case-insensitive text the message must contain
+    pub text: Option<String>,
+
+    /// Keep only the most recent entries
+    pub limit: Option<usize>,
+
+    /// Return the newest entry first
+    pub newest_first: bool,
+}
+
+impl LogQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_connection(mut self, connection: impl Into<String>) -> Self {
+        self.connection = Some(connection.into());
+        self
+    }
+
+    pub fn with_session(mut self, session_id: Uuid) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    pub fn with_min_level(mut self, level: LogLevel) -> Self {
+        self.min_level = Some(level);
+        self
+    }
+
+    pub fn with_event(mut self, kind: impl Into<String>) -> Self {
+        self.events.push(kind.into());
+        self
+    }
+
+    pub fn with_range(
+        mut self,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.since = since;
+        self.until = until;
+        self
+    }
+
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn newest_first(mut self) -> Self {
+        self.newest_first = true;
+        self
+    }
+
+    /// Whether an entry passes every filter (limit and order aside)
+    pub fn matches(&self, log: &ConnectionLog) -> bool {
+        if let Some(connection) = &self.connection
+            && !log.connection_name.eq_ignore_ascii_case(connection)
+            && log.connection_id.to_string() != *connection
+        {
+            return false;
+        }
+        if self.session_id.is_some() && log.session_id != self.session_id {
+            return false;
+        }
+        if self.min_level.is_some_and(|level| log.level < level) {
+            return false;
+        }
+        if !self.events.is_empty() && !self.events.iter().any(|e| e == log.event.kind()) {
+            return false;
+        }
+        if self.since.is_some_and(|since| log.timestamp < since)
+            || self.until.is_some_and(|until| log.timestamp > until)
+        {
+            return false;
+        }
+        if let Some(text) = &self.text {
+            let text = text.to_lowercase();
+            if !log
+                .message
+                .as_ref()
+                .is_some_and(|m| m.to_lowercase().contains(&text))
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Matching entries in time order, cut to the most recent `limit`
+    pub fn apply(&self, logs: impl IntoIterator<Item = ConnectionLog>) -> Vec<ConnectionLog> {
+        let mut matched: Vec<_> = logs.into_iter().filter(|l| self.matches(l)).collect();
+        matched.sort_by_key(|l| l.timestamp);
+
+        if let Some(limit) = self.limit {
+            let skip = matched.len().saturating_sub(limit);
+            matched.drain(..skip);
+        }
+        if self.newest_first {
+            matched.reverse();
+        }
+        matched
+    }
+
+    /// Parse a time bound: RFC 3339, a local date (`2026-01-31`), or an age
+    /// before `now` such as `30m`, `12h` or `7d`
+    pub fn parse_time(text: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let text = text.trim();
+        if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+            return Some(time.with_timezone(&Utc));
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            let midnight = Local
+                .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+                .earliest()?;
+            return Some(midnight.with_timezone(&Utc));
+        }
+
+        let unit = text.chars().last()?;
+        let amount: i64 = text[..text.len() - unit.len_utf8()].parse().ok()?;
+        let age = match unit {
+            's' => Duration::try_seconds(amount),
+            'm' => Duration::try_minutes(amount),
+            'h' => Duration::try_hours(amount),
+            'd' => Duration::try_days(amount),
+            'w' => Duration::try_weeks(amount),
+            _ => return None,
+        }?;
+        now.checked_sub_signed(age)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionEvent;
+
+    fn entry(
+        name: &str,
+        level: LogLevel,
+        event: ConnectionEvent,
+        minutes_ago: i64,
+    ) -> ConnectionLog {
+        let mut log = ConnectionLog::new(Uuid::new_v4(), name, level, event);
+        log.timestamp = Utc::now() - Duration::minutes(minutes_ago);
+        log
+    }
+
+    fn names(logs: &[ConnectionLog]) -> Vec<&str> {
+        logs.iter().map(|l| l.connection_name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_query_combines_filters() {
+        let logs = vec![
+            entry("db", LogLevel::Info, ConnectionEvent::Connected, 30),
+            entry("db", LogLevel::Error, ConnectionEvent::ConnectionFailed, 20)
+                .with_message("Connection refused"),
+            entry("web", LogLevel::Warning, ConnectionEvent::IdleTimeout, 10),
+            entry("DB", LogLevel::Error, ConnectionEvent::ErrorDisconnect, 5)
+                .with_message("Broken pipe"),
+        ];
+
+        let query = LogQuery::new()
+            .with_connection("db")
+            .with_min_level(LogLevel::Warning);
+        assert_eq!(query.apply(logs.clone()).len(), 2);
+
+        let query = LogQuery::new()
+            .with_event("idle_timeout")
+            .with_event("connected");
+        assert_eq!(names(&query.apply(logs.clone())), vec!["db", "web"]);
+
+        let query = LogQuery::new().with_text("REFUSED");
+        assert_eq!(query.apply(logs.clone()).len(), 1);
+
+        let since = Utc::now() - Duration::minutes(15);
+        let query = LogQuery::new().with_range(Some(since), None);
+        assert_eq!(names(&query.apply(logs.clone())), vec!["web", "DB"]);
+
+        let id = logs[2].connection_id.to_string();
+        assert_eq!(LogQuery::new().with_connection(id).apply(logs).len(), 1);
+    }
+
+    #[test]
+    fn test_query_limit_keeps_most_recent() {
+        let logs: Vec<_> = (0..5)
+            .map(|i| {
+                entry(
+                    &format!("log {}", i),
+                    LogLevel::Info,
+                    ConnectionEvent::Connected,
+                    10 - i,
+                )
+            })
+            .rev()
+            .collect();
+
+        let query = LogQuery::new().with_limit(2);
+        assert_eq!(names(&query.apply(logs.clone())), vec!["log 3", "log 4"]);
+
+        let query = LogQuery::new().with_limit(2).newest_first();
+        assert_eq!(names(&query.apply(logs)), vec!["log 4", "log 3"]);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let now = Utc::now();
+
+        assert_eq!(
+            LogQuery::parse_time("2h", now),
+            Some(now - Duration::hours(2))
+        );
+        assert_eq!(
+            LogQuery::parse_time("2026-01-31T12:00:00Z", now).map(|t| t.to_rfc3339()),
+            Some("2026-01-31T12:00:00+00:00".to_string())
+        );
+        assert!(LogQuery::parse_time("2026-01-31", now).is_some());
+        assert!(LogQuery::parse_time("yesterday", now).is_none());
+        assert!(LogQuery::parse_time("h", now).is_none());
+        // Ages too far back for a timestamp
+        assert!(LogQuery::parse_time("99999999999d", now).is_none());
+        assert!(LogQuery::parse_time("9223372036854775807w", now).is_none());
+        assert!(LogQuery::parse_time("-99999999999999s", now).is_none());
+    }
+}
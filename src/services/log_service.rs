@@ -1,26 +1,141 @@
 #![allow(dead_code)]
 
 use crate::models::{ConnectionEvent, ConnectionLog, LogLevel};
+use crate::services::config_service::{AppSettings, ConfigService};
+use crate::services::event_bus::EventBus;
+use crate::services::log_query::LogQuery;
+use crate::utils::atomic_file::FileLock;
 use crate::utils::error::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use std::collections::VecDeque;
-use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::fs::OpenOptions;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufWriter};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{RwLock, mpsc, oneshot};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+/// When log files are rotated and how long rotated files are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogRotation {
+    /// Rotate once the active file would grow past this size (0 = no limit)
+    pub max_file_bytes: u64,
+
+    /// Rotate when the first entry of a new (UTC) day is written
+    pub daily: bool,
+
+    /// Delete rotated files older than this many days (0 = keep forever)
+    pub retention_days: u32,
+}
+
+impl LogRotation {
+    /// First day whose rotated files are kept, if any are pruned
+    fn retention_cutoff(&self) -> Option<NaiveDate> {
+        (self.retention_days > 0)
+            .then(|| Utc::now().date_naive() - Duration::days(self.retention_days.into()))
+    }
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 10 * 1024 * 1024,
+            daily: true,
+            retention_days: 30,
+        }
+    }
+}
+
+/// Entries the apps keep in memory; older ones are read from the file
+const APP_MEMORY_LOGS: usize = 1000;
+
+/// Destination of the entries an [`EventRecorder`] takes off the bus
+pub trait EventSink: Send + Sync + 'static {
+    /// Persist one entry, reporting failures itself
+    fn record_event(&self, log_entry: ConnectionLog) -> impl Future<Output = ()> + Send;
+
+    /// Wait until every entry recorded so far is persisted
+    fn flush(&self) -> impl Future<Output = ()> + Send;
+}
+
+/// Task logging bus events, see [`LogService::record`]
+pub struct EventRecorder {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl EventRecorder {
+    /// Pass every event published on `bus` to `sink` until finished or
+    /// dropped
+    pub fn start<S: EventSink>(bus: &EventBus, sink: Arc<S>) -> Self {
+        let mut events = bus.subscribe();
+        let (stop, mut stopped) = oneshot::channel::<()>();
+
+        let task = tokio::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    biased;
+                    _ = &mut stopped => break,
+                    event = events.recv() => event,
+                };
+                match event {
+                    Ok(event) => sink.record_event(event.to_log()).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!("Connection log missed {} event(s)", missed);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+
+            // Events published before stopping are still logged
+            while let Ok(event) = events.try_recv() {
+                sink.record_event(event.to_log()).await;
+            }
+            sink.flush().await;
+        });
+
+        Self { stop, task }
+    }
+
+    /// Log the events published so far, write them out and stop
+    pub async fn finish(self) {
+        let _ = self.stop.send(());
+        let _ = self.task.await;
+    }
+}
+
+/// Request to the log writer task
+enum WriterCommand {
+    Write(ConnectionLog),
+    Flush(oneshot::Sender<()>),
+}
+
 /// Connection logging service
+///
+/// With a log file, entries are appended as JSON Lines by a single background
+/// task that owns a buffered writer. The file is rotated to
+/// `<stem>.<date>.<n>.<ext>`; the history in all of them is loaded once when
+/// the file is set and extended with every entry logged afterwards.
 pub struct LogService {
     /// In-memory log buffer (most recent logs)
     logs: Arc<RwLock<VecDeque<ConnectionLog>>>,
 
+    /// Every entry in the log files and logged since, oldest first
+    history: Arc<RwLock<Vec<ConnectionLog>>>,
+
     /// Maximum logs to keep in memory
     max_memory_logs: usize,
 
     /// Optional log file path
     log_file_path: Option<PathBuf>,
+
+    /// Rotation and retention of the log file
+    rotation: LogRotation,
+
+    /// Channel to the writer task, started on the first write
+    writer: OnceLock<mpsc::UnboundedSender<WriterCommand>>,
 }
 
 impl LogService {
@@ -28,17 +143,47 @@ impl LogService {
     pub fn new(max_memory_logs: usize) -> Self {
         Self {
             logs: Arc::new(RwLock::new(VecDeque::with_capacity(max_memory_logs))),
+            history: Arc::new(RwLock::new(Vec::new())),
             max_memory_logs,
             log_file_path: None,
+            rotation: LogRotation::default(),
+            writer: OnceLock::new(),
         }
     }
 
     /// Create with file logging enabled
+    ///
+    /// The entries already in the file and its rotations are loaded as the
+    /// history, and the most recent of them into the in-memory buffer.
     pub fn with_file(mut self, log_file_path: PathBuf) -> Self {
+        let mut history = Vec::new();
+        for file in log_files(&log_file_path) {
+            if let Ok(content) = std::fs::read_to_string(&file) {
+                history.extend(parse_lines(&content));
+            }
+        }
+        let skip = history.len().saturating_sub(self.max_memory_logs);
+        let recent: VecDeque<_> = history[skip..].iter().cloned().collect();
+
+        self.logs = Arc::new(RwLock::new(recent));
+        self.history = Arc::new(RwLock::new(history));
         self.log_file_path = Some(log_file_path);
         self
     }
 
+    /// The app's connection log, as configured in the settings
+    pub fn for_app(config_service: &ConfigService, settings: &AppSettings) -> Self {
+        Self::new(APP_MEMORY_LOGS)
+            .with_file(config_service.log_file_path())
+            .with_rotation(settings.log_rotation())
+    }
+
+    /// Set when the log file is rotated and how long rotations are kept
+    pub fn with_rotation(mut self, rotation: LogRotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
     /// Log a connection event
     pub async fn log(
         &self,
@@ -91,9 +236,12 @@ impl LogService {
             logs.push_back(log_entry.clone());
         }
 
-        // Write to file if configured
-        if let Some(ref file_path) = self.log_file_path {
-            self.write_to_file(file_path, &log_entry).await?;
+        // Hand over to the writer task if configured
+        if let Some(writer) = self.writer() {
+            self.history.write().await.push(log_entry.clone());
+            writer
+                .send(WriterCommand::Write(log_entry.clone()))
+                .map_err(|_| anyhow::anyhow!("Log writer has stopped"))?;
         }
 
         // Also log via tracing
@@ -106,28 +254,51 @@ impl LogService {
         Ok(())
     }
 
-    /// Write log entry to file
-    async fn write_to_file(&self, file_path: &PathBuf, log_entry: &ConnectionLog) -> Result<()> {
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(file_path)
-            .await
-            .map_err(|e| {
-                anyhow::anyhow!("Failed to open log file {}: {}", file_path.display(), e)
-            })?;
+    /// Log every event published on `bus` until the returned recorder is
+    /// finished or dropped
+    pub fn record(self: &Arc<Self>, bus: &EventBus) -> EventRecorder {
+        EventRecorder::start(bus, self.clone())
+    }
 
-        let log_line = format!("{}\n", log_entry.format());
+    /// Channel to the writer task, starting it if needed
+    fn writer(&self) -> Option<&mpsc::UnboundedSender<WriterCommand>> {
+        let path = self.log_file_path.as_ref()?;
+        Some(self.writer.get_or_init(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(run_writer(LogWriter::new(path.clone(), self.rotation), rx));
+            tx
+        }))
+    }
 
-        file.write_all(log_line.as_bytes())
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to write to log file: {}", e))?;
+    /// Wait until every entry logged so far has been written to the file
+    pub async fn flush(&self) {
+        let Some(writer) = self.writer.get() else {
+            return;
+        };
+        let (ack, done) = oneshot::channel();
+        if writer.send(WriterCommand::Flush(ack)).is_ok() {
+            let _ = done.await;
+        }
+    }
 
-        file.flush()
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to flush log file: {}", e))?;
+    /// All entries within retention, oldest first
+    ///
+    /// The history loaded from the log file and its rotations plus the
+    /// entries logged since; without a log file this is the in-memory buffer.
+    pub async fn history(&self) -> Vec<ConnectionLog> {
+        if self.log_file_path.is_none() {
+            return self.get_logs().await;
+        }
 
-        Ok(())
+        let history = self.history.read().await;
+        match self.rotation.retention_cutoff() {
+            Some(cutoff) => history
+                .iter()
+                .filter(|l| l.timestamp.date_naive() >= cutoff)
+                .cloned()
+                .collect(),
+            None => history.clone(),
+        }
     }
 
     /// Get all logs in memory
@@ -136,12 +307,19 @@ impl LogService {
         logs.iter().cloned().collect()
     }
 
+    /// Entries matching `query` in the history
+    pub async fn query(&self, query: &LogQuery) -> Vec<ConnectionLog> {
+        query.apply(self.history().await)
+    }
+
     /// Get logs for a specific connection
+    ///
+    /// Includes the persisted history when logging to a file.
     pub async fn get_logs_for_connection(&self, connection_id: Uuid) -> Vec<ConnectionLog> {
-        let logs = self.logs.read().await;
-        logs.iter()
+        self.history()
+            .await
+            .into_iter()
             .filter(|log| log.connection_id == connection_id)
-            .cloned()
             .collect()
     }
 
@@ -189,6 +367,312 @@ impl LogService {
     }
 }
 
+impl EventSink for LogService {
+    async fn record_event(&self, log_entry: ConnectionLog) {
+        if let Err(e) = self.log_entry(log_entry).await {
+            tracing::warn!("Failed to log event: {}", e);
+        }
+    }
+
+    async fn flush(&self) {
+        LogService::flush(self).await
+    }
+}
+
+/// Reads the entries appended to a log file since the last poll, e.g. by
+/// another process
+pub struct LogTail {
+    path: PathBuf,
+    offset: u64,
+}
+
+impl LogTail {
+    /// Start at the current end of the file
+    pub fn new(path: PathBuf) -> Self {
+        let offset = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        Self { path, offset }
+    }
+
+    /// Complete entries written since the last call
+    ///
+    /// Starts over from the beginning when the file was rotated.
+    pub async fn poll(&mut self) -> Vec<ConnectionLog> {
+        let Ok(mut file) = File::open(&self.path).await else {
+            return Vec::new();
+        };
+        let len = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+        if len < self.offset {
+            self.offset = 0;
+        }
+
+        let mut appended = Vec::new();
+        if file
+            .seek(std::io::SeekFrom::Start(self.offset))
+            .await
+            .is_err()
+            || file.read_to_end(&mut appended).await.is_err()
+        {
+            return Vec::new();
+        }
+
+        // A partly written last line is read again next time
+        let complete = appended
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |end| end + 1);
+        self.offset += complete as u64;
+        parse_lines(&String::from_utf8_lossy(&appended[..complete]))
+    }
+}
+
+/// Buffered JSON Lines writer that rotates the active log file
+///
+/// Every process logging to the file has its own writer. Rotation happens
+/// under the file's advisory lock, and a writer whose file was rotated by
+/// another process reopens the new one.
+struct LogWriter {
+    path: PathBuf,
+    rotation: LogRotation,
+    file: Option<BufWriter<File>>,
+
+    /// Size of the active file
+    size: u64,
+
+    /// Day of the entries in the active file
+    day: NaiveDate,
+}
+
+impl LogWriter {
+    fn new(path: PathBuf, rotation: LogRotation) -> Self {
+        Self {
+            path,
+            rotation,
+            file: None,
+            size: 0,
+            day: Utc::now().date_naive(),
+        }
+    }
+
+    /// The active file, opened for appending if it is not yet
+    async fn open(&mut self) -> std::io::Result<&mut BufWriter<File>> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => {
+                if let Some(parent) = self.path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)
+                    .await?;
+                let metadata = file.metadata().await?;
+                self.size = metadata.len();
+                if let (true, Ok(modified)) = (self.size > 0, metadata.modified()) {
+                    self.day = DateTime::<Utc>::from(modified).date_naive();
+                }
+                BufWriter::new(file)
+            }
+        };
+        Ok(self.file.insert(file))
+    }
+
+    async fn write(&mut self, entry: &ConnectionLog) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+        line.push('\n');
+
+        self.reopen_if_moved().await?;
+        let day = entry.timestamp.date_naive();
+        if self.needs_rotation(day, line.len()) {
+            let path = self.path.clone();
+            let _lock = tokio::task::spawn_blocking(move || FileLock::exclusive(&path))
+                .await?
+                .map_err(std::io::Error::other)?;
+            // Another process may have rotated the file, or grown it, since
+            self.reopen_if_moved().await?;
+            if let Some(file) = &mut self.file {
+                file.flush().await?;
+                self.size = file.get_ref().metadata().await?.len();
+            }
+            if self.needs_rotation(day, line.len()) {
+                self.rotate().await?;
+            }
+        }
+        if self.size == 0 {
+            self.day = day;
+        }
+
+        self.open().await?.write_all(line.as_bytes()).await?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Whether writing `len` bytes of an entry from `day` starts a new file
+    fn needs_rotation(&self, day: NaiveDate, len: usize) -> bool {
+        let too_big = self.rotation.max_file_bytes > 0
+            && self.size + len as u64 > self.rotation.max_file_bytes;
+        self.size > 0 && (too_big || (self.rotation.daily && day != self.day))
+    }
+
+    /// Open the active file, or reopen it if the path no longer refers to
+    /// the open file because another process rotated it
+    async fn reopen_if_moved(&mut self) -> std::io::Result<()> {
+        if let Some(file) = &mut self.file {
+            let open = file.get_ref().metadata().await?;
+            let moved = match tokio::fs::metadata(&self.path).await {
+                Ok(current) => !same_file(&open, &current),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => true,
+                Err(e) => return Err(e),
+            };
+            if moved {
+                file.flush().await?;
+                self.file = None;
+            }
+        }
+        self.open().await?;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> std::io::Result<()> {
+        if let Some(file) = &mut self.file {
+            file.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Move the active file aside and drop rotations past retention
+    async fn rotate(&mut self) -> std::io::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush().await?;
+        }
+        tokio::fs::rename(&self.path, next_rotation_path(&self.path, self.day)).await?;
+        self.size = 0;
+        self.prune().await;
+        Ok(())
+    }
+
+    async fn prune(&self) {
+        let Some(cutoff) = self.rotation.retention_cutoff() else {
+            return;
+        };
+        for file in log_files(&self.path) {
+            if matches!(rotation_of(&self.path, &file), Some((day, _)) if day < cutoff)
+                && let Err(e) = tokio::fs::remove_file(&file).await
+            {
+                tracing::warn!("Failed to remove old log {}: {}", file.display(), e);
+            }
+        }
+    }
+}
+
+/// Write entries until every sender is gone, flushing whenever the queue
+/// runs empty
+async fn run_writer(mut writer: LogWriter, mut commands: mpsc::UnboundedReceiver<WriterCommand>) {
+    writer.prune().await;
+
+    while let Some(command) = commands.recv().await {
+        let result = match command {
+            WriterCommand::Write(entry) => writer.write(&entry).await,
+            WriterCommand::Flush(ack) => {
+                let result = writer.flush().await;
+                let _ = ack.send(());
+                result
+            }
+        };
+        let result = match result {
+            Ok(()) if commands.is_empty() => writer.flush().await,
+            other => other,
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to write log {}: {}", writer.path.display(), e);
+        }
+    }
+}
+
+/// Whether two handles refer to the same file
+#[cfg(unix)]
+fn same_file(a: &std::fs::Metadata, b: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+/// Open files cannot be renamed on other platforms, so the path always
+/// refers to the open file
+#[cfg(not(unix))]
+fn same_file(_a: &std::fs::Metadata, _b: &std::fs::Metadata) -> bool {
+    true
+}
+
+/// `<stem>.` and `.<ext>` around the day and number of rotated files
+fn rotation_affixes(path: &Path) -> (String, String) {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (format!("{}.", stem), ext)
+}
+
+/// Day and number of a rotation of the log file at `path`
+fn rotation_of(path: &Path, file: &Path) -> Option<(NaiveDate, u32)> {
+    let (prefix, suffix) = rotation_affixes(path);
+    let name = file.file_name()?.to_str()?;
+    let (day, number) = name
+        .strip_prefix(&prefix)?
+        .strip_suffix(&suffix)?
+        .split_once('.')?;
+    Some((
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()?,
+        number.parse().ok()?,
+    ))
+}
+
+/// Rotations of the log file, oldest first, followed by the file itself
+fn log_files(path: &Path) -> Vec<PathBuf> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut rotated: Vec<_> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|file| rotation_of(path, &file).map(|key| (key, file)))
+        .collect();
+    rotated.sort();
+
+    let mut files: Vec<_> = rotated.into_iter().map(|(_, file)| file).collect();
+    if path.exists() {
+        files.push(path.to_path_buf());
+    }
+    files
+}
+
+fn next_rotation_path(path: &Path, day: NaiveDate) -> PathBuf {
+    let number = log_files(path)
+        .iter()
+        .filter_map(|file| rotation_of(path, file))
+        .filter(|(d, _)| *d == day)
+        .map(|(_, n)| n)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let (prefix, suffix) = rotation_affixes(path);
+    path.with_file_name(format!("{}{}.{}{}", prefix, day, number, suffix))
+}
+
+/// Entries of a JSON Lines log, skipping lines that do not parse
+fn parse_lines(content: &str) -> Vec<ConnectionLog> {
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -506,9 +990,247 @@ mod tests {
             .await
             .unwrap();
 
+        service.flush().await;
+
         // Check file was created and has content
         let content = std::fs::read_to_string(&log_path).unwrap();
         assert!(content.contains("Test"));
         assert!(content.contains("Connected"));
     }
+
+    fn entry_at(conn_id: Uuid, name: &str, timestamp: DateTime<Utc>) -> ConnectionLog {
+        let mut entry =
+            ConnectionLog::new(conn_id, name, LogLevel::Info, ConnectionEvent::Connected);
+        entry.timestamp = timestamp;
+        entry
+    }
+
+    #[tokio::test]
+    async fn test_log_service_rotates_by_size_and_reloads_history() {
+        let temp = tempdir().unwrap();
+        let log_path = temp.path().join("connections.jsonl");
+        std::fs::write(&log_path, "legacy text line\n").unwrap();
+        let conn_id = Uuid::new_v4();
+
+        let service = LogService::new(100)
+            .with_file(log_path.clone())
+            .with_rotation(LogRotation {
+                max_file_bytes: 1,
+                daily: false,
+                retention_days: 0,
+            });
+        for i in 0..3 {
+            service
+                .log(
+                    conn_id,
+                    format!("Log {}", i),
+                    LogLevel::Info,
+                    ConnectionEvent::Connected,
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+        service.flush().await;
+        assert_eq!(log_files(&log_path).len(), 4);
+
+        let reloaded = LogService::new(2).with_file(log_path);
+        let recent = reloaded.get_logs().await;
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[1].connection_name, "Log 2");
+
+        let history = reloaded.get_logs_for_connection(conn_id).await;
+        let names: Vec<_> = history.iter().map(|l| l.connection_name.as_str()).collect();
+        assert_eq!(names, vec!["Log 0", "Log 1", "Log 2"]);
+    }
+
+    #[tokio::test]
+    async fn test_log_service_rotates_daily_and_prunes() {
+        let temp = tempdir().unwrap();
+        let log_path = temp.path().join("connections.jsonl");
+        let conn_id = Uuid::new_v4();
+        let now = Utc::now();
+
+        let service = LogService::new(100)
+            .with_file(log_path.clone())
+            .with_rotation(LogRotation {
+                max_file_bytes: 0,
+                daily: true,
+                retention_days: 7,
+            });
+        for (name, days_ago) in [("old", 10), ("yesterday", 1), ("today", 0)] {
+            service
+                .log_entry(entry_at(conn_id, name, now - Duration::days(days_ago)))
+                .await
+                .unwrap();
+        }
+        service.flush().await;
+
+        let yesterday = (now - Duration::days(1)).date_naive();
+        assert_eq!(
+            log_files(&log_path),
+            vec![
+                temp.path()
+                    .join(format!("connections.{}.1.jsonl", yesterday)),
+                log_path.clone(),
+            ]
+        );
+
+        let names: Vec<_> = service
+            .history()
+            .await
+            .into_iter()
+            .map(|l| l.connection_name)
+            .collect();
+        assert_eq!(names, vec!["yesterday", "today"]);
+    }
+
+    #[tokio::test]
+    async fn test_log_history_is_cached() {
+        let temp = tempdir().unwrap();
+        let log_path = temp.path().join("connections.jsonl");
+        let conn_id = Uuid::new_v4();
+        let earlier = LogService::new(100).with_file(log_path.clone());
+        earlier
+            .log(
+                conn_id,
+                "Earlier",
+                LogLevel::Info,
+                ConnectionEvent::Connected,
+                None,
+            )
+            .await
+            .unwrap();
+        earlier.flush().await;
+
+        let service = LogService::new(100).with_file(log_path.clone());
+        std::fs::remove_file(&log_path).unwrap();
+        service
+            .log(
+                conn_id,
+                "Later",
+                LogLevel::Info,
+                ConnectionEvent::Disconnected,
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Served from memory: the files are not read again
+        let names: Vec<_> = service
+            .get_logs_for_connection(conn_id)
+            .await
+            .into_iter()
+            .map(|l| l.connection_name)
+            .collect();
+        assert_eq!(names, vec!["Earlier", "Later"]);
+    }
+
+    #[tokio::test]
+    async fn test_log_writers_share_rotation() {
+        let temp = tempdir().unwrap();
+        let log_path = temp.path().join("connections.jsonl");
+        let conn_id = Uuid::new_v4();
+        let now = Utc::now();
+        let line_len = serde_json::to_string(&entry_at(conn_id, "a", now))
+            .unwrap()
+            .len() as u64
+            + 1;
+        let rotation = LogRotation {
+            max_file_bytes: line_len * 5 / 2,
+            daily: false,
+            retention_days: 0,
+        };
+
+        // Two processes appending to the same file
+        let mut first = LogWriter::new(log_path.clone(), rotation);
+        let mut second = LogWriter::new(log_path.clone(), rotation);
+        let write = async |writer: &mut LogWriter, name: &str| {
+            writer.write(&entry_at(conn_id, name, now)).await.unwrap();
+            writer.flush().await.unwrap();
+        };
+        write(&mut first, "a").await;
+        write(&mut second, "b").await;
+        write(&mut first, "c").await;
+        // Rotates once it sees the other writer's entries under the lock
+        write(&mut first, "d").await;
+        // Follows the rotation instead of appending to the rotated file
+        write(&mut second, "e").await;
+
+        let rotated = temp
+            .path()
+            .join(format!("connections.{}.1.jsonl", now.date_naive()));
+        assert_eq!(
+            log_files(&log_path),
+            vec![rotated.clone(), log_path.clone()]
+        );
+        let names = |path: &Path| -> Vec<String> {
+            parse_lines(&std::fs::read_to_string(path).unwrap())
+                .into_iter()
+                .map(|l| l.connection_name)
+                .collect()
+        };
+        assert_eq!(names(&rotated), vec!["a", "b", "c"]);
+        assert_eq!(names(&log_path), vec!["d", "e"]);
+    }
+
+    #[tokio::test]
+    async fn test_log_tail_reads_appended_entries() {
+        let temp = tempdir().unwrap();
+        let log_path = temp.path().join("connections.jsonl");
+        let service = LogService::new(100).with_file(log_path.clone());
+        let conn_id = Uuid::new_v4();
+
+        service
+            .log(
+                conn_id,
+                "Before",
+                LogLevel::Info,
+                ConnectionEvent::Connected,
+                None,
+            )
+            .await
+            .unwrap();
+        service.flush().await;
+
+        let mut tail = LogTail::new(log_path.clone());
+        assert!(tail.poll().await.is_empty());
+
+        service
+            .log(
+                conn_id,
+                "After",
+                LogLevel::Info,
+                ConnectionEvent::Disconnected,
+                None,
+            )
+            .await
+            .unwrap();
+        service.flush().await;
+
+        let appended = tail.poll().await;
+        assert_eq!(appended.len(), 1);
+        assert_eq!(appended[0].connection_name, "After");
+        assert!(tail.poll().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_log_service_records_bus_events() {
+        use crate::models::SshConnection;
+
+        let service = Arc::new(LogService::new(100));
+        let bus = EventBus::new();
+        let recorder = service.record(&bus);
+        let connection = SshConnection::new("db", "bastion", "deploy");
+
+        let source = bus.source(&connection);
+        source.publish(ConnectionEvent::ConnectAttempt, None);
+        source.publish(ConnectionEvent::AuthFailed, Some("denied".to_string()));
+        recorder.finish().await;
+
+        let logs = service.get_logs_for_connection(connection.id).await;
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[1].level, LogLevel::Error);
+        assert_eq!(logs[1].message.as_deref(), Some("denied"));
+    }
 }
@@ -0,0 +1,530 @@
+use crate::models::ConnectionEvent;
+use crate::services::config_service::AppSettings;
+use crate::services::event_bus::SessionEvent;
+use crate::services::session_manager::SessionManager;
+use crate::utils::error::{Result, SshToolError};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Prefix of every exported metric
+const PREFIX: &str = "ssh_tunnel_manager";
+
+/// Upper bounds, in seconds, of the connect latency histogram buckets
+const LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Largest request head read from a scraper
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// Pause after a failed accept (e.g. out of file descriptors) before the next
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Latency histogram in the Prometheus layout
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    /// Observations per bucket (not cumulative), `+Inf` last
+    counts: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// Counters of one connection, built from session events
+#[derive(Debug, Default, Clone)]
+struct ConnectionStats {
+    name: String,
+    /// Start of the connect attempt in progress
+    attempt_started: Option<DateTime<Utc>>,
+    /// Whether the connection has been up before, so the next attempt is a
+    /// reconnect
+    connected_before: bool,
+    reconnects: u64,
+    auth_failures: u64,
+    connect_latency: Histogram,
+}
+
+/// Prometheus metrics for the sessions and tunnels of a [`SessionManager`]
+///
+/// Traffic, channels and uptime are read from the session manager on every
+/// scrape; connect latencies, reconnects and authentication failures are
+/// counted from its event bus.
+#[derive(Clone)]
+pub struct MetricsService {
+    sessions: Arc<SessionManager>,
+    connections: Arc<Mutex<HashMap<Uuid, ConnectionStats>>>,
+}
+
+impl MetricsService {
+    pub fn new(sessions: Arc<SessionManager>) -> Self {
+        Self {
+            sessions,
+            connections: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Serve metrics on `address` until the returned server is dropped
+    pub async fn start(self, address: &str) -> Result<MetricsServer> {
+        let listener = TcpListener::bind(address).await.map_err(|e| {
+            SshToolError::ConfigError(format!(
+                "Failed to bind metrics endpoint to {}: {}",
+                address, e
+            ))
+        })?;
+        self.start_on(listener)
+    }
+
+    /// Serve metrics on an already bound listener
+    pub fn start_on(self, listener: TcpListener) -> Result<MetricsServer> {
+        let address = listener.local_addr()?;
+        tracing::info!("Serving metrics on http://{}/metrics", address);
+
+        let recorder = self.record();
+        let server = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        let metrics = self.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = metrics.handle_request(stream).await {
+                                tracing::debug!("Metrics request failed: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        tracing::warn!("Metrics accept error: {}", e);
+                        tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    }
+                }
+            }
+        });
+
+        Ok(MetricsServer {
+            address,
+            tasks: vec![recorder, server],
+        })
+    }
+
+    /// Metrics server configured in the app settings, if enabled
+    ///
+    /// Failing to start is logged rather than returned, so a taken port does
+    /// not keep the app from running.
+    pub async fn for_app(
+        sessions: Arc<SessionManager>,
+        settings: &AppSettings,
+    ) -> Option<MetricsServer> {
+        let address = settings.metrics_address()?;
+        match Self::new(sessions).start(&address).await {
+            Ok(server) => Some(server),
+            Err(e) => {
+                tracing::warn!("Metrics endpoint not started: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Count events of the session manager from now on
+    fn record(&self) -> JoinHandle<()> {
+        let mut events = self.sessions.events().subscribe();
+        let metrics = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => metrics.observe(&event),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Metrics missed {} session events", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    fn observe(&self, event: &SessionEvent) {
+        let Ok(mut connections) = self.connections.lock() else {
+            return;
+        };
+        let stats = connections.entry(event.connection_id).or_default();
+        stats.name = event.connection_name.clone();
+
+        match &event.event {
+            ConnectionEvent::ConnectAttempt => {
+                if stats.connected_before {
+                    stats.reconnects += 1;
+                }
+                stats.attempt_started = Some(event.timestamp);
+            }
+            ConnectionEvent::Connected => {
+                if let Some(started) = stats.attempt_started.take() {
+                    let elapsed = (event.timestamp - started).to_std().unwrap_or_default();
+                    stats.connect_latency.observe(elapsed.as_secs_f64());
+                }
+                stats.connected_before = true;
+            }
+            ConnectionEvent::AuthFailed => {
+                stats.auth_failures += 1;
+                stats.attempt_started = None;
+            }
+            ConnectionEvent::ConnectionFailed => stats.attempt_started = None,
+            _ => {}
+        }
+    }
+
+    /// Current metrics in the Prometheus text exposition format
+    pub async fn render(&self) -> String {
+        let sessions = self.sessions.list_sessions().await;
+        let tunnels = self.sessions.list_tunnels().await;
+        let connections: Vec<_> = match self.connections.lock() {
+            Ok(connections) => connections.values().cloned().collect(),
+            Err(_) => Vec::new(),
+        };
+        let session_labels =
+            |id: &Uuid, name: &str| format!("session=\"{}\",connection=\"{}\"", id, escape(name));
+        let mut out = String::new();
+
+        family(&mut out, "sessions", "gauge", "Active SSH sessions");
+        sample(&mut out, "sessions", "", sessions.len());
+
+        family(
+            &mut out,
+            "session_uptime_seconds",
+            "gauge",
+            "Seconds since the session connected",
+        );
+        for session in &sessions {
+            let labels = session_labels(&session.id, &session.connection_name);
+            let uptime = session.duration().num_seconds().max(0);
+            sample(&mut out, "session_uptime_seconds", &labels, uptime);
+        }
+
+        for (name, help) in [
+            (
+                "session_bytes_sent_total",
+                "Bytes sent through all tunnels of the session",
+            ),
+            (
+                "session_bytes_received_total",
+                "Bytes received through all tunnels of the session",
+            ),
+        ] {
+            family(&mut out, name, "counter", help);
+            for session in &sessions {
+                let labels = session_labels(&session.id, &session.connection_name);
+                let bytes = if name == "session_bytes_sent_total" {
+                    session.bytes_sent
+                } else {
+                    session.bytes_received
+                };
+                sample(&mut out, name, &labels, bytes);
+            }
+        }
+
+        for (name, kind, help) in [
+            (
+                "tunnel_bytes_sent_total",
+                "counter",
+                "Bytes sent through the tunnel",
+            ),
+            (
+                "tunnel_bytes_received_total",
+                "counter",
+                "Bytes received through the tunnel",
+            ),
+            (
+                "tunnel_active_channels",
+                "gauge",
+                "Channels currently open through the tunnel",
+            ),
+        ] {
+            family(&mut out, name, kind, help);
+            for tunnel in &tunnels {
+                let labels = format!(
+                    "{},tunnel=\"{}\"",
+                    session_labels(&tunnel.session_id, &tunnel.connection_name),
+                    escape(&tunnel.config.description())
+                );
+                let value = match name {
+                    "tunnel_bytes_sent_total" => tunnel.bytes_sent,
+                    "tunnel_bytes_received_total" => tunnel.bytes_received,
+                    _ => tunnel.active_channels,
+                };
+                sample(&mut out, name, &labels, value);
+            }
+        }
+
+        family(
+            &mut out,
+            "connect_duration_seconds",
+            "histogram",
+            "Time from connect attempt to established session",
+        );
+        for stats in &connections {
+            let connection = format!("connection=\"{}\"", escape(&stats.name));
+            let histogram = &stats.connect_latency;
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&histogram.counts) {
+                cumulative += count;
+                let labels = format!("{},le=\"{}\"", connection, bound);
+                sample(
+                    &mut out,
+                    "connect_duration_seconds_bucket",
+                    &labels,
+                    cumulative,
+                );
+            }
+            let labels = format!("{},le=\"+Inf\"", connection);
+            sample(
+                &mut out,
+                "connect_duration_seconds_bucket",
+                &labels,
+                histogram.count,
+            );
+            sample(
+                &mut out,
+                "connect_duration_seconds_sum",
+                &connection,
+                histogram.sum,
+            );
+            sample(
+                &mut out,
+                "connect_duration_seconds_count",
+                &connection,
+                histogram.count,
+            );
+        }
+
+        for (name, help) in [
+            (
+                "reconnects_total",
+                "Connect attempts after the connection had been up",
+            ),
+            ("auth_failures_total", "Failed authentication attempts"),
+        ] {
+            family(&mut out, name, "counter", help);
+            for stats in &connections {
+                let labels = format!("connection=\"{}\"", escape(&stats.name));
+                let count = if name == "reconnects_total" {
+                    stats.reconnects
+                } else {
+                    stats.auth_failures
+                };
+                sample(&mut out, name, &labels, count);
+            }
+        }
+
+        out
+    }
+
+    /// Answer one HTTP request; only `GET /metrics` is served
+    async fn handle_request(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 || request.len() + n > MAX_REQUEST_BYTES {
+                break;
+            }
+            request.extend_from_slice(&buf[..n]);
+        }
+
+        let request = String::from_utf8_lossy(&request);
+        let mut parts = request
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .split_whitespace();
+        let method = parts.next().unwrap_or_default();
+        let path = parts.next().unwrap_or_default();
+        let path = path.split('?').next().unwrap_or_default();
+
+        let (status, content_type, body) = match (method, path) {
+            ("GET" | "HEAD", "/metrics") => (
+                "200 OK",
+                "text/plain; version=0.0.4; charset=utf-8",
+                self.render().await,
+            ),
+            (_, "/metrics") => (
+                "405 Method Not Allowed",
+                "text/plain; charset=utf-8",
+                "Method not allowed\n".to_string(),
+            ),
+            _ => (
+                "404 Not Found",
+                "text/plain; charset=utf-8",
+                "Not found\n".to_string(),
+            ),
+        };
+
+        let mut response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            content_type,
+            body.len()
+        );
+        if method != "HEAD" {
+            response.push_str(&body);
+        }
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+}
+
+/// Running metrics endpoint; stops when dropped
+pub struct MetricsServer {
+    address: SocketAddr,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Write the HELP and TYPE lines of a metric family
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {}_{} {}", PREFIX, name, help);
+    let _ = writeln!(out, "# TYPE {}_{} {}", PREFIX, name, kind);
+}
+
+/// Write one sample line
+fn sample(out: &mut String, name: &str, labels: &str, value: impl std::fmt::Display) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{}_{} {}", PREFIX, name, value);
+    } else {
+        let _ = writeln!(out, "{}_{}{{{}}} {}", PREFIX, name, labels, value);
+    }
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SshConnection;
+    use chrono::Duration;
+
+    fn event(
+        connection: &SshConnection,
+        event: ConnectionEvent,
+        timestamp: DateTime<Utc>,
+    ) -> SessionEvent {
+        SessionEvent {
+            session_id: None,
+            connection_id: connection.id,
+            connection_name: connection.name.clone(),
+            event,
+            message: None,
+            timestamp,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_connection_events() {
+        let metrics = MetricsService::new(Arc::new(SessionManager::new(300)));
+        let connection = SshConnection::new("prod \"db\"", "bastion", "deploy");
+        let start = Utc::now();
+
+        for (offset_ms, kind) in [
+            (0, ConnectionEvent::ConnectAttempt),
+            (200, ConnectionEvent::AuthFailed),
+            (1000, ConnectionEvent::ConnectAttempt),
+            (1300, ConnectionEvent::Connected),
+            (5000, ConnectionEvent::ConnectAttempt),
+            (7000, ConnectionEvent::Connected),
+        ] {
+            metrics.observe(&event(
+                &connection,
+                kind,
+                start + Duration::milliseconds(offset_ms),
+            ));
+        }
+
+        let text = metrics.render().await;
+        let label = "connection=\"prod \\\"db\\\"\"";
+        assert!(text.contains("ssh_tunnel_manager_sessions 0\n"));
+        assert!(text.contains(&format!(
+            "ssh_tunnel_manager_auth_failures_total{{{}}} 1\n",
+            label
+        )));
+        assert!(text.contains(&format!(
+            "ssh_tunnel_manager_reconnects_total{{{}}} 1\n",
+            label
+        )));
+        assert!(text.contains(&format!(
+            "ssh_tunnel_manager_connect_duration_seconds_bucket{{{},le=\"0.5\"}} 1\n",
+            label
+        )));
+        assert!(text.contains(&format!(
+            "ssh_tunnel_manager_connect_duration_seconds_bucket{{{},le=\"2.5\"}} 2\n",
+            label
+        )));
+        assert!(text.contains(&format!(
+            "ssh_tunnel_manager_connect_duration_seconds_count{{{}}} 2\n",
+            label
+        )));
+        assert!(text.contains("# TYPE ssh_tunnel_manager_connect_duration_seconds histogram\n"));
+    }
+
+    async fn request(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(format!("GET {} HTTP/1.1\r\nHost: test\r\n\r\n", path).as_bytes())
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_serves_text_format() {
+        let metrics = MetricsService::new(Arc::new(SessionManager::new(300)));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = metrics.start_on(listener).unwrap();
+
+        let response = request(server.local_addr(), "/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("text/plain; version=0.0.4"));
+        assert!(response.contains("ssh_tunnel_manager_sessions 0"));
+
+        assert!(
+            request(server.local_addr(), "/")
+                .await
+                .starts_with("HTTP/1.1 404")
+        );
+    }
+}
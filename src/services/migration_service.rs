@@ -0,0 +1,354 @@
+use crate::utils::atomic_file::{self, FileLock};
+use crate::utils::error::{Result, SshToolError};
+use chrono::Utc;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Schema version written by this release
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// Files without a `schema_version` key predate versioning
+const UNVERSIONED_SCHEMA_VERSION: u32 = 1;
+
+/// A single upgrade step from `version` to `version + 1`
+type Migration = fn(ConfigFileKind, &mut Table) -> Result<()>;
+
+/// Migration steps, indexed by the version they upgrade from
+const MIGRATIONS: &[(u32, Migration)] = &[(1, v1_to_v2)];
+
+/// The configuration files that carry a schema version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigFileKind {
+    Connections,
+    Templates,
+    Settings,
+}
+
+impl ConfigFileKind {
+    pub const ALL: [ConfigFileKind; 3] = [Self::Connections, Self::Templates, Self::Settings];
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Self::Connections => "connections.toml",
+            Self::Templates => "templates.toml",
+            Self::Settings => "settings.toml",
+        }
+    }
+
+    /// Key of the array of entries in the file, if it has one
+    fn entries_key(&self) -> Option<&'static str> {
+        match self {
+            Self::Connections => Some("connections"),
+            Self::Templates => Some("templates"),
+            Self::Settings => None,
+        }
+    }
+}
+
+/// Schema state of a config file on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub kind: ConfigFileKind,
+    pub path: PathBuf,
+    /// Version currently stored in the file
+    pub version: u32,
+}
+
+impl MigrationStatus {
+    pub fn is_pending(&self) -> bool {
+        self.version < CURRENT_SCHEMA_VERSION
+    }
+}
+
+/// Upgrades stored configuration files to the current schema
+///
+/// Files are parsed as plain TOML tables and migrated step by step
+/// (v1 → v2 → …) before being deserialised, so older layouts never have to
+/// match the current structs. A copy of the original file is written next to
+/// it before an upgraded version replaces it.
+pub struct MigrationService;
+
+impl MigrationService {
+    /// Schema version of a parsed file
+    pub fn schema_version(table: &Table) -> Result<u32> {
+        match table.get("schema_version") {
+            None => Ok(UNVERSIONED_SCHEMA_VERSION),
+            Some(Value::Integer(v)) if *v >= 1 => Ok(*v as u32),
+            Some(other) => Err(SshToolError::ConfigError(format!(
+                "Invalid schema_version: {}",
+                other
+            ))),
+        }
+    }
+
+    /// Set the schema version of a table about to be written
+    pub fn stamp(table: &mut Table) {
+        table.insert(
+            "schema_version".to_string(),
+            Value::Integer(CURRENT_SCHEMA_VERSION as i64),
+        );
+    }
+
+    /// Migrate a parsed file in memory, returning the version it started at
+    pub fn migrate(kind: ConfigFileKind, table: &mut Table) -> Result<u32> {
+        let from = Self::schema_version(table)?;
+        if from > CURRENT_SCHEMA_VERSION {
+            return Err(SshToolError::ConfigError(format!(
+                "{} has schema version {}, but this release only supports up to {}; please upgrade",
+                kind.file_name(),
+                from,
+                CURRENT_SCHEMA_VERSION
+            )));
+        }
+
+        for version in from..CURRENT_SCHEMA_VERSION {
+            let (_, step) = MIGRATIONS
+                .iter()
+                .find(|(v, _)| *v == version)
+                .ok_or_else(|| {
+                    SshToolError::ConfigError(format!("No migration from schema v{}", version))
+                })?;
+            step(kind, table)?;
+            tracing::info!(
+                "Migrated {} from schema v{} to v{}",
+                kind.file_name(),
+                version,
+                version + 1
+            );
+        }
+
+        Self::stamp(table);
+        Ok(from)
+    }
+
+    /// Read a config file, upgrading it on disk if it uses an older schema
+    ///
+    /// Returns `None` if the file does not exist.
+    pub fn load(kind: ConfigFileKind, path: &Path) -> Result<Option<Table>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let mut table = read_table(kind, path)?;
+        let from = Self::migrate(kind, &mut table)?;
+        if from < CURRENT_SCHEMA_VERSION {
+            let backup = Self::backup(path, from)?;
+            write_table(kind, path, &table)?;
+            tracing::info!(
+                "Upgraded {:?} to schema v{} (backup: {:?})",
+                path,
+                CURRENT_SCHEMA_VERSION,
+                backup
+            );
+        }
+
+        Ok(Some(table))
+    }
+
+    /// Report the schema version of every config file that exists
+    pub fn check(config_dir: &Path) -> Result<Vec<MigrationStatus>> {
+        let mut statuses = Vec::new();
+        for kind in ConfigFileKind::ALL {
+            let path = config_dir.join(kind.file_name());
+            if !path.exists() {
+                continue;
+            }
+            let table = read_table(kind, &path)?;
+            statuses.push(MigrationStatus {
+                kind,
+                version: Self::schema_version(&table)?,
+                path,
+            });
+        }
+        Ok(statuses)
+    }
+
+    /// Upgrade every outdated config file, returning the statuses before migration
+    pub fn migrate_all(config_dir: &Path) -> Result<Vec<MigrationStatus>> {
+        let statuses = Self::check(config_dir)?;
+        for status in statuses.iter().filter(|s| s.is_pending()) {
+            let _lock = FileLock::exclusive(&status.path)?;
+            Self::load(status.kind, &status.path)?;
+        }
+        Ok(statuses)
+    }
+
+    /// Copy a file to `<name>.v<version>-<timestamp>.bak` before upgrading it
+    fn backup(path: &Path, version: u32) -> Result<PathBuf> {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let backup = path.with_file_name(format!(
+            "{}.v{}-{}.bak",
+            file_name,
+            version,
+            Utc::now().format("%Y%m%dT%H%M%S%.3f")
+        ));
+        fs::copy(path, &backup)?;
+        Ok(backup)
+    }
+}
+
+fn read_table(kind: ConfigFileKind, path: &Path) -> Result<Table> {
+    let content = fs::read_to_string(path)?;
+    content.parse::<Table>().map_err(|e| {
+        SshToolError::ConfigError(format!("Failed to parse {}: {}", kind.file_name(), e))
+    })
+}
+
+/// Write a migrated table with `schema_version` as the first line, matching
+/// the layout `ConfigService` writes
+fn write_table(kind: ConfigFileKind, path: &Path, table: &Table) -> Result<()> {
+    let mut body = table.clone();
+    let version = body
+        .remove("schema_version")
+        .unwrap_or(Value::Integer(CURRENT_SCHEMA_VERSION as i64));
+    let body = toml::to_string_pretty(&body).map_err(|e| {
+        SshToolError::ConfigError(format!("Failed to serialize {}: {}", kind.file_name(), e))
+    })?;
+    let content = format!("schema_version = {}\n\n{}", version, body);
+    atomic_file::write_atomic(path, content.as_bytes())?;
+    Ok(())
+}
+
+/// v1 → v2: persist fields that v1 files left to serde defaults
+///
+/// Entries without an `id` got a fresh random id on every load, so sessions
+/// and logs could not be matched to a connection across restarts. The same
+/// applied to the creation/update timestamps.
+fn v1_to_v2(kind: ConfigFileKind, table: &mut Table) -> Result<()> {
+    let Some(key) = kind.entries_key() else {
+        return Ok(());
+    };
+    let Some(entries) = table.get_mut(key).and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+
+    let now = Value::String(Utc::now().to_rfc3339());
+    for entry in entries.iter_mut().filter_map(Value::as_table_mut) {
+        entry
+            .entry("id")
+            .or_insert_with(|| Value::String(uuid::Uuid::new_v4().to_string()));
+        if kind == ConfigFileKind::Connections {
+            entry.entry("created_at").or_insert_with(|| now.clone());
+            entry.entry("updated_at").or_insert_with(|| now.clone());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const V1_CONNECTIONS: &str = r#"
+[[connections]]
+name = "Legacy"
+host = "example.com"
+username = "user"
+"#;
+
+    #[test]
+    fn test_unversioned_file_is_v1() {
+        let table: Table = V1_CONNECTIONS.parse().unwrap();
+        assert_eq!(MigrationService::schema_version(&table).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_migrate_v1_assigns_ids() {
+        let mut table: Table = V1_CONNECTIONS.parse().unwrap();
+        let from = MigrationService::migrate(ConfigFileKind::Connections, &mut table).unwrap();
+
+        assert_eq!(from, 1);
+        assert_eq!(
+            MigrationService::schema_version(&table).unwrap(),
+            CURRENT_SCHEMA_VERSION
+        );
+        let entry = table["connections"].as_array().unwrap()[0]
+            .as_table()
+            .unwrap();
+        assert!(uuid::Uuid::parse_str(entry["id"].as_str().unwrap()).is_ok());
+        assert!(entry.contains_key("created_at"));
+    }
+
+    #[test]
+    fn test_migrate_keeps_existing_ids() {
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut table: Table = format!("[[templates]]\nid = \"{}\"\nname = \"t\"\n", id)
+            .parse()
+            .unwrap();
+        MigrationService::migrate(ConfigFileKind::Templates, &mut table).unwrap();
+        let entry = table["templates"].as_array().unwrap()[0]
+            .as_table()
+            .unwrap();
+        assert_eq!(entry["id"].as_str().unwrap(), id);
+    }
+
+    #[test]
+    fn test_newer_version_rejected() {
+        let mut table: Table = "schema_version = 99\n".parse().unwrap();
+        assert!(MigrationService::migrate(ConfigFileKind::Settings, &mut table).is_err());
+    }
+
+    #[test]
+    fn test_load_writes_backup_and_upgrades() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("connections.toml");
+        fs::write(&path, V1_CONNECTIONS).unwrap();
+
+        let table = MigrationService::load(ConfigFileKind::Connections, &path)
+            .unwrap()
+            .unwrap();
+        let id = table["connections"].as_array().unwrap()[0]["id"].clone();
+
+        let backups: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|n| n.starts_with("connections.toml.v1-") && n.ends_with(".bak"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(
+            fs::read_to_string(dir.path().join(&backups[0])).unwrap(),
+            V1_CONNECTIONS
+        );
+
+        // The upgraded file is stable across loads
+        let reloaded = MigrationService::load(ConfigFileKind::Connections, &path)
+            .unwrap()
+            .unwrap();
+        assert_eq!(reloaded["connections"].as_array().unwrap()[0]["id"], id);
+        assert_eq!(
+            MigrationService::check(dir.path()).unwrap()[0].version,
+            CURRENT_SCHEMA_VERSION
+        );
+    }
+
+    #[test]
+    fn test_check_reports_pending() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("connections.toml"), V1_CONNECTIONS).unwrap();
+        fs::write(
+            dir.path().join("settings.toml"),
+            format!(
+                "schema_version = {}\nlanguage = \"en\"\n",
+                CURRENT_SCHEMA_VERSION
+            ),
+        )
+        .unwrap();
+
+        let statuses = MigrationService::check(dir.path()).unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert!(statuses[0].is_pending());
+        assert!(!statuses[1].is_pending());
+
+        // Checking never modifies files
+        assert_eq!(
+            fs::read_to_string(dir.path().join("connections.toml")).unwrap(),
+            V1_CONNECTIONS
+        );
+    }
+}
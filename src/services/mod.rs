@@ -1,10 +1,23 @@
 // Services module
+pub mod audit_service;
 pub mod config_service;
-// Note: key_service is commented out due to russh 0.55.0 API changes
-// pub mod key_service;
+pub mod event_bus;
+pub mod exec_service;
+pub mod group_service;
+pub mod hook_service;
+pub mod import_service;
+pub mod key_service;
+pub mod log_query;
 pub mod log_service;
+pub mod metrics_service;
+pub mod migration_service;
+pub mod notification_service;
 pub mod port_validator;
+pub mod relay_server;
 pub mod session_manager;
+pub mod session_snapshot;
+pub mod sftp_service;
 pub mod ssh_service;
+pub mod template_service;
 pub mod tunnel_service;
 pub mod validation_service;
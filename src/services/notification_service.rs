@@ -0,0 +1,493 @@
+use crate::models::{ConnectionLog, NotificationSink, NotificationTarget};
+use crate::services::config_service::AppSettings;
+use crate::services::event_bus::EventBus;
+use crate::utils::error::{Result, SshToolError};
+use once_cell::sync::OnceCell;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use uuid::Uuid;
+
+/// Longest a webhook request or command may take
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends session events to the configured notification sinks
+///
+/// Each sink sees the events passing its level and event filters, at most
+/// once per rate-limit window for the same connection and event kind.
+pub struct NotificationService {
+    sinks: Vec<NotificationSink>,
+    /// When each (sink, connection, event kind) was last sent
+    last_sent: Mutex<HashMap<(usize, Uuid, &'static str), Instant>>,
+}
+
+impl NotificationService {
+    pub fn new(sinks: Vec<NotificationSink>) -> Self {
+        Self {
+            sinks,
+            last_sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Service for the sinks in the app settings, if any are configured
+    pub fn for_app(settings: &AppSettings) -> Option<Arc<Self>> {
+        if settings.notifications.is_empty() {
+            return None;
+        }
+        Some(Arc::new(Self::new(settings.notifications.clone())))
+    }
+
+    /// Notify about every event published on `bus` from now on
+    pub fn listen(self: Arc<Self>, bus: &EventBus) -> JoinHandle<()> {
+        let mut events = bus.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        let service = self.clone();
+                        // A slow sink must not hold up later events
+                        tokio::spawn(async move {
+                            service.notify(&event.to_log()).await;
+                        });
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Notifications missed {} session events", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Deliver a log entry to every sink that accepts it, returning how many
+    /// deliveries succeeded
+    pub async fn notify(&self, log: &ConnectionLog) -> usize {
+        let deliveries = self
+            .sinks
+            .iter()
+            .enumerate()
+            .filter(|(index, sink)| sink.accepts(log) && self.take_slot(*index, sink, log))
+            .map(|(_, sink)| async move {
+                let result = tokio::time::timeout(DELIVERY_TIMEOUT, deliver(sink, log))
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out").into()));
+                if let Err(e) = &result {
+                    tracing::warn!("Notification via {} failed: {}", sink.describe(), e);
+                }
+                result.is_ok()
+            });
+
+        futures::future::join_all(deliveries)
+            .await
+            .into_iter()
+            .filter(|delivered| *delivered)
+            .count()
+    }
+
+    /// Whether the rate limit lets `log` through to a sink, recording it if so
+    fn take_slot(&self, index: usize, sink: &NotificationSink, log: &ConnectionLog) -> bool {
+        let Ok(mut last_sent) = self.last_sent.lock() else {
+            return false;
+        };
+        let key = (index, log.connection_id, log.event.kind());
+        let now = Instant::now();
+
+        if let Some(sent) = last_sent.get(&key)
+            && now.duration_since(*sent) < Duration::from_secs(sink.rate_limit_seconds)
+        {
+            tracing::debug!(
+                "Rate limited {} notification for {} via {}",
+                key.2,
+                log.connection_name,
+                sink.describe()
+            );
+            return false;
+        }
+        last_sent.insert(key, now);
+        true
+    }
+}
+
+async fn deliver(sink: &NotificationSink, log: &ConnectionLog) -> Result<()> {
+    match &sink.target {
+        NotificationTarget::Webhook { url, headers } => post_webhook(url, headers, log).await,
+        NotificationTarget::Command { command, args } => run_command(command, args, log).await,
+    }
+}
+
+/// POST the log entry as JSON, over TLS for `https://` URLs
+async fn post_webhook(
+    url: &str,
+    headers: &BTreeMap<String, String>,
+    log: &ConnectionLog,
+) -> Result<()> {
+    let target = WebhookUrl::parse(url)?;
+    let body = serde_json::to_string(log).map_err(anyhow::Error::from)?;
+
+    let mut request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\nUser-Agent: ssh-tunnel-manager\r\n",
+        target.path,
+        target.authority,
+        body.len()
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    request.push_str(&body);
+
+    let stream = TcpStream::connect((target.host.as_str(), target.port)).await?;
+    let response = if target.tls {
+        let server_name = ServerName::try_from(target.host.clone()).map_err(|e| {
+            SshToolError::ConfigError(format!("Invalid host in webhook URL {}: {}", url, e))
+        })?;
+        let stream = TlsConnector::from(tls_config()?)
+            .connect(server_name, stream)
+            .await?;
+        exchange(stream, &request).await?
+    } else {
+        exchange(stream, &request).await?
+    };
+    let response = String::from_utf8_lossy(&response);
+    let status = response
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok());
+
+    match status {
+        Some(code) if (200..300).contains(&code) => Ok(()),
+        Some(code) => Err(anyhow::anyhow!("{} answered HTTP {}", url, code).into()),
+        None => Err(anyhow::anyhow!("{} sent no HTTP response", url).into()),
+    }
+}
+
+/// Send a request and read the response until the server closes
+async fn exchange<S>(mut stream: S, request: &str) -> std::io::Result<Vec<u8>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut response = Vec::new();
+    match stream.read_to_end(&mut response).await {
+        Ok(_) => Ok(response),
+        // Many HTTPS servers close without a TLS close_notify; with
+        // `Connection: close` the response is complete all the same
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof && !response.is_empty() => {
+            Ok(response)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// TLS client settings trusting the system's root certificates, loaded once
+fn tls_config() -> Result<Arc<ClientConfig>> {
+    static CONFIG: OnceCell<Arc<ClientConfig>> = OnceCell::new();
+
+    CONFIG
+        .get_or_try_init(|| {
+            let certs = rustls_native_certs::load_native_certs();
+            for error in &certs.errors {
+                tracing::warn!("Could not load a root certificate: {}", error);
+            }
+            let mut roots = RootCertStore::empty();
+            let (added, _) = roots.add_parsable_certificates(certs.certs);
+            if added == 0 {
+                return Err(SshToolError::ConfigError(
+                    "No root certificates found for HTTPS webhooks".to_string(),
+                ));
+            }
+
+            let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(|e| SshToolError::ConfigError(format!("TLS setup failed: {}", e)))?
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            Ok(Arc::new(config))
+        })
+        .cloned()
+}
+
+/// Where a webhook is posted to
+#[derive(Debug, PartialEq)]
+struct WebhookUrl {
+    tls: bool,
+    /// Host name or IP address, without IPv6 brackets
+    host: String,
+    port: u16,
+    /// Host and port as written in the URL, for the `Host` header
+    authority: String,
+    path: String,
+}
+
+impl WebhookUrl {
+    /// Split an `http[s]://host[:port][/path]` URL
+    fn parse(url: &str) -> Result<Self> {
+        let (tls, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            return Err(SshToolError::ConfigError(format!(
+                "Unsupported webhook URL {} (expected http:// or https://)",
+                url
+            )));
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+        // Bracketed IPv6 hosts contain colons of their own
+        let port_separator = match authority.rfind(']') {
+            Some(end) => authority[end..].find(':').map(|i| end + i),
+            None => authority.rfind(':'),
+        };
+        let (host, port) = match port_separator {
+            Some(index) => {
+                let port = authority[index + 1..].parse().map_err(|_| {
+                    SshToolError::ConfigError(format!("Invalid port in webhook URL {}", url))
+                })?;
+                (&authority[..index], port)
+            }
+            None => (authority, if tls { 443 } else { 80 }),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(SshToolError::ConfigError(format!(
+                "Missing host in webhook URL {}",
+                url
+            )));
+        }
+        Ok(Self {
+            tls,
+            host: host.to_string(),
+            port,
+            authority: authority.to_string(),
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Run a command with the event in environment variables
+async fn run_command(command: &str, args: &[String], log: &ConnectionLog) -> Result<()> {
+    let json = serde_json::to_string(log).map_err(anyhow::Error::from)?;
+    let status = tokio::process::Command::new(command)
+        .args(args)
+        .env("SSH_TUNNEL_EVENT", log.event.kind())
+        .env("SSH_TUNNEL_LEVEL", log.level.as_str())
+        .env("SSH_TUNNEL_CONNECTION", &log.connection_name)
+        .env("SSH_TUNNEL_CONNECTION_ID", log.connection_id.to_string())
+        .env(
+            "SSH_TUNNEL_SESSION_ID",
+            log.session_id.map(|id| id.to_string()).unwrap_or_default(),
+        )
+        .env("SSH_TUNNEL_DESCRIPTION", log.event.description())
+        .env(
+            "SSH_TUNNEL_MESSAGE",
+            log.message.as_deref().unwrap_or_default(),
+        )
+        .env("SSH_TUNNEL_TIMESTAMP", log.timestamp.to_rfc3339())
+        .env("SSH_TUNNEL_LOG", json)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .status()
+        .await?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("{} exited with {}", command, status).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionEvent, LogLevel};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    fn dropped(name: &str) -> ConnectionLog {
+        ConnectionLog::new(
+            Uuid::new_v4(),
+            name,
+            LogLevel::Error,
+            ConnectionEvent::ErrorDisconnect,
+        )
+        .with_message("SSH connection closed")
+    }
+
+    /// HTTP server answering `status` and passing each request on
+    async fn http_stub(status: &'static str) -> (String, mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hooks/ssh", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                // Read the head, then as much body as Content-Length says
+                loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length: usize = head
+                            .lines()
+                            .find_map(|l| l.strip_prefix("Content-Length: "))
+                            .and_then(|l| l.parse().ok())
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            break;
+                        }
+                    }
+                    if n == 0 {
+                        break;
+                    }
+                }
+                let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+                stream.write_all(response.as_bytes()).await.unwrap();
+                sender
+                    .send(String::from_utf8_lossy(&request).to_string())
+                    .unwrap();
+            }
+        });
+
+        (url, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_webhook_posts_log_json() {
+        let (url, mut requests) = http_stub("204 No Content").await;
+        let mut sink = NotificationSink::webhook(url.clone());
+        if let NotificationTarget::Webhook { headers, .. } = &mut sink.target {
+            headers.insert("Authorization".to_string(), "Bearer secret".to_string());
+        }
+        let service = NotificationService::new(vec![sink]);
+
+        let log = dropped("prod-db");
+        assert_eq!(service.notify(&log).await, 1);
+
+        let request = requests.recv().await.unwrap();
+        assert!(request.starts_with("POST /hooks/ssh HTTP/1.1\r\n"));
+        let authority = url.trim_start_matches("http://").split('/').next().unwrap();
+        assert!(request.contains(&format!("Host: {}\r\n", authority)));
+        assert!(request.contains("Authorization: Bearer secret\r\n"));
+        let body = request.split_once("\r\n\r\n").unwrap().1;
+        let posted: ConnectionLog = serde_json::from_str(body).unwrap();
+        assert_eq!(posted.id, log.id);
+        assert_eq!(posted.event, ConnectionEvent::ErrorDisconnect);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_error_status_fails() {
+        let (url, _requests) = http_stub("500 Internal Server Error").await;
+        let service = NotificationService::new(vec![NotificationSink::webhook(url)]);
+
+        assert_eq!(service.notify(&dropped("prod-db")).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_notifications_filtered_and_rate_limited() {
+        let (url, mut requests) = http_stub("200 OK").await;
+        let service = NotificationService::new(vec![
+            NotificationSink::webhook(url).with_event("error_disconnect"),
+        ]);
+
+        let log = dropped("prod-db");
+        assert_eq!(service.notify(&log).await, 1);
+        // Same connection and event within the window
+        assert_eq!(service.notify(&log).await, 0);
+        // Another connection has its own window
+        assert_eq!(service.notify(&dropped("staging-db")).await, 1);
+
+        let info = ConnectionLog::new(
+            log.connection_id,
+            "prod-db",
+            LogLevel::Info,
+            ConnectionEvent::Connected,
+        );
+        assert_eq!(service.notify(&info).await, 0);
+
+        requests.recv().await.unwrap();
+        requests.recv().await.unwrap();
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_gets_event_environment() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let out = dir.path().join("event.txt");
+        let script = format!(
+            "printf '%s %s %s' \"$SSH_TUNNEL_EVENT\" \"$SSH_TUNNEL_CONNECTION\" \"$SSH_TUNNEL_MESSAGE\" > {}",
+            out.display()
+        );
+        let service = NotificationService::new(vec![
+            NotificationSink::command("sh", vec!["-c".to_string(), script]).with_rate_limit(0),
+            NotificationSink::command("false", vec![]),
+        ]);
+
+        assert_eq!(service.notify(&dropped("prod-db")).await, 1);
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "error_disconnect prod-db SSH connection closed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_https_webhook_needs_tls() {
+        // A plain HTTP answer is no TLS handshake, so nothing is posted
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("https://{}/hooks/ssh", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                    .await;
+            }
+        });
+        let service = NotificationService::new(vec![NotificationSink::webhook(url)]);
+
+        assert_eq!(service.notify(&dropped("prod-db")).await, 0);
+    }
+
+    #[test]
+    fn test_parse_webhook_url() {
+        let url = WebhookUrl::parse("http://hooks.internal:8080/ssh?x=1").unwrap();
+        assert_eq!(
+            url,
+            WebhookUrl {
+                tls: false,
+                host: "hooks.internal".to_string(),
+                port: 8080,
+                authority: "hooks.internal:8080".to_string(),
+                path: "/ssh?x=1".to_string(),
+            }
+        );
+        let url = WebhookUrl::parse("http://[::1]/").unwrap();
+        assert_eq!((url.host.as_str(), url.port), ("::1", 80));
+        assert_eq!(url.authority, "[::1]");
+        let url = WebhookUrl::parse("https://hooks.slack.com/services/T0/B0/x").unwrap();
+        assert!(url.tls);
+        assert_eq!((url.port, url.authority.as_str()), (443, "hooks.slack.com"));
+        assert_eq!(
+            WebhookUrl::parse("https://[::1]:8443").unwrap().authority,
+            "[::1]:8443"
+        );
+        assert_eq!(WebhookUrl::parse("http://example.com").unwrap().path, "/");
+        assert!(WebhookUrl::parse("ftp://example.com/hook").is_err());
+        assert!(WebhookUrl::parse("http://:80/").is_err());
+    }
+}
@@ -0,0 +1,422 @@
+use crate::models::AuthorizedKey;
+use crate::services::key_service::KeyService;
+use crate::utils::error::{Result, SshToolError};
+use russh::keys::{HashAlg, PublicKey, parse_public_key_base64};
+use russh::server::{Auth, Config, Handler, Msg, Session};
+use russh::{Channel, MethodKind, MethodSet};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// Pause after a failed accept (e.g. out of file descriptors) before the next
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Configuration for the embedded relay server
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+    /// Address to listen on (default: "0.0.0.0")
+    pub bind_address: String,
+    /// Port to listen on (default: 2222)
+    pub port: u16,
+    /// Server host key, generated on first start
+    pub host_key_path: PathBuf,
+    /// authorized_keys file, managed through `KeyService`
+    pub authorized_keys_path: PathBuf,
+}
+
+impl RelayConfig {
+    /// Default configuration with keys stored under `<config_dir>/relay`
+    pub fn new(config_dir: &Path) -> Self {
+        let relay_dir = config_dir.join("relay");
+        Self {
+            bind_address: "0.0.0.0".to_string(),
+            port: 2222,
+            host_key_path: relay_dir.join("ssh_host_ed25519_key"),
+            authorized_keys_path: relay_dir.join("authorized_keys"),
+        }
+    }
+
+    pub fn with_bind_address(mut self, bind_address: impl Into<String>) -> Self {
+        self.bind_address = bind_address.into();
+        self
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+}
+
+/// Minimal SSH server that only accepts port forwarding
+///
+/// Clients authenticate with a public key listed in the authorized_keys file.
+/// Sessions, shells and exec requests are refused; direct-tcpip channels and
+/// tcpip-forward listeners are checked against the key's `permitopen` and
+/// `permitlisten` entries. The authorized_keys file is re-read on every
+/// authentication attempt, so changes apply without a restart.
+pub struct RelayServer {
+    config: RelayConfig,
+    ssh_config: Arc<Config>,
+    host_key_fingerprint: String,
+}
+
+impl RelayServer {
+    /// Create a relay server, loading or generating its host key
+    pub fn new(config: RelayConfig) -> Result<Self> {
+        let host_key = KeyService::load_or_generate_host_key(&config.host_key_path)?;
+        let host_key_fingerprint = host_key
+            .public_key()
+            .fingerprint(HashAlg::Sha256)
+            .to_string();
+
+        let ssh_config = Config {
+            methods: MethodSet::from(&[MethodKind::PublicKey][..]),
+            keys: vec![host_key],
+            auth_rejection_time: Duration::from_secs(1),
+            auth_rejection_time_initial: Some(Duration::ZERO),
+            inactivity_timeout: Some(Duration::from_secs(3600)),
+            keepalive_interval: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+
+        Ok(Self {
+            config,
+            ssh_config: Arc::new(ssh_config),
+            host_key_fingerprint,
+        })
+    }
+
+    pub fn config(&self) -> &RelayConfig {
+        &self.config
+    }
+
+    /// SHA256 fingerprint of the server host key
+    pub fn host_key_fingerprint(&self) -> &str {
+        &self.host_key_fingerprint
+    }
+
+    /// Bind to the configured address and serve until the task is cancelled
+    pub async fn run(&self) -> Result<()> {
+        let bind_addr = format!("{}:{}", self.config.bind_address, self.config.port);
+        let listener = TcpListener::bind(&bind_addr).await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::AddrInUse {
+                SshToolError::PortInUse(self.config.port)
+            } else {
+                SshToolError::TunnelFailed(format!("Failed to bind to {}: {}", bind_addr, e))
+            }
+        })?;
+
+        self.run_on(listener).await
+    }
+
+    /// Serve connections accepted from an existing listener
+    pub async fn run_on(&self, listener: TcpListener) -> Result<()> {
+        tracing::info!(
+            "Relay server listening on {} (host key {})",
+            listener.local_addr()?,
+            self.host_key_fingerprint
+        );
+
+        loop {
+            let (stream, peer_addr) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!("Relay accept error: {}", e);
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                }
+            };
+            let ssh_config = self.ssh_config.clone();
+            let handler = RelayHandler::new(self.config.authorized_keys_path.clone(), peer_addr);
+
+            tokio::spawn(async move {
+                tracing::debug!("Relay connection from {}", peer_addr);
+                match russh::server::run_stream(ssh_config, stream, handler).await {
+                    Ok(session) => {
+                        if let Err(e) = session.await {
+                            tracing::debug!("Relay session {} ended: {}", peer_addr, e);
+                        }
+                    }
+                    Err(e) => tracing::debug!("Relay handshake with {} failed: {}", peer_addr, e),
+                }
+            });
+        }
+    }
+}
+
+/// Per-connection handler enforcing the authorized key's permissions
+struct RelayHandler {
+    authorized_keys_path: PathBuf,
+    peer_addr: SocketAddr,
+    key: Option<AuthorizedKey>,
+    forwards: HashMap<(String, u32), JoinHandle<()>>,
+}
+
+impl RelayHandler {
+    fn new(authorized_keys_path: PathBuf, peer_addr: SocketAddr) -> Self {
+        Self {
+            authorized_keys_path,
+            peer_addr,
+            key: None,
+            forwards: HashMap::new(),
+        }
+    }
+
+    fn find_key(&self, public_key: &PublicKey) -> Option<AuthorizedKey> {
+        match KeyService::load_authorized_keys(&self.authorized_keys_path) {
+            Ok(keys) => keys.into_iter().find(|k| {
+                parse_public_key_base64(&k.key_data)
+                    .is_ok_and(|key| key.key_data() == public_key.key_data())
+            }),
+            Err(e) => {
+                tracing::error!("Failed to load authorized keys: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for RelayHandler {
+    fn drop(&mut self) {
+        for (_, task) in self.forwards.drain() {
+            task.abort();
+        }
+    }
+}
+
+impl Handler for RelayHandler {
+    type Error = russh::Error;
+
+    async fn auth_publickey_offered(
+        &mut self,
+        _user: &str,
+        public_key: &PublicKey,
+    ) -> std::result::Result<Auth, Self::Error> {
+        if self.find_key(public_key).is_some() {
+            Ok(Auth::Accept)
+        } else {
+            Ok(Auth::reject())
+        }
+    }
+
+    async fn auth_publickey(
+        &mut self,
+        user: &str,
+        public_key: &PublicKey,
+    ) -> std::result::Result<Auth, Self::Error> {
+        match self.find_key(public_key) {
+            Some(key) => {
+                tracing::info!(
+                    "Relay: {} authenticated as {} ({})",
+                    self.peer_addr,
+                    user,
+                    public_key.fingerprint(HashAlg::Sha256)
+                );
+                self.key = Some(key);
+                Ok(Auth::Accept)
+            }
+            None => {
+                tracing::warn!("Relay: rejected key from {}", self.peer_addr);
+                Ok(Auth::reject())
+            }
+        }
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> std::result::Result<bool, Self::Error> {
+        tracing::warn!("Relay: refused session channel from {}", self.peer_addr);
+        Ok(false)
+    }
+
+    async fn channel_open_direct_tcpip(
+        &mut self,
+        channel: Channel<Msg>,
+        host_to_connect: &str,
+        port_to_connect: u32,
+        _originator_address: &str,
+        _originator_port: u32,
+        _session: &mut Session,
+    ) -> std::result::Result<bool, Self::Error> {
+        let port = u16::try_from(port_to_connect).unwrap_or(0);
+        let allowed = self
+            .key
+            .as_ref()
+            .is_some_and(|k| k.allows_open(host_to_connect, port));
+        if !allowed {
+            tracing::warn!(
+                "Relay: {} denied open to {}:{}",
+                self.peer_addr,
+                host_to_connect,
+                port_to_connect
+            );
+            return Ok(false);
+        }
+
+        let target = match TcpStream::connect((host_to_connect, port)).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!(
+                    "Relay: failed to connect to {}:{}: {}",
+                    host_to_connect,
+                    port,
+                    e
+                );
+                return Ok(false);
+            }
+        };
+
+        tracing::info!(
+            "Relay: {} opened {}:{}",
+            self.peer_addr,
+            host_to_connect,
+            port
+        );
+        tokio::spawn(async move {
+            let mut channel_stream = channel.into_stream();
+            let mut target = target;
+            let _ = tokio::io::copy_bidirectional(&mut channel_stream, &mut target).await;
+        });
+        Ok(true)
+    }
+
+    async fn tcpip_forward(
+        &mut self,
+        address: &str,
+        port: &mut u32,
+        session: &mut Session,
+    ) -> std::result::Result<bool, Self::Error> {
+        let requested_port = u16::try_from(*port).unwrap_or(0);
+        let allowed = self
+            .key
+            .as_ref()
+            .is_some_and(|k| k.allows_listen(address, requested_port));
+        if !allowed {
+            tracing::warn!(
+                "Relay: {} denied listen on {}:{}",
+                self.peer_addr,
+                address,
+                port
+            );
+            return Ok(false);
+        }
+
+        let bind_host = match address {
+            "" | "localhost" => "127.0.0.1",
+            other => other,
+        };
+        let listener = match TcpListener::bind((bind_host, requested_port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::warn!("Relay: failed to listen on {}:{}: {}", bind_host, port, e);
+                return Ok(false);
+            }
+        };
+        let bound_port = listener.local_addr()?.port();
+        *port = bound_port as u32;
+
+        tracing::info!(
+            "Relay: {} listening on {}:{}",
+            self.peer_addr,
+            bind_host,
+            bound_port
+        );
+
+        let handle = session.handle();
+        let connected_address = address.to_string();
+        let task = tokio::spawn(async move {
+            loop {
+                let (mut stream, origin) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        tracing::warn!("Relay: accept on port {} failed: {}", bound_port, e);
+                        tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                        continue;
+                    }
+                };
+                let handle = handle.clone();
+                let connected_address = connected_address.clone();
+                tokio::spawn(async move {
+                    match handle
+                        .channel_open_forwarded_tcpip(
+                            connected_address,
+                            bound_port as u32,
+                            origin.ip().to_string(),
+                            origin.port() as u32,
+                        )
+                        .await
+                    {
+                        Ok(channel) => {
+                            let mut channel_stream = channel.into_stream();
+                            let _ = tokio::io::copy_bidirectional(&mut channel_stream, &mut stream)
+                                .await;
+                        }
+                        Err(e) => tracing::debug!("Relay: forwarded channel failed: {}", e),
+                    }
+                });
+            }
+        });
+
+        self.forwards.insert((address.to_string(), *port), task);
+        Ok(true)
+    }
+
+    async fn cancel_tcpip_forward(
+        &mut self,
+        address: &str,
+        port: u32,
+        _session: &mut Session,
+    ) -> std::result::Result<bool, Self::Error> {
+        match self.forwards.remove(&(address.to_string(), port)) {
+            Some(task) => {
+                task.abort();
+                tracing::info!("Relay: {} cancelled {}:{}", self.peer_addr, address, port);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_relay_config_defaults() {
+        let config = RelayConfig::new(Path::new("/tmp/stm"));
+        assert_eq!(config.bind_address, "0.0.0.0");
+        assert_eq!(config.port, 2222);
+        assert_eq!(
+            config.authorized_keys_path,
+            PathBuf::from("/tmp/stm/relay/authorized_keys")
+        );
+    }
+
+    #[test]
+    fn test_relay_config_builder() {
+        let config = RelayConfig::new(Path::new("/tmp/stm"))
+            .with_bind_address("127.0.0.1")
+            .with_port(2022);
+        assert_eq!(config.bind_address, "127.0.0.1");
+        assert_eq!(config.port, 2022);
+    }
+
+    #[test]
+    fn test_relay_server_generates_stable_host_key() {
+        let temp_dir = tempdir().unwrap();
+        let config = RelayConfig::new(temp_dir.path());
+
+        let first = RelayServer::new(config.clone()).unwrap();
+        let second = RelayServer::new(config).unwrap();
+        assert!(first.host_key_fingerprint().starts_with("SHA256:"));
+        assert_eq!(first.host_key_fingerprint(), second.host_key_fingerprint());
+    }
+}
@@ -1,12 +1,21 @@
-use crate::models::{ActiveSession, ForwardingConfig, SessionStatus, SshConnection};
-use crate::services::ssh_service::SshSession;
+use crate::models::{
+    ActiveSession, ConnectionEvent, EnvProfile, ForwardingConfig, HookStage, SessionStatus,
+    SshConnection, TrafficHistory,
+};
+use crate::services::event_bus::{EventBus, EventSource};
+use crate::services::hook_service::HookService;
+use crate::services::ssh_service::{SshService, SshSession};
 use crate::services::tunnel_service::{TunnelHandle, TunnelService};
 use crate::utils::error::{Result, SshToolError};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{Mutex, RwLock};
 
+/// How often the monitor samples session and tunnel traffic
+pub const TRAFFIC_SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Data for an active session
 struct SessionData {
     connection_id: uuid::Uuid,
@@ -17,10 +26,18 @@ struct SessionData {
     last_activity: Instant,
     bytes_sent: u64,
     bytes_received: u64,
+    config_changed: bool,
+    events: EventSource,
+    traffic: TrafficHistory,
+    /// Traffic history of each tunnel, by tunnel id
+    tunnel_traffic: HashMap<uuid::Uuid, TrafficHistory>,
 }
 
 impl SessionData {
-    fn new(connection: SshConnection, session: SshSession) -> Self {
+    fn new(connection: SshConnection, session: SshSession, events: EventSource) -> Self {
+        let mut traffic = TrafficHistory::default();
+        traffic.record(chrono::Utc::now(), 0, 0);
+
         Self {
             connection_id: connection.id,
             connection,
@@ -30,6 +47,10 @@ impl SessionData {
             last_activity: Instant::now(),
             bytes_sent: 0,
             bytes_received: 0,
+            config_changed: false,
+            events,
+            traffic,
+            tunnel_traffic: HashMap::new(),
         }
     }
 
@@ -49,6 +70,8 @@ impl SessionData {
             idle_timeout_seconds: self.connection.idle_timeout_seconds.unwrap_or(300),
             bytes_sent: self.bytes_sent,
             bytes_received: self.bytes_received,
+            config_changed: self.config_changed,
+            traffic: self.traffic.clone(),
         }
     }
 
@@ -73,6 +96,37 @@ impl SessionData {
         }
     }
 
+    /// Sync traffic and add a sample to the session's and each tunnel's history
+    fn sample_traffic(&mut self, now: chrono::DateTime<chrono::Utc>) {
+        self.sync_traffic_from_tunnels();
+        self.traffic
+            .record(now, self.bytes_sent, self.bytes_received);
+
+        let running: Vec<_> = self.tunnel_handles.iter().map(|h| h.id).collect();
+        self.tunnel_traffic.retain(|id, _| running.contains(id));
+        for handle in &self.tunnel_handles {
+            let (sent, received) = handle.get_traffic_stats();
+            self.tunnel_traffic
+                .entry(handle.id)
+                .or_default()
+                .record(now, sent, received);
+        }
+    }
+
+    /// Shut the session down, then publish the traffic it carried and `event`
+    async fn close(&mut self, event: ConnectionEvent, message: Option<String>) {
+        self.sync_traffic_from_tunnels();
+        self.shutdown().await;
+        self.events.publish(
+            ConnectionEvent::ForwardingActivity {
+                bytes_sent: self.bytes_sent,
+                bytes_received: self.bytes_received,
+            },
+            None,
+        );
+        self.events.publish(event, message);
+    }
+
     async fn shutdown(&mut self) {
         // Stop all tunnels
         for mut handle in self.tunnel_handles.drain(..) {
@@ -85,11 +139,40 @@ impl SessionData {
     }
 }
 
+/// Traffic of one running forward
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TunnelStats {
+    pub session_id: uuid::Uuid,
+    pub connection_name: String,
+    pub config: ForwardingConfig,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub active_channels: u64,
+    /// Samples taken by the monitor
+    #[serde(default)]
+    pub traffic: TrafficHistory,
+}
+
+/// Forwards started and stopped by [`SessionManager::reconcile`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TunnelChanges {
+    pub added: Vec<ForwardingConfig>,
+    pub removed: Vec<ForwardingConfig>,
+}
+
+impl TunnelChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
 /// Service for managing SSH sessions and their lifecycles
 pub struct SessionManager {
     sessions: Arc<RwLock<HashMap<uuid::Uuid, SessionData>>>,
     idle_timeout: Duration,
     monitor_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    profile: RwLock<Option<EnvProfile>>,
+    events: EventBus,
 }
 
 impl Default for SessionManager {
@@ -106,17 +189,59 @@ impl SessionManager {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             idle_timeout: Duration::from_secs(idle_timeout_seconds),
             monitor_handle: Arc::new(Mutex::new(None)),
+            profile: RwLock::new(None),
+            events: EventBus::new(),
         }
     }
 
+    /// Publish lifecycle events to a shared bus instead of an own one
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Bus the session and tunnel lifecycle events are published to
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Set the profile used to resolve `${VAR}` placeholders
+    ///
+    /// Running sessions are not touched; pass the saved connections to
+    /// `apply_connection_changes` to re-target them.
+    pub async fn set_profile(&self, profile: Option<EnvProfile>) {
+        *self.profile.write().await = profile;
+    }
+
+    /// Builder-style variant of `set_profile` for a new manager
+    pub fn with_profile(mut self, profile: Option<EnvProfile>) -> Self {
+        self.profile = RwLock::new(profile);
+        self
+    }
+
+    /// The profile used to resolve placeholders
+    pub async fn profile(&self) -> Option<EnvProfile> {
+        self.profile.read().await.clone()
+    }
+
+    /// Resolve a connection's placeholders with the current profile
+    pub async fn resolve(&self, connection: &SshConnection) -> Result<SshConnection> {
+        connection.resolve(self.profile.read().await.as_ref())
+    }
+
     /// Start the idle monitoring background task
+    ///
+    /// The monitor also samples traffic every [`TRAFFIC_SAMPLE_INTERVAL`].
     pub async fn start_idle_monitor(&self) {
         let sessions = self.sessions.clone();
         let timeout = self.idle_timeout;
         let check_interval = Duration::from_secs(60); // Check every minute
+        let samples_per_check =
+            (check_interval.as_secs() / TRAFFIC_SAMPLE_INTERVAL.as_secs()).max(1);
 
         let handle = tokio::spawn(async move {
-            let mut interval = tokio::time::interval(check_interval);
+            let mut interval = tokio::time::interval(TRAFFIC_SAMPLE_INTERVAL);
+            let mut samples = 0u64;
 
             loop {
                 interval.tick().await;
@@ -125,29 +250,75 @@ impl SessionManager {
                 let now = Instant::now();
 
                 // Sync traffic statistics from all tunnel handles
+                let sampled_at = chrono::Utc::now();
                 for (_session_id, data) in sessions_guard.iter_mut() {
-                    data.sync_traffic_from_tunnels();
+                    data.sample_traffic(sampled_at);
+                }
+
+                samples += 1;
+                if !samples.is_multiple_of(samples_per_check) {
+                    continue;
                 }
 
+                // Drop sessions the server or network closed; one whose
+                // mutex is held (a running command or hook) is in use, and
+                // checked next time
+                let closed_session_ids: Vec<uuid::Uuid> = sessions_guard
+                    .iter()
+                    .filter(|(_, data)| {
+                        data.session
+                            .try_lock()
+                            .is_ok_and(|session| session.is_closed())
+                    })
+                    .map(|(id, _)| *id)
+                    .collect();
+
                 // Find idle sessions
                 let idle_session_ids: Vec<uuid::Uuid> = sessions_guard
                     .iter()
+                    .filter(|(id, _)| !closed_session_ids.contains(id))
                     .filter(|(_, data)| now.duration_since(data.last_activity) > timeout)
                     .map(|(id, _)| *id)
                     .collect();
 
+                let mut remove = |ids: Vec<uuid::Uuid>| -> Vec<(uuid::Uuid, SessionData)> {
+                    ids.into_iter()
+                        .filter_map(|id| sessions_guard.remove(&id).map(|data| (id, data)))
+                        .collect()
+                };
+                let closed = remove(closed_session_ids);
+                let idle = remove(idle_session_ids);
+                // Closing waits for each session's mutex, so not under the lock
+                drop(sessions_guard);
+
+                for (session_id, mut data) in closed {
+                    tracing::warn!(
+                        "Session {} ({}@{}) was closed by the remote side",
+                        session_id,
+                        data.connection.username,
+                        data.connection.host
+                    );
+                    data.close(
+                        ConnectionEvent::ErrorDisconnect,
+                        Some("SSH connection closed".to_string()),
+                    )
+                    .await;
+                }
+
                 // Shutdown idle sessions
-                for session_id in idle_session_ids {
-                    if let Some(mut data) = sessions_guard.remove(&session_id) {
-                        tracing::info!(
-                            "Closing idle session {} ({}@{})",
-                            session_id,
-                            data.connection.username,
-                            data.connection.host
-                        );
-
-                        data.shutdown().await;
-                    }
+                for (session_id, mut data) in idle {
+                    tracing::info!(
+                        "Closing idle session {} ({}@{})",
+                        session_id,
+                        data.connection.username,
+                        data.connection.host
+                    );
+
+                    data.close(
+                        ConnectionEvent::IdleTimeout,
+                        Some(format!("idle for more than {}s", timeout.as_secs())),
+                    )
+                    .await;
                 }
             }
         });
@@ -174,7 +345,9 @@ impl SessionManager {
         session: SshSession,
     ) -> Result<uuid::Uuid> {
         let session_id = uuid::Uuid::new_v4();
-        let session_data = SessionData::new(connection.clone(), session);
+        let events = self.events.source(&connection).for_session(session_id);
+        session.events().set(events.clone());
+        let session_data = SessionData::new(connection.clone(), session, events.clone());
 
         tracing::info!(
             "Creating session {} for {}@{}:{}",
@@ -185,6 +358,13 @@ impl SessionManager {
         );
 
         self.sessions.write().await.insert(session_id, session_data);
+        events.publish(
+            ConnectionEvent::Connected,
+            Some(format!(
+                "{}@{}:{}",
+                connection.username, connection.host, connection.port
+            )),
+        );
 
         Ok(session_id)
     }
@@ -206,42 +386,293 @@ impl SessionManager {
         Ok(session_id)
     }
 
+    /// Connect to a saved connection and start all of its forwards
+    ///
+    /// Placeholders are resolved with the current profile first; the session
+    /// keeps the resolved connection. The connection's hooks run around each
+    /// step, and a failing hook set to abort disconnects again.
+    pub async fn connect(
+        &self,
+        connection: &SshConnection,
+        password: Option<&str>,
+    ) -> Result<uuid::Uuid> {
+        let connection = self.resolve(connection).await?;
+        let events = self.events.source(&connection);
+        events.publish(
+            ConnectionEvent::ConnectAttempt,
+            Some(format!(
+                "{}@{}:{}",
+                connection.username, connection.host, connection.port
+            )),
+        );
+
+        if let Err(e) = self.run_pre_connect_hooks(&connection).await {
+            events.publish(ConnectionEvent::ConnectionFailed, Some(e.to_string()));
+            return Err(e);
+        }
+
+        let session = match SshService::connect(&connection, password).await {
+            Ok(session) => session,
+            Err(e) => {
+                let event = if matches!(e, SshToolError::AuthenticationFailed(_)) {
+                    ConnectionEvent::AuthFailed
+                } else {
+                    ConnectionEvent::ConnectionFailed
+                };
+                events.publish(event, Some(e.to_string()));
+                return Err(e);
+            }
+        };
+        events.publish(ConnectionEvent::AuthSuccess, Some(session.authentication()));
+
+        let session_id = self.create_session(connection.clone(), session).await?;
+        if let Err(e) = self
+            .run_session_hooks(session_id, HookStage::PostConnect)
+            .await
+        {
+            let _ = self.disconnect_session(session_id).await;
+            return Err(e);
+        }
+        if !connection.forwarding_configs.is_empty()
+            && let Err(e) = self
+                .setup_tunnels(session_id, &connection.forwarding_configs)
+                .await
+        {
+            // Don't leave a half-forwarded session behind
+            let _ = self.disconnect_session(session_id).await;
+            events.publish(ConnectionEvent::ConnectionFailed, Some(e.to_string()));
+            return Err(e);
+        }
+        if let Err(e) = self
+            .run_session_hooks(session_id, HookStage::PostTunnel)
+            .await
+        {
+            let _ = self.disconnect_session(session_id).await;
+            return Err(e);
+        }
+
+        Ok(session_id)
+    }
+
+    /// Run the pre-connect hooks of a connection that is about to connect
+    pub async fn run_pre_connect_hooks(&self, connection: &SshConnection) -> Result<()> {
+        let events = self.events.source(connection);
+        HookService::run_stage(connection, HookStage::PreConnect, None, &events).await
+    }
+
+    /// Run the hooks of `stage` for the connection of a running session
+    pub async fn run_session_hooks(&self, session_id: uuid::Uuid, stage: HookStage) -> Result<()> {
+        let (connection, session, events) = {
+            let sessions = self.sessions.read().await;
+            let data = sessions
+                .get(&session_id)
+                .ok_or_else(|| SshToolError::SessionNotFound(session_id.to_string()))?;
+            (
+                data.connection.clone(),
+                data.session.clone(),
+                data.events.clone(),
+            )
+        };
+        HookService::run_stage(&connection, stage, Some(&session), &events).await
+    }
+
     /// Setup port forwarding tunnels for a session
     pub async fn setup_tunnels(
         &self,
         session_id: uuid::Uuid,
         configs: &[ForwardingConfig],
     ) -> Result<()> {
+        let (session, events) = self.session_parts(session_id).await?;
+
+        for config in configs {
+            tracing::info!("Setting up tunnel: {}", config.description());
+
+            let handle = create_tunnel(&events, session.clone(), config).await?;
+            self.attach_tunnel(session_id, handle, None).await?;
+        }
+
+        tracing::info!(
+            "Setup {} tunnel(s) for session {}",
+            configs.len(),
+            session_id
+        );
+        Ok(())
+    }
+
+    /// Start a single forward on a running session, returning the tunnel id
+    pub async fn add_tunnel(
+        &self,
+        session_id: uuid::Uuid,
+        config: ForwardingConfig,
+    ) -> Result<uuid::Uuid> {
+        if self.tunnel_running(session_id, &config).await? {
+            return Err(already_running(&config));
+        }
+        let (session, events) = self.session_parts(session_id).await?;
+
+        tracing::info!(
+            "Adding tunnel to session {}: {}",
+            session_id,
+            config.description()
+        );
+        let handle = create_tunnel(&events, session, &config).await?;
+        let tunnel_id = handle.id;
+        self.attach_tunnel(session_id, handle, Some(config)).await?;
+
+        Ok(tunnel_id)
+    }
+
+    /// Stop a single forward on a running session, keeping the SSH session
+    ///
+    /// Returns `false` if no such forward was running.
+    pub async fn remove_tunnel(
+        &self,
+        session_id: uuid::Uuid,
+        config: &ForwardingConfig,
+    ) -> Result<bool> {
         let mut sessions = self.sessions.write().await;
 
         let session_data = sessions
             .get_mut(&session_id)
             .ok_or_else(|| SshToolError::SessionNotFound(session_id.to_string()))?;
 
-        let session_arc = session_data.session.clone();
+        let Some(pos) = session_data
+            .tunnel_handles
+            .iter()
+            .position(|h| h.config == *config)
+        else {
+            return Ok(false);
+        };
 
-        for config in configs {
-            tracing::info!("Setting up tunnel: {}", config.description());
+        tracing::info!(
+            "Removing tunnel from session {}: {}",
+            session_id,
+            config.description()
+        );
+        let handle = session_data.tunnel_handles.remove(pos);
+        session_data
+            .connection
+            .forwarding_configs
+            .retain(|c| c != config);
+        session_data.update_activity();
+        let session = session_data.session.clone();
+        // Cancelling a remote forward waits for the server
+        drop(sessions);
+
+        TunnelService::stop_tunnel(&session, handle).await?;
+        Ok(true)
+    }
+
+    /// Session handle and event source of a running session, so network
+    /// I/O on it can run without holding the sessions lock
+    async fn session_parts(
+        &self,
+        session_id: uuid::Uuid,
+    ) -> Result<(Arc<Mutex<SshSession>>, EventSource)> {
+        let sessions = self.sessions.read().await;
+        let data = sessions
+            .get(&session_id)
+            .ok_or_else(|| SshToolError::SessionNotFound(session_id.to_string()))?;
+        Ok((data.session.clone(), data.events.clone()))
+    }
+
+    async fn tunnel_running(
+        &self,
+        session_id: uuid::Uuid,
+        config: &ForwardingConfig,
+    ) -> Result<bool> {
+        let sessions = self.sessions.read().await;
+        let data = sessions
+            .get(&session_id)
+            .ok_or_else(|| SshToolError::SessionNotFound(session_id.to_string()))?;
+        Ok(data.tunnel_handles.iter().any(|h| h.config == *config))
+    }
+
+    /// Keep a started tunnel with its session, adding `config` to the
+    /// session's forwards if given
+    ///
+    /// The tunnel is stopped again if the session went away, or another call
+    /// started the same forward, while it was being set up.
+    async fn attach_tunnel(
+        &self,
+        session_id: uuid::Uuid,
+        mut handle: TunnelHandle,
+        config: Option<ForwardingConfig>,
+    ) -> Result<()> {
+        let mut sessions = self.sessions.write().await;
+        let Some(session_data) = sessions.get_mut(&session_id) else {
+            handle.stop();
+            return Err(SshToolError::SessionNotFound(session_id.to_string()));
+        };
+        if session_data
+            .tunnel_handles
+            .iter()
+            .any(|h| h.config == handle.config)
+        {
+            handle.stop();
+            return Err(already_running(&handle.config));
+        }
 
-            let handle = TunnelService::create_tunnel(session_arc.clone(), config.clone()).await?;
+        session_data.tunnel_handles.push(handle);
+        if let Some(config) = config
+            && !session_data.connection.forwarding_configs.contains(&config)
+        {
+            session_data.connection.forwarding_configs.push(config);
+        }
+        session_data.update_activity();
+        Ok(())
+    }
 
-            session_data.tunnel_handles.push(handle);
-            session_data.update_activity();
+    /// Make a running session's forwards match `configs`
+    ///
+    /// Forwards no longer listed are stopped first, so a forward whose target
+    /// changed can reuse its port; unchanged ones keep running.
+    pub async fn reconcile(
+        &self,
+        session_id: uuid::Uuid,
+        configs: Vec<ForwardingConfig>,
+    ) -> Result<TunnelChanges> {
+        let running: Vec<ForwardingConfig> = {
+            let sessions = self.sessions.read().await;
+            let session_data = sessions
+                .get(&session_id)
+                .ok_or_else(|| SshToolError::SessionNotFound(session_id.to_string()))?;
+            session_data
+                .tunnel_handles
+                .iter()
+                .map(|h| h.config.clone())
+                .collect()
+        };
+
+        let mut changes = TunnelChanges::default();
+        for config in running.iter().filter(|c| !configs.contains(c)) {
+            if self.remove_tunnel(session_id, config).await? {
+                changes.removed.push(config.clone());
+            }
+        }
+        for config in configs.iter().filter(|c| !running.contains(c)) {
+            self.add_tunnel(session_id, config.clone()).await?;
+            changes.added.push(config.clone());
+        }
+
+        if let Some(session_data) = self.sessions.write().await.get_mut(&session_id) {
+            session_data.connection.forwarding_configs = configs;
         }
 
         tracing::info!(
-            "Setup {} tunnel(s) for session {}",
-            configs.len(),
-            session_id
+            "Reconciled session {}: {} tunnel(s) added, {} removed",
+            session_id,
+            changes.added.len(),
+            changes.removed.len()
         );
-        Ok(())
+        Ok(changes)
     }
 
     /// Disconnect a session
     pub async fn disconnect_session(&self, session_id: uuid::Uuid) -> Result<()> {
-        let mut sessions = self.sessions.write().await;
+        let removed = self.sessions.write().await.remove(&session_id);
 
-        if let Some(mut data) = sessions.remove(&session_id) {
+        if let Some(mut data) = removed {
             tracing::info!(
                 "Disconnecting session {} ({}@{})",
                 session_id,
@@ -249,7 +680,7 @@ impl SessionManager {
                 data.connection.host
             );
 
-            data.shutdown().await;
+            data.close(ConnectionEvent::Disconnected, None).await;
 
             Ok(())
         } else {
@@ -288,6 +719,44 @@ impl SessionManager {
             .collect()
     }
 
+    /// Traffic of every running forward
+    pub async fn list_tunnels(&self) -> Vec<TunnelStats> {
+        let sessions = self.sessions.read().await;
+
+        sessions
+            .iter()
+            .flat_map(|(id, data)| {
+                data.tunnel_handles.iter().map(|handle| {
+                    let (bytes_sent, bytes_received) = handle.get_traffic_stats();
+                    TunnelStats {
+                        session_id: *id,
+                        connection_name: data.connection.name.clone(),
+                        config: handle.config.clone(),
+                        bytes_sent,
+                        bytes_received,
+                        active_channels: handle.traffic_counter.active_channels(),
+                        traffic: data
+                            .tunnel_traffic
+                            .get(&handle.id)
+                            .cloned()
+                            .unwrap_or_default(),
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Add a traffic sample to every session and tunnel
+    ///
+    /// The idle monitor does this every [`TRAFFIC_SAMPLE_INTERVAL`]; call it
+    /// directly to keep history without the monitor.
+    pub async fn sample_traffic(&self) {
+        let now = chrono::Utc::now();
+        for data in self.sessions.write().await.values_mut() {
+            data.sample_traffic(now);
+        }
+    }
+
     /// Update session activity timestamp
     pub async fn update_session_activity(&self, session_id: uuid::Uuid) -> Result<()> {
         let mut sessions = self.sessions.write().await;
@@ -319,6 +788,70 @@ impl SessionManager {
         }
     }
 
+    /// Compare running sessions with freshly loaded connections
+    ///
+    /// Renames are applied in place and changed forwards are reconciled on
+    /// the live session. Sessions whose saved connection was otherwise edited
+    /// or removed keep running with the settings they were started with and
+    /// are flagged as needing a reconnect. Returns the ids of newly flagged
+    /// sessions.
+    ///
+    /// Saved connections are resolved with the current profile before the
+    /// comparison, so switching profile re-targets the sessions too; one that
+    /// no longer resolves counts as changed.
+    pub async fn apply_connection_changes(&self, connections: &[SshConnection]) -> Vec<uuid::Uuid> {
+        let mut flagged = Vec::new();
+        let mut to_reconcile = Vec::new();
+        let profile = self.profile.read().await.clone();
+
+        {
+            let mut sessions = self.sessions.write().await;
+            for (session_id, data) in sessions.iter_mut() {
+                let saved = connections
+                    .iter()
+                    .find(|c| c.id == data.connection_id)
+                    .and_then(|c| c.resolve(profile.as_ref()).ok());
+                if let Some(saved) = &saved {
+                    data.connection.name = saved.name.clone();
+                    if saved.forwarding_configs != data.connection.forwarding_configs {
+                        to_reconcile.push((*session_id, saved.forwarding_configs.clone()));
+                    }
+                }
+
+                let changed = saved
+                    .as_ref()
+                    .is_none_or(|saved| settings_changed(&data.connection, saved));
+                if changed && !data.config_changed {
+                    tracing::info!(
+                        "Connection {} changed on disk; session {} needs a reconnect",
+                        data.connection.name,
+                        session_id
+                    );
+                    data.config_changed = true;
+                    flagged.push(*session_id);
+                }
+            }
+        }
+
+        for (session_id, configs) in to_reconcile {
+            if let Err(e) = self.reconcile(session_id, configs).await {
+                tracing::warn!(
+                    "Failed to reconcile tunnels of session {}: {}",
+                    session_id,
+                    e
+                );
+                if let Some(data) = self.sessions.write().await.get_mut(&session_id)
+                    && !data.config_changed
+                {
+                    data.config_changed = true;
+                    flagged.push(session_id);
+                }
+            }
+        }
+
+        flagged
+    }
+
     /// Get session count
     pub async fn session_count(&self) -> usize {
         self.sessions.read().await.len()
@@ -331,20 +864,23 @@ impl SessionManager {
 
     /// Disconnect all sessions
     pub async fn disconnect_all(&self) -> Result<()> {
-        let mut sessions = self.sessions.write().await;
+        let sessions: Vec<_> = self.sessions.write().await.drain().collect();
 
         tracing::info!("Disconnecting all {} session(s)", sessions.len());
 
-        for (session_id, mut data) in sessions.drain() {
-            tracing::info!(
-                "Disconnecting session {} ({}@{})",
-                session_id,
-                data.connection.username,
-                data.connection.host
-            );
+        let closing = sessions
+            .into_iter()
+            .map(|(session_id, mut data)| async move {
+                tracing::info!(
+                    "Disconnecting session {} ({}@{})",
+                    session_id,
+                    data.connection.username,
+                    data.connection.host
+                );
 
-            data.shutdown().await;
-        }
+                data.close(ConnectionEvent::Disconnected, None).await;
+            });
+        futures::future::join_all(closing).await;
 
         Ok(())
     }
@@ -374,6 +910,45 @@ impl Drop for SessionManager {
     }
 }
 
+fn already_running(config: &ForwardingConfig) -> SshToolError {
+    SshToolError::TunnelFailed(format!("{} is already running", config.description()))
+}
+
+/// Start a tunnel, publishing whether it came up
+async fn create_tunnel(
+    events: &EventSource,
+    session: Arc<Mutex<SshSession>>,
+    config: &ForwardingConfig,
+) -> Result<TunnelHandle> {
+    let tunnel_type = config.description();
+    match TunnelService::create_tunnel(session, config.clone()).await {
+        Ok(handle) => {
+            events.publish(ConnectionEvent::TunnelCreated { tunnel_type }, None);
+            Ok(handle)
+        }
+        Err(e) => {
+            events.publish(
+                ConnectionEvent::TunnelFailed { tunnel_type },
+                Some(e.to_string()),
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Whether a saved connection differs from the one a session runs with in
+/// anything that needs a reconnect, i.e. other than its name, timestamps,
+/// forwards and hooks
+fn settings_changed(running: &SshConnection, saved: &SshConnection) -> bool {
+    let mut saved = saved.clone();
+    saved.name = running.name.clone();
+    saved.forwarding_configs = running.forwarding_configs.clone();
+    saved.hooks = running.hooks.clone();
+    saved.created_at = running.created_at;
+    saved.updated_at = running.updated_at;
+    saved != *running
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -388,8 +963,7 @@ mod tests {
     async fn test_session_lifecycle() {
         let manager = SessionManager::new(300);
 
-        // Note: This test requires a mock SSH session
-        // In a real scenario, we would need integration tests with a test SSH server
+        // Note: Sessions backed by a real server are covered in tests/ssh_end_to_end_test.rs
         assert_eq!(manager.session_count().await, 0);
     }
 
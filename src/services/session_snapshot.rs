@@ -0,0 +1,160 @@
+use crate::models::ActiveSession;
+use crate::services::session_manager::{SessionManager, TRAFFIC_SAMPLE_INTERVAL, TunnelStats};
+use crate::utils::error::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// Snapshots not refreshed for this long belong to a process that is gone
+const STALE_AFTER_SECONDS: i64 = 10;
+
+/// Sessions of one running process, shared through a file so other
+/// processes (such as `sessions`) can show them
+///
+/// Sessions only live in the process that opened them; each such process
+/// keeps `<pid>.json` in the sessions directory up to date while it runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub pid: u32,
+    pub updated_at: DateTime<Utc>,
+    pub sessions: Vec<ActiveSession>,
+    #[serde(default)]
+    pub tunnels: Vec<TunnelStats>,
+}
+
+impl SessionSnapshot {
+    /// Current sessions and tunnels of this process
+    pub async fn capture(session_manager: &SessionManager) -> Self {
+        Self {
+            pid: std::process::id(),
+            updated_at: Utc::now(),
+            sessions: session_manager.list_sessions().await,
+            tunnels: session_manager.list_tunnels().await,
+        }
+    }
+
+    /// Tunnels of one session
+    pub fn tunnels_of(&self, session_id: uuid::Uuid) -> impl Iterator<Item = &TunnelStats> {
+        self.tunnels
+            .iter()
+            .filter(move |t| t.session_id == session_id)
+    }
+
+    /// Snapshots of all running processes, oldest process first
+    ///
+    /// Stale and unreadable files are skipped.
+    pub fn load_all(dir: &Path) -> Vec<Self> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let oldest = Utc::now() - Duration::seconds(STALE_AFTER_SECONDS);
+
+        let mut snapshots: Vec<Self> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let content = std::fs::read_to_string(&path).ok()?;
+                serde_json::from_str::<Self>(&content).ok()
+            })
+            .filter(|snapshot| snapshot.updated_at >= oldest)
+            .collect();
+        snapshots.sort_by_key(|s| s.pid);
+        snapshots
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Readers never see a half-written file
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(
+            &temp,
+            serde_json::to_vec(self).map_err(anyhow::Error::from)?,
+        )?;
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    }
+}
+
+/// Keeps this process's snapshot file current; removes it when dropped
+pub struct SnapshotPublisher {
+    path: PathBuf,
+    task: JoinHandle<()>,
+}
+
+impl SnapshotPublisher {
+    /// Write a snapshot of `session_manager` to `dir` every
+    /// [`TRAFFIC_SAMPLE_INTERVAL`]
+    pub fn start(session_manager: Arc<SessionManager>, dir: &Path) -> Self {
+        let path = dir.join(format!("{}.json", std::process::id()));
+        let target = path.clone();
+
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TRAFFIC_SAMPLE_INTERVAL);
+            loop {
+                interval.tick().await;
+                let snapshot = SessionSnapshot::capture(&session_manager).await;
+                if let Err(e) = snapshot.write(&target) {
+                    tracing::warn!("Failed to write session snapshot: {}", e);
+                }
+            }
+        });
+
+        Self { path, task }
+    }
+}
+
+impl Drop for SnapshotPublisher {
+    fn drop(&mut self) {
+        self.task.abort();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_snapshots_skip_stale_files() {
+        let dir = TempDir::new().unwrap();
+        let manager = SessionManager::new(300);
+
+        let current = SessionSnapshot::capture(&manager).await;
+        current.write(&dir.path().join("1.json")).unwrap();
+
+        let mut stale = current.clone();
+        stale.pid = 2;
+        stale.updated_at = Utc::now() - Duration::minutes(5);
+        stale.write(&dir.path().join("2.json")).unwrap();
+        std::fs::write(dir.path().join("3.json"), "not json").unwrap();
+
+        let snapshots = SessionSnapshot::load_all(dir.path());
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].pid, std::process::id());
+        assert!(SessionSnapshot::load_all(&dir.path().join("missing")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_publisher_removes_its_file() {
+        let dir = TempDir::new().unwrap();
+        let publisher = SnapshotPublisher::start(Arc::new(SessionManager::new(300)), dir.path());
+
+        let path = dir.path().join(format!("{}.json", std::process::id()));
+        for _ in 0..50 {
+            if path.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(SessionSnapshot::load_all(dir.path()).len(), 1);
+
+        drop(publisher);
+        assert!(!path.exists());
+    }
+}
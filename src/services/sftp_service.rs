@@ -0,0 +1,597 @@
+use crate::models::AuthorizedKey;
+use crate::services::ssh_service::SshSession;
+use crate::utils::error::{Result, SshToolError};
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::{FileAttributes, OpenFlags};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+
+/// Bytes read per request while copying a file
+const CHUNK_SIZE: usize = 32 * 1024;
+
+/// Where `sshd` looks for login keys, relative to the home directory that
+/// SFTP paths start from
+const SSH_DIR: &str = ".ssh";
+const AUTHORIZED_KEYS: &str = ".ssh/authorized_keys";
+
+/// How a transfer treats directories and partial files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TransferOptions {
+    /// Copy a directory with everything in it
+    pub recursive: bool,
+
+    /// Continue files already partly at the destination instead of copying
+    /// them again
+    pub resume: bool,
+}
+
+/// Progress of a transfer, reported as it runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferProgress {
+    /// Copying of a file of `size` bytes starts, at `offset` when resuming
+    Started {
+        path: String,
+        size: u64,
+        offset: u64,
+    },
+    /// More bytes of the current file were copied
+    Copied(u64),
+    /// The current file is complete
+    Finished,
+}
+
+/// What a finished transfer copied
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransferSummary {
+    pub files: usize,
+    pub directories: usize,
+
+    /// Bytes copied, not counting those resumed from
+    pub bytes: u64,
+}
+
+/// Copies files over the SFTP subsystem of a session
+///
+/// Like `scp`, a transfer into an existing directory copies into it under
+/// the source's name. Permission bits are preserved; symbolic links and
+/// special files are skipped.
+pub struct SftpService {
+    sftp: SftpSession,
+}
+
+impl SftpService {
+    /// Start the SFTP subsystem on a new channel of `session`
+    pub async fn open(session: &SshSession) -> Result<Self> {
+        let channel = session
+            .channel_open_session()
+            .await
+            .map_err(|e| SshToolError::SshConnectionFailed(e.to_string()))?;
+        channel
+            .request_subsystem(true, "sftp")
+            .await
+            .map_err(|e| SshToolError::SshConnectionFailed(e.to_string()))?;
+        let sftp = SftpSession::new(channel.into_stream())
+            .await
+            .map_err(|e| SshToolError::TransferFailed(format!("SFTP unavailable: {}", e)))?;
+        Ok(Self { sftp })
+    }
+
+    /// Copy `remote` from the server to `local`
+    pub async fn download<F>(
+        &self,
+        remote: &str,
+        local: &Path,
+        options: TransferOptions,
+        mut progress: F,
+    ) -> Result<TransferSummary>
+    where
+        F: FnMut(TransferProgress),
+    {
+        let attrs = self
+            .sftp
+            .metadata(remote)
+            .await
+            .map_err(|e| remote_error(remote, e))?;
+        let target = if local.is_dir() {
+            local.join(remote_file_name(remote)?)
+        } else {
+            local.to_path_buf()
+        };
+
+        let mut summary = TransferSummary::default();
+        if attrs.is_dir() {
+            if !options.recursive {
+                return Err(is_directory(remote));
+            }
+            self.download_dir(
+                remote,
+                &target,
+                &attrs,
+                options,
+                &mut progress,
+                &mut summary,
+            )
+            .await?;
+        } else {
+            self.download_file(
+                remote,
+                &target,
+                &attrs,
+                options,
+                &mut progress,
+                &mut summary,
+            )
+            .await?;
+        }
+        Ok(summary)
+    }
+
+    /// Copy `local` to `remote` on the server
+    pub async fn upload<F>(
+        &self,
+        local: &Path,
+        remote: &str,
+        options: TransferOptions,
+        mut progress: F,
+    ) -> Result<TransferSummary>
+    where
+        F: FnMut(TransferProgress),
+    {
+        let metadata = tokio::fs::metadata(local).await?;
+        let target = match self.sftp.metadata(remote).await {
+            Ok(attrs) if attrs.is_dir() => join_remote(remote, &local_file_name(local)?),
+            _ => remote.to_string(),
+        };
+
+        let mut summary = TransferSummary::default();
+        if metadata.is_dir() {
+            if !options.recursive {
+                return Err(is_directory(&local.display().to_string()));
+            }
+            self.upload_dir(local, &target, options, &mut progress, &mut summary)
+                .await?;
+        } else {
+            self.upload_file(
+                local,
+                &target,
+                &metadata,
+                options,
+                &mut progress,
+                &mut summary,
+            )
+            .await?;
+        }
+        Ok(summary)
+    }
+
+    /// Add a public key line to the user's `~/.ssh/authorized_keys` unless
+    /// the key is listed there already; returns whether it was added
+    ///
+    /// The directory and file are created as 0700 and 0600. Existing ones
+    /// lose group and world write access, which `sshd` refuses to trust.
+    pub async fn authorize_key(&self, public_key: &str) -> Result<bool> {
+        let key = AuthorizedKey::parse(public_key)?;
+
+        match self.sftp.metadata(SSH_DIR).await {
+            Ok(attrs) if attrs.is_dir() => {
+                self.restrict_permissions(SSH_DIR, attrs.permissions, 0o700)
+                    .await?
+            }
+            Ok(_) => {
+                return Err(SshToolError::TransferFailed(format!(
+                    "~/{} is not a directory",
+                    SSH_DIR
+                )));
+            }
+            Err(_) => {
+                self.sftp
+                    .create_dir(SSH_DIR)
+                    .await
+                    .map_err(|e| remote_error(SSH_DIR, e))?;
+                self.restrict_permissions(SSH_DIR, None, 0o700).await?;
+            }
+        }
+
+        let (existing, permissions) = match self.sftp.metadata(AUTHORIZED_KEYS).await {
+            Ok(attrs) => {
+                let content = self
+                    .sftp
+                    .read(AUTHORIZED_KEYS)
+                    .await
+                    .map_err(|e| remote_error(AUTHORIZED_KEYS, e))?;
+                (content, attrs.permissions)
+            }
+            Err(_) => (Vec::new(), None),
+        };
+        let listed = String::from_utf8_lossy(&existing)
+            .lines()
+            .any(|line| line.split_whitespace().any(|field| field == key.key_data));
+
+        if !listed {
+            let mut line = String::new();
+            if !existing.is_empty() && !existing.ends_with(b"\n") {
+                line.push('\n');
+            }
+            line.push_str(public_key.trim());
+            line.push('\n');
+
+            let mut file = self
+                .sftp
+                .open_with_flags(AUTHORIZED_KEYS, OpenFlags::WRITE | OpenFlags::CREATE)
+                .await
+                .map_err(|e| remote_error(AUTHORIZED_KEYS, e))?;
+            file.seek(SeekFrom::Start(existing.len() as u64)).await?;
+            file.write_all(line.as_bytes()).await?;
+            file.shutdown().await?;
+            tracing::info!("Added key {} to ~/{}", key.key_type, AUTHORIZED_KEYS);
+        }
+        self.restrict_permissions(AUTHORIZED_KEYS, permissions, 0o600)
+            .await?;
+        Ok(!listed)
+    }
+
+    /// Close the subsystem channel
+    pub async fn close(self) -> Result<()> {
+        self.sftp
+            .close()
+            .await
+            .map_err(|e| SshToolError::TransferFailed(e.to_string()))
+    }
+
+    async fn download_dir<F: FnMut(TransferProgress)>(
+        &self,
+        remote: &str,
+        local: &Path,
+        attrs: &FileAttributes,
+        options: TransferOptions,
+        progress: &mut F,
+        summary: &mut TransferSummary,
+    ) -> Result<()> {
+        // Walked with a stack rather than recursion; directory permissions
+        // are set last, so a read-only directory can still be filled
+        let mut pending = vec![(remote.to_string(), local.to_path_buf(), attrs.permissions)];
+        let mut created = Vec::new();
+        while let Some((remote_dir, local_dir, permissions)) = pending.pop() {
+            tokio::fs::create_dir_all(&local_dir).await?;
+            summary.directories += 1;
+
+            let entries = self
+                .sftp
+                .read_dir(remote_dir.as_str())
+                .await
+                .map_err(|e| remote_error(&remote_dir, e))?;
+            for entry in entries {
+                let name = entry.file_name();
+                // A name is all a listing may add to a path; anything else
+                // could write outside the target
+                if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+                    tracing::warn!("Skipping entry {:?} of {}", name, remote_dir);
+                    continue;
+                }
+                let remote_path = join_remote(&remote_dir, &name);
+                let local_path = local_dir.join(&name);
+                let attrs = entry.metadata();
+                if attrs.is_dir() {
+                    pending.push((remote_path, local_path, attrs.permissions));
+                } else if attrs.is_regular() {
+                    self.download_file(
+                        &remote_path,
+                        &local_path,
+                        &attrs,
+                        options,
+                        progress,
+                        summary,
+                    )
+                    .await?;
+                } else {
+                    tracing::warn!("Skipping {}: not a regular file", remote_path);
+                }
+            }
+            created.push((local_dir, permissions));
+        }
+
+        for (dir, permissions) in created.iter().rev() {
+            set_local_permissions(dir, *permissions)?;
+        }
+        Ok(())
+    }
+
+    async fn download_file<F: FnMut(TransferProgress)>(
+        &self,
+        remote: &str,
+        local: &Path,
+        attrs: &FileAttributes,
+        options: TransferOptions,
+        progress: &mut F,
+        summary: &mut TransferSummary,
+    ) -> Result<()> {
+        let size = attrs.size.unwrap_or(0);
+        let existing = match tokio::fs::metadata(local).await {
+            Ok(metadata) if metadata.is_file() => metadata.len(),
+            _ => 0,
+        };
+        let offset = resume_offset(options, existing, size);
+        progress(TransferProgress::Started {
+            path: remote.to_string(),
+            size,
+            offset,
+        });
+
+        if offset < size || offset == 0 {
+            let mut source = self
+                .sftp
+                .open(remote)
+                .await
+                .map_err(|e| remote_error(remote, e))?;
+            let mut dest = if offset > 0 {
+                source.seek(SeekFrom::Start(offset)).await?;
+                tokio::fs::OpenOptions::new()
+                    .append(true)
+                    .open(local)
+                    .await?
+            } else {
+                tokio::fs::File::create(local).await?
+            };
+            summary.bytes += copy(&mut source, &mut dest, progress).await?;
+            dest.flush().await?;
+            source.shutdown().await?;
+        }
+        set_local_permissions(local, attrs.permissions)?;
+
+        summary.files += 1;
+        progress(TransferProgress::Finished);
+        Ok(())
+    }
+
+    async fn upload_dir<F: FnMut(TransferProgress)>(
+        &self,
+        local: &Path,
+        remote: &str,
+        options: TransferOptions,
+        progress: &mut F,
+        summary: &mut TransferSummary,
+    ) -> Result<()> {
+        let mut pending = vec![(local.to_path_buf(), remote.to_string())];
+        let mut created = Vec::new();
+        while let Some((local_dir, remote_dir)) = pending.pop() {
+            let exists = matches!(self.sftp.metadata(remote_dir.as_str()).await, Ok(attrs) if attrs.is_dir());
+            if !exists {
+                self.sftp
+                    .create_dir(remote_dir.as_str())
+                    .await
+                    .map_err(|e| remote_error(&remote_dir, e))?;
+            }
+            summary.directories += 1;
+
+            let mut entries = tokio::fs::read_dir(&local_dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let local_path = entry.path();
+                let remote_path = join_remote(&remote_dir, &entry.file_name().to_string_lossy());
+                let file_type = entry.file_type().await?;
+                if file_type.is_dir() {
+                    pending.push((local_path, remote_path));
+                } else if file_type.is_file() {
+                    let metadata = entry.metadata().await?;
+                    self.upload_file(
+                        &local_path,
+                        &remote_path,
+                        &metadata,
+                        options,
+                        progress,
+                        summary,
+                    )
+                    .await?;
+                } else {
+                    tracing::warn!("Skipping {}: not a regular file", local_path.display());
+                }
+            }
+            let metadata = tokio::fs::metadata(&local_dir).await?;
+            created.push((remote_dir, local_permissions(&metadata)));
+        }
+
+        for (dir, permissions) in created.iter().rev() {
+            self.set_remote_permissions(dir, *permissions).await?;
+        }
+        Ok(())
+    }
+
+    async fn upload_file<F: FnMut(TransferProgress)>(
+        &self,
+        local: &Path,
+        remote: &str,
+        metadata: &std::fs::Metadata,
+        options: TransferOptions,
+        progress: &mut F,
+        summary: &mut TransferSummary,
+    ) -> Result<()> {
+        let size = metadata.len();
+        let existing = match self.sftp.metadata(remote).await {
+            Ok(attrs) if attrs.is_regular() => attrs.size.unwrap_or(0),
+            _ => 0,
+        };
+        let offset = resume_offset(options, existing, size);
+        progress(TransferProgress::Started {
+            path: local.display().to_string(),
+            size,
+            offset,
+        });
+
+        if offset < size || offset == 0 {
+            let flags = if offset > 0 {
+                OpenFlags::WRITE
+            } else {
+                OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE
+            };
+            let mut dest = self
+                .sftp
+                .open_with_flags(remote, flags)
+                .await
+                .map_err(|e| remote_error(remote, e))?;
+            let mut source = tokio::fs::File::open(local).await?;
+            if offset > 0 {
+                dest.seek(SeekFrom::Start(offset)).await?;
+                source.seek(SeekFrom::Start(offset)).await?;
+            }
+            summary.bytes += copy(&mut source, &mut dest, progress).await?;
+            dest.shutdown().await?;
+        }
+        self.set_remote_permissions(remote, local_permissions(metadata))
+            .await?;
+
+        summary.files += 1;
+        progress(TransferProgress::Finished);
+        Ok(())
+    }
+
+    /// Give a new path `mode`, and take group and world write access off an
+    /// existing one with `permissions`
+    async fn restrict_permissions(
+        &self,
+        path: &str,
+        permissions: Option<u32>,
+        mode: u32,
+    ) -> Result<()> {
+        match permissions {
+            None => self.set_remote_permissions(path, Some(mode)).await,
+            Some(permissions) if permissions & 0o022 != 0 => {
+                self.set_remote_permissions(path, Some(permissions & !0o022))
+                    .await
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    async fn set_remote_permissions(&self, path: &str, permissions: Option<u32>) -> Result<()> {
+        let Some(permissions) = permissions else {
+            return Ok(());
+        };
+        let attrs = FileAttributes {
+            permissions: Some(permissions & 0o777),
+            ..FileAttributes::empty()
+        };
+        self.sftp
+            .set_metadata(path, attrs)
+            .await
+            .map_err(|e| remote_error(path, e))
+    }
+}
+
+/// Where a copy continues: past what is already at the destination when
+/// resuming, unless that is longer than the source and so not a prefix of it
+fn resume_offset(options: TransferOptions, existing: u64, size: u64) -> u64 {
+    if options.resume && existing <= size {
+        existing
+    } else {
+        0
+    }
+}
+
+async fn copy<R, W, F>(reader: &mut R, writer: &mut W, progress: &mut F) -> Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FnMut(TransferProgress),
+{
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut copied = 0;
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(copied);
+        }
+        writer.write_all(&buf[..n]).await?;
+        copied += n as u64;
+        progress(TransferProgress::Copied(n as u64));
+    }
+}
+
+fn join_remote(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+fn remote_file_name(path: &str) -> Result<String> {
+    match path.trim_end_matches('/').rsplit('/').next() {
+        Some(name) if !name.is_empty() && name != "." && name != ".." => Ok(name.to_string()),
+        _ => Err(SshToolError::TransferFailed(format!(
+            "{}: name a local target to copy to",
+            path
+        ))),
+    }
+}
+
+fn local_file_name(path: &Path) -> Result<String> {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            SshToolError::TransferFailed(format!(
+                "{}: name a remote target to copy to",
+                path.display()
+            ))
+        })
+}
+
+fn remote_error(path: &str, error: russh_sftp::client::error::Error) -> SshToolError {
+    SshToolError::TransferFailed(format!("{}: {}", path, error))
+}
+
+fn is_directory(path: &str) -> SshToolError {
+    SshToolError::TransferFailed(format!("{} is a directory (copy it recursively)", path))
+}
+
+#[cfg(unix)]
+fn local_permissions(metadata: &std::fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn local_permissions(_metadata: &std::fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn set_local_permissions(path: &Path, permissions: Option<u32>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    if let Some(permissions) = permissions {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(permissions & 0o777))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_local_permissions(_path: &Path, _permissions: Option<u32>) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_offset() {
+        let resume = TransferOptions {
+            resume: true,
+            ..Default::default()
+        };
+        assert_eq!(resume_offset(resume, 40, 100), 40);
+        assert_eq!(resume_offset(resume, 100, 100), 100);
+        // Longer than the source: not a partial copy of it
+        assert_eq!(resume_offset(resume, 120, 100), 0);
+        assert_eq!(resume_offset(TransferOptions::default(), 40, 100), 0);
+    }
+
+    #[test]
+    fn test_remote_paths() {
+        assert_eq!(
+            join_remote("/var/backups/", "db.sql"),
+            "/var/backups/db.sql"
+        );
+        assert_eq!(join_remote("/", "etc"), "/etc");
+        assert_eq!(remote_file_name("/var/backups/").unwrap(), "backups");
+        assert_eq!(remote_file_name("dump.sql").unwrap(), "dump.sql");
+        assert!(remote_file_name("/").is_err());
+        assert!(remote_file_name("..").is_err());
+    }
+}
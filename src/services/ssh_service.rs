@@ -1,21 +1,187 @@
 use crate::models::forwarding::RemoteForwarding;
-use crate::models::{AuthMethod, JumpHost, SshConnection};
+use crate::models::{AuthMethod, ConnectionEvent, JumpHost, SshConnection};
+use crate::services::event_bus::EventSlot;
+use crate::services::key_service::KeyService;
+use crate::services::tunnel_service::TrafficCounter;
 use crate::utils::error::{Result, SshToolError};
 use russh::client::{self, AuthResult, Handle, Msg}; // client types
 use russh::{Channel, ChannelMsg, Disconnect};
 // Note: In russh 0.55.0, key types are re-exported in russh::keys
 use russh::keys::{PrivateKey, PrivateKeyWithHashAlg, PublicKey};
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::RwLock;
 
+/// Shared remote forwards configuration (used across session and tunnels)
+pub type SharedRemoteForwards = Arc<RwLock<Vec<RemoteRoute>>>;
+
+/// A remote forward served by the handler and the traffic it carried
+#[derive(Debug, Clone)]
+pub struct RemoteRoute {
+    pub forward: RemoteForwarding,
+    pub traffic: TrafficCounter,
+}
+
+impl RemoteRoute {
+    pub fn new(forward: RemoteForwarding) -> Self {
+        Self {
+            forward,
+            traffic: TrafficCounter::new(),
+        }
+    }
+}
+
 /// SSH client session handle
-pub type SshSession = Handle<SshClientHandler>;
+///
+/// Dereferences to the russh [`Handle`]. It also keeps the remote forwards
+/// the session's handler serves, so `-R` forwards can be added or removed
+/// while connected, and the slot its channel events are published to.
+pub struct SshSession {
+    handle: Handle<SshClientHandler>,
+    remote_forwards: SharedRemoteForwards,
+    events: EventSlot,
+    key_fingerprint: Option<String>,
+}
 
-/// Shared remote forwards configuration (used across session and tunnels)
-#[allow(dead_code)]
-pub type SharedRemoteForwards = Arc<RwLock<Vec<RemoteForwarding>>>;
+impl SshSession {
+    pub fn new(
+        handle: Handle<SshClientHandler>,
+        remote_forwards: SharedRemoteForwards,
+        events: EventSlot,
+    ) -> Self {
+        Self {
+            handle,
+            remote_forwards,
+            events,
+            key_fingerprint: None,
+        }
+    }
+
+    /// Record the key the session authenticated with
+    pub fn with_key_fingerprint(mut self, fingerprint: impl Into<String>) -> Self {
+        self.key_fingerprint = Some(fingerprint.into());
+        self
+    }
+
+    /// SHA256 fingerprint of the key used for public key authentication
+    pub fn key_fingerprint(&self) -> Option<&str> {
+        self.key_fingerprint.as_deref()
+    }
+
+    /// How the session authenticated, e.g. `publickey SHA256:...`
+    pub fn authentication(&self) -> String {
+        match &self.key_fingerprint {
+            Some(fingerprint) => format!("publickey {}", fingerprint),
+            None => "password".to_string(),
+        }
+    }
+
+    /// Where events of this session's channels are published
+    pub fn events(&self) -> EventSlot {
+        self.events.clone()
+    }
+
+    /// Route connections the server forwards on `forward.remote_port`
+    ///
+    /// Replaces any existing entry for the same remote port, keeping its
+    /// traffic counter, which is returned.
+    pub async fn add_remote_forward(&self, forward: RemoteForwarding) -> TrafficCounter {
+        let mut forwards = self.remote_forwards.write().await;
+        let traffic = match forwards
+            .iter()
+            .position(|r| r.forward.remote_port == forward.remote_port)
+        {
+            Some(index) => forwards.remove(index).traffic,
+            None => TrafficCounter::new(),
+        };
+        forwards.push(RemoteRoute {
+            forward,
+            traffic: traffic.clone(),
+        });
+        traffic
+    }
+
+    /// Stop routing connections forwarded on `remote_port`
+    pub async fn remove_remote_forward(&self, remote_port: u16) {
+        self.remote_forwards
+            .write()
+            .await
+            .retain(|r| r.forward.remote_port != remote_port);
+    }
+}
+
+impl Deref for SshSession {
+    type Target = Handle<SshClientHandler>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
+
+impl DerefMut for SshSession {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.handle
+    }
+}
+
+/// Output of a command run with [`SshService::execute_command`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// Exit status, unless the server did not report one
+    pub exit_status: Option<u32>,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.exit_status == Some(0)
+    }
+}
+
+/// Which output stream of a remote command data came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// A command running on the server, started with
+/// [`SshService::start_command`]
+///
+/// It has its own channel, so the session can be used (and unlocked) while
+/// its output is read.
+pub struct RemoteCommand {
+    channel: Channel<Msg>,
+    exit_status: Option<u32>,
+}
+
+impl RemoteCommand {
+    /// Next chunk of output, or `None` once the command has finished
+    pub async fn next(&mut self) -> Option<(OutputStream, Vec<u8>)> {
+        // The exit status may come before or after EOF; wait for the close
+        while let Some(msg) = self.channel.wait().await {
+            match msg {
+                ChannelMsg::Data { data } => return Some((OutputStream::Stdout, data.to_vec())),
+                ChannelMsg::ExtendedData { data, .. } => {
+                    return Some((OutputStream::Stderr, data.to_vec()));
+                }
+                ChannelMsg::ExitStatus { exit_status } => self.exit_status = Some(exit_status),
+                ChannelMsg::Close => break,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Exit status, once [`next`](Self::next) has returned `None`; `None`
+    /// if the server did not report one
+    pub fn exit_status(&self) -> Option<u32> {
+        self.exit_status
+    }
+}
 
 /// SSH service for managing connections
 pub struct SshService;
@@ -64,6 +230,8 @@ impl SshService {
             SshClientHandler::new()
         };
 
+        let remote_forwards = sh.remote_forwards.clone();
+        let events = sh.events.clone();
         let mut session = client::connect(Arc::new(config), (host, port), sh)
             .await
             .map_err(|e| SshToolError::SshConnectionFailed(e.to_string()))?;
@@ -81,7 +249,7 @@ impl SshService {
         }
 
         tracing::info!("Successfully authenticated with password");
-        Ok(session)
+        Ok(SshSession::new(session, remote_forwards, events))
     }
 
     /// Connect to SSH server with public key authentication
@@ -150,10 +318,14 @@ impl SshService {
             SshClientHandler::new()
         };
 
+        let remote_forwards = sh.remote_forwards.clone();
+        let events = sh.events.clone();
         let mut session = client::connect(Arc::new(config), (host, port), sh)
             .await
             .map_err(|e| SshToolError::SshConnectionFailed(e.to_string()))?;
 
+        let fingerprint = KeyService::fingerprint(key.public_key());
+
         // In russh 0.55.0, authenticate_publickey expects PrivateKeyWithHashAlg
         let key_with_alg = PrivateKeyWithHashAlg::new(Arc::new(key), None);
         let auth_res = session
@@ -168,8 +340,8 @@ impl SshService {
             ));
         }
 
-        tracing::info!("Successfully authenticated with public key");
-        Ok(session)
+        tracing::info!("Successfully authenticated with public key {}", fingerprint);
+        Ok(SshSession::new(session, remote_forwards, events).with_key_fingerprint(fingerprint))
     }
 
     /// Connect using configuration
@@ -316,13 +488,12 @@ impl SshService {
         Self::connect(destination, dest_password.as_deref()).await
     }
 
-    /// Execute a command on the remote server
-    #[allow(dead_code)]
-    pub async fn execute_command(
-        session: &mut SshSession,
-        command: &str,
-    ) -> Result<(String, String)> {
-        let mut channel = session
+    /// Start a command on the remote server
+    ///
+    /// The command is published as a [`ConnectionEvent::CommandExecuted`]
+    /// event of the session.
+    pub async fn start_command(session: &SshSession, command: &str) -> Result<RemoteCommand> {
+        let channel = session
             .channel_open_session()
             .await
             .map_err(|e| SshToolError::SshConnectionFailed(e.to_string()))?;
@@ -331,28 +502,69 @@ impl SshService {
             .exec(true, command)
             .await
             .map_err(|e| SshToolError::SshConnectionFailed(e.to_string()))?;
+        session.events.publish(
+            ConnectionEvent::CommandExecuted {
+                command: command.to_string(),
+            },
+            None,
+        );
 
-        let mut stdout = String::new();
-        let mut stderr = String::new();
+        Ok(RemoteCommand {
+            channel,
+            exit_status: None,
+        })
+    }
 
-        loop {
-            match channel.wait().await {
-                Some(ChannelMsg::Data { ref data }) => {
-                    stdout.push_str(&String::from_utf8_lossy(data));
-                }
-                Some(ChannelMsg::ExtendedData { ref data, .. }) => {
-                    stderr.push_str(&String::from_utf8_lossy(data));
-                }
-                Some(ChannelMsg::Eof) | Some(ChannelMsg::ExitStatus { .. }) => {
-                    break;
-                }
-                Some(ChannelMsg::Close) => break,
-                None => break,
-                _ => {}
+    /// Start an interactive login shell on a pseudo-terminal of type `term`
+    /// and size `cols` x `rows`
+    ///
+    /// The returned channel carries the terminal both ways; send
+    /// `window_change` when the local terminal is resized.
+    pub async fn open_shell(
+        session: &SshSession,
+        term: &str,
+        cols: u32,
+        rows: u32,
+    ) -> Result<Channel<Msg>> {
+        let channel = session
+            .channel_open_session()
+            .await
+            .map_err(|e| SshToolError::SshConnectionFailed(e.to_string()))?;
+
+        channel
+            .request_pty(true, term, cols, rows, 0, 0, &[])
+            .await
+            .map_err(|e| SshToolError::SshConnectionFailed(e.to_string()))?;
+        channel
+            .request_shell(true)
+            .await
+            .map_err(|e| SshToolError::SshConnectionFailed(e.to_string()))?;
+
+        session.events.publish(
+            ConnectionEvent::ShellOpened {
+                term: term.to_string(),
+            },
+            Some(format!("{}x{}", cols, rows)),
+        );
+        tracing::info!("Opened {} shell ({}x{})", term, cols, rows);
+        Ok(channel)
+    }
+
+    /// Execute a command on the remote server, collecting its output
+    pub async fn execute_command(session: &mut SshSession, command: &str) -> Result<CommandOutput> {
+        let mut remote = Self::start_command(session, command).await?;
+
+        let mut output = CommandOutput::default();
+        while let Some((stream, data)) = remote.next().await {
+            let text = String::from_utf8_lossy(&data);
+            match stream {
+                OutputStream::Stdout => output.stdout.push_str(&text),
+                OutputStream::Stderr => output.stderr.push_str(&text),
             }
         }
+        output.exit_status = remote.exit_status();
 
-        Ok((stdout, stderr))
+        Ok(output)
     }
 
     /// Disconnect from SSH server
@@ -376,7 +588,9 @@ pub struct SshClientHandler {
     pub expected_fingerprint: Option<String>,
     /// Remote forwarding configurations
     /// Shared across async tasks to handle incoming forwarded connections
-    pub remote_forwards: Arc<RwLock<Vec<RemoteForwarding>>>,
+    pub remote_forwards: SharedRemoteForwards,
+    /// Where events of forwarded channels are published
+    pub events: EventSlot,
 }
 
 impl SshClientHandler {
@@ -385,6 +599,7 @@ impl SshClientHandler {
             verify_host_keys: false,
             expected_fingerprint: None,
             remote_forwards: Arc::new(RwLock::new(Vec::new())),
+            events: EventSlot::default(),
         }
     }
 
@@ -394,6 +609,7 @@ impl SshClientHandler {
             verify_host_keys: true,
             expected_fingerprint,
             remote_forwards: Arc::new(RwLock::new(Vec::new())),
+            events: EventSlot::default(),
         }
     }
 
@@ -403,14 +619,17 @@ impl SshClientHandler {
         Self {
             verify_host_keys: false,
             expected_fingerprint: None,
-            remote_forwards: Arc::new(RwLock::new(remote_forwards)),
+            remote_forwards: Arc::new(RwLock::new(
+                remote_forwards.into_iter().map(RemoteRoute::new).collect(),
+            )),
+            events: EventSlot::default(),
         }
     }
 
     /// Add a remote forward configuration
     pub async fn add_remote_forward(&self, forward: RemoteForwarding) {
         let mut forwards = self.remote_forwards.write().await;
-        forwards.push(forward);
+        forwards.push(RemoteRoute::new(forward));
     }
 
     /// Clear all remote forward configurations
@@ -495,6 +714,7 @@ impl client::Handler for SshClientHandler {
         let connected_address = connected_address.to_string();
         let originator_address = originator_address.to_string();
         let remote_forwards = self.remote_forwards.clone();
+        let events = self.events.clone();
 
         async move {
             tracing::info!(
@@ -507,14 +727,17 @@ impl client::Handler for SshClientHandler {
 
             // Find matching remote forward configuration
             let forwards = remote_forwards.read().await;
-            let forward_config = forwards
+            let route = forwards
                 .iter()
-                .find(|f| f.remote_port == connected_port as u16)
+                .find(|r| r.forward.remote_port == connected_port as u16)
                 .cloned();
             drop(forwards);
 
-            match forward_config {
-                Some(config) => {
+            match route {
+                Some(RemoteRoute {
+                    forward: config,
+                    traffic,
+                }) => {
                     // Spawn task to handle this connection
                     let local_addr = format!("{}:{}", config.local_host, config.local_port);
                     tracing::info!(
@@ -523,21 +746,34 @@ impl client::Handler for SshClientHandler {
                         local_addr
                     );
 
+                    let origin = format!("from {}:{}", originator_address, originator_port);
+
                     // Connect to local service
                     match tokio::net::TcpStream::connect(&local_addr).await {
                         Ok(local_stream) => {
                             tracing::debug!("Connected to local service {}", local_addr);
+                            events.publish(
+                                ConnectionEvent::ChannelOpened {
+                                    target: local_addr.clone(),
+                                },
+                                Some(origin),
+                            );
 
                             // Start bidirectional forwarding
                             tokio::spawn(async move {
-                                if let Err(e) =
-                                    Self::forward_bidirectional(channel, local_stream).await
-                                {
+                                let result =
+                                    Self::forward_bidirectional(channel, local_stream, traffic)
+                                        .await;
+                                if let Err(e) = &result {
                                     tracing::error!(
                                         "Remote forward bidirectional transfer failed: {}",
                                         e
                                     );
                                 }
+                                events.publish(
+                                    ConnectionEvent::ChannelClosed { target: local_addr },
+                                    result.err().map(|e| e.to_string()),
+                                );
                             });
 
                             Ok(())
@@ -548,6 +784,10 @@ impl client::Handler for SshClientHandler {
                                 local_addr,
                                 e
                             );
+                            events.publish(
+                                ConnectionEvent::ChannelClosed { target: local_addr },
+                                Some(format!("{}: {}", origin, e)),
+                            );
                             Err(russh::Error::Disconnect)
                         }
                     }
@@ -569,7 +809,9 @@ impl SshClientHandler {
     async fn forward_bidirectional(
         mut channel: Channel<Msg>,
         local_stream: tokio::net::TcpStream,
+        traffic: TrafficCounter,
     ) -> std::result::Result<(), russh::Error> {
+        let _channel = traffic.open_channel();
         let (mut local_read, mut local_write) = tokio::io::split(local_stream);
 
         // Buffer for reading data
@@ -592,6 +834,7 @@ impl SshClientHandler {
                                 tracing::error!("Failed to send data to SSH channel: {}", e);
                                 return Err(e);
                             }
+                            traffic.add_sent(n as u64);
                         }
                         Err(e) => {
                             tracing::error!("Failed to read from local stream: {}", e);
@@ -609,6 +852,7 @@ impl SshClientHandler {
                                 tracing::error!("Failed to write to local stream: {}", e);
                                 return Err(russh::Error::IO(e));
                             }
+                            traffic.add_received(data.len() as u64);
                         }
                         Some(ChannelMsg::Eof) => {
                             // SSH channel closed
@@ -644,11 +888,10 @@ mod tests {
 
     #[test]
     fn test_ssh_client_handler() {
-        let _handler = SshClientHandler::new();
-        // Just ensure it can be created
-        assert!(true);
+        let handler = SshClientHandler::new();
+        assert!(!handler.verify_host_keys);
+        assert!(handler.expected_fingerprint.is_none());
     }
 
-    // Note: Integration tests for actual SSH connections would require a test SSH server
-    // Those should be in integration tests with proper setup
+    // Note: Tests against a live server are in tests/ssh_end_to_end_test.rs
 }
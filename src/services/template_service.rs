@@ -0,0 +1,349 @@
+use crate::models::{
+    ConnectionTemplate, ForwardingConfig, ParameterType, SshConnection, TemplateParameter,
+};
+use crate::services::validation_service::ValidationService;
+use crate::utils::error::{Result, SshToolError};
+use crate::utils::ssh_command_parser::SshCommandParser;
+use crate::utils::ssh_config_parser::bracket_ipv6;
+use std::collections::HashMap;
+
+/// Service for filling in parameterised templates
+pub struct TemplateService;
+
+impl TemplateService {
+    /// Check a value against the parameter's type
+    pub fn validate_parameter(parameter: &TemplateParameter, value: &str) -> Result<()> {
+        let invalid = |reason: &str| {
+            SshToolError::TemplateError(format!("{}: {} ({})", parameter.name, reason, value))
+        };
+
+        match parameter.kind {
+            ParameterType::Text if value.trim().is_empty() => Err(invalid("value is empty")),
+            ParameterType::Text => Ok(()),
+            ParameterType::Host => ValidationService::new()
+                .validate_host(value)
+                .map_err(|_| invalid("not a valid host")),
+            ParameterType::Port => match value.parse::<u16>() {
+                Ok(port) if port > 0 => Ok(()),
+                _ => Err(invalid("not a valid port")),
+            },
+        }
+    }
+
+    /// Values for every parameter, taken from `given` or the defaults
+    ///
+    /// Every value is validated; parameters with neither are reported
+    /// together in one error.
+    pub fn resolve_values(
+        template: &ConnectionTemplate,
+        given: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>> {
+        if let Some(unknown) = given
+            .keys()
+            .find(|name| !template.parameters.iter().any(|p| &p.name == *name))
+        {
+            return Err(SshToolError::TemplateError(format!(
+                "unknown parameter: {}",
+                unknown
+            )));
+        }
+
+        let mut values = HashMap::new();
+        let mut missing = Vec::new();
+        for parameter in &template.parameters {
+            match given.get(&parameter.name).or(parameter.default.as_ref()) {
+                Some(value) => {
+                    Self::validate_parameter(parameter, value)?;
+                    values.insert(parameter.name.clone(), value.clone());
+                }
+                None => missing.push(parameter.name.clone()),
+            }
+        }
+
+        if missing.is_empty() {
+            Ok(values)
+        } else {
+            Err(SshToolError::TemplateError(format!(
+                "missing values for: {}",
+                missing.join(", ")
+            )))
+        }
+    }
+
+    /// Replace `{name}` placeholders with parameter values
+    ///
+    /// `${VAR}` profile placeholders are left for connect time.
+    pub fn fill(text: &str, values: &HashMap<String, String>) -> Result<String> {
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find('{') {
+            let is_variable = rest[..start].ends_with('$');
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let name = after.find('}').map(|end| &after[..end]);
+
+            match name {
+                Some(name) if !is_variable && is_parameter_name(name) => {
+                    let value = values.get(name).ok_or_else(|| {
+                        SshToolError::TemplateError(format!("unknown parameter: {}", name))
+                    })?;
+                    output.push_str(value);
+                    rest = &after[name.len() + 1..];
+                }
+                _ => {
+                    output.push('{');
+                    rest = after;
+                }
+            }
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+
+    /// Create a connection from a template and validated parameter values
+    pub fn instantiate(
+        template: &ConnectionTemplate,
+        name: &str,
+        host: &str,
+        username: &str,
+        values: &HashMap<String, String>,
+    ) -> Result<SshConnection> {
+        let mut connection = SshConnection::new(name, host, username)
+            .with_port(template.default_port)
+            .with_auth_method(template.default_auth_method.clone());
+
+        for preset in &template.forwarding_presets {
+            connection = connection.with_forwarding(preset.clone());
+        }
+        // IPv6 values need brackets to be read back out of a forward spec
+        let forward_values: HashMap<String, String> = values
+            .iter()
+            .map(|(name, value)| (name.clone(), bracket_ipv6(value)))
+            .collect();
+        for spec in &template.forwards {
+            connection =
+                connection.with_forwarding(parse_forward(&Self::fill(spec, &forward_values)?)?);
+        }
+        Ok(connection)
+    }
+
+    /// Template that recreates a saved connection's settings
+    ///
+    /// The ports and target hosts of its forwards become parameters with the
+    /// connection's values as defaults, suffixed `_2`, `_3`... when there is
+    /// more than one forward.
+    pub fn from_connection(
+        name: &str,
+        description: &str,
+        connection: &SshConnection,
+    ) -> ConnectionTemplate {
+        let mut template = ConnectionTemplate::new(name, description)
+            .with_port(connection.port)
+            .with_username(connection.username.clone())
+            .with_auth_method(connection.auth_method.clone());
+
+        let numbered = connection.forwarding_configs.len() > 1;
+        for (index, forward) in connection.forwarding_configs.iter().enumerate() {
+            let suffix = if numbered && index > 0 {
+                format!("_{}", index + 1)
+            } else {
+                String::new()
+            };
+            let mut param = |base: &str, kind: ParameterType, default: String| {
+                let name = format!("{}{}", base, suffix);
+                template
+                    .parameters
+                    .push(TemplateParameter::new(&name, kind).with_default(default));
+                format!("{{{}}}", name)
+            };
+
+            let spec = match forward {
+                ForwardingConfig::Local(local) => format!(
+                    "-L {}:{}:{}:{}",
+                    bracket_ipv6(&local.bind_address),
+                    param(
+                        "local_port",
+                        ParameterType::Port,
+                        local.local_port.to_string()
+                    ),
+                    param(
+                        "remote_host",
+                        ParameterType::Host,
+                        local.remote_host.clone()
+                    ),
+                    local.remote_port
+                ),
+                ForwardingConfig::Remote(remote) => format!(
+                    "-R {}:{}:{}",
+                    param(
+                        "remote_port",
+                        ParameterType::Port,
+                        remote.remote_port.to_string()
+                    ),
+                    bracket_ipv6(&remote.local_host),
+                    param(
+                        "local_port",
+                        ParameterType::Port,
+                        remote.local_port.to_string()
+                    )
+                ),
+                ForwardingConfig::Dynamic(dynamic) => format!(
+                    "-D {}:{}",
+                    bracket_ipv6(&dynamic.bind_address),
+                    param(
+                        "local_port",
+                        ParameterType::Port,
+                        dynamic.local_port.to_string()
+                    )
+                ),
+            };
+            template.forwards.push(spec);
+        }
+        template
+    }
+}
+
+/// Parse a forward spec like `-L 8080:localhost:80`
+fn parse_forward(spec: &str) -> Result<ForwardingConfig> {
+    let (flag, arg) = spec
+        .trim()
+        .split_once(char::is_whitespace)
+        .map(|(flag, arg)| (flag, arg.trim()))
+        .unwrap_or((spec.trim(), ""));
+
+    match flag {
+        "-L" => SshCommandParser::parse_local_forward(arg).map(ForwardingConfig::Local),
+        "-R" => SshCommandParser::parse_remote_forward(arg).map(ForwardingConfig::Remote),
+        "-D" => SshCommandParser::parse_dynamic_forward(arg).map(ForwardingConfig::Dynamic),
+        _ => Err(SshToolError::TemplateError(format!(
+            "forward must start with -L, -R or -D: {}",
+            spec
+        ))),
+    }
+}
+
+fn is_parameter_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionTemplate, DynamicForwarding, LocalForwarding};
+
+    fn db_template() -> ConnectionTemplate {
+        ConnectionTemplate::new("DB", "Database via bastion")
+            .with_port(2222)
+            .with_parameter(
+                TemplateParameter::new("local_port", ParameterType::Port).with_default("15432"),
+            )
+            .with_parameter(TemplateParameter::new("db_host", ParameterType::Host))
+            .with_forward_spec("-L {local_port}:{db_host}:5432")
+            .with_forward_spec("-D 1080")
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_values_uses_defaults_and_validates() {
+        let template = db_template();
+
+        let resolved =
+            TemplateService::resolve_values(&template, &values(&[("db_host", "db.internal")]))
+                .unwrap();
+        assert_eq!(resolved["local_port"], "15432");
+
+        let missing = TemplateService::resolve_values(&template, &HashMap::new());
+        assert!(matches!(missing, Err(SshToolError::TemplateError(e)) if e.contains("db_host")));
+
+        let bad_port = values(&[("db_host", "db"), ("local_port", "99999")]);
+        assert!(TemplateService::resolve_values(&template, &bad_port).is_err());
+
+        let unknown = values(&[("db_host", "db"), ("typo", "x")]);
+        assert!(TemplateService::resolve_values(&template, &unknown).is_err());
+    }
+
+    #[test]
+    fn test_fill_keeps_profile_variables() {
+        let filled = TemplateService::fill(
+            "{db_host}.${ENV}.example.com",
+            &values(&[("db_host", "db"), ("ENV", "wrong")]),
+        )
+        .unwrap();
+        assert_eq!(filled, "db.${ENV}.example.com");
+        assert!(TemplateService::fill("{nope}", &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_instantiate_builds_forwards() {
+        let template = db_template();
+        let resolved =
+            TemplateService::resolve_values(&template, &values(&[("db_host", "db.internal")]))
+                .unwrap();
+        let conn =
+            TemplateService::instantiate(&template, "db", "bastion", "deploy", &resolved).unwrap();
+
+        assert_eq!(conn.port, 2222);
+        assert_eq!(conn.forwarding_configs.len(), 2);
+        assert!(matches!(
+            &conn.forwarding_configs[0],
+            ForwardingConfig::Local(l) if l.local_port == 15432 && l.remote_host == "db.internal"
+        ));
+        assert!(matches!(
+            &conn.forwarding_configs[1],
+            ForwardingConfig::Dynamic(_)
+        ));
+    }
+
+    #[test]
+    fn test_from_connection_round_trip() {
+        let conn = SshConnection::new("api", "bastion", "deploy")
+            .with_forwarding(ForwardingConfig::local(13306, "mysql.internal", 3306))
+            .with_forwarding(ForwardingConfig::remote(8080, "localhost", 3000));
+
+        let template = TemplateService::from_connection("api", "", &conn);
+        let names: Vec<_> = template
+            .parameters
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["local_port", "remote_host", "remote_port_2", "local_port_2"]
+        );
+
+        let resolved = TemplateService::resolve_values(&template, &HashMap::new()).unwrap();
+        let copy = TemplateService::instantiate(&template, "copy", "bastion", "deploy", &resolved)
+            .unwrap();
+        assert_eq!(copy.forwarding_configs, conn.forwarding_configs);
+    }
+
+    #[test]
+    fn test_from_connection_round_trip_ipv6() {
+        let mut local = LocalForwarding::new(13306, "fd00::5", 3306);
+        local.bind_address = "::1".to_string();
+        let conn = SshConnection::new("v6", "bastion", "deploy")
+            .with_forwarding(ForwardingConfig::Local(local))
+            .with_forwarding(ForwardingConfig::remote(8080, "::1", 3000))
+            .with_forwarding(ForwardingConfig::Dynamic(
+                DynamicForwarding::new(1080).with_bind_address("::"),
+            ));
+
+        let template = TemplateService::from_connection("v6", "", &conn);
+        assert_eq!(
+            template.forwards[0],
+            "-L [::1]:{local_port}:{remote_host}:3306"
+        );
+
+        let resolved = TemplateService::resolve_values(&template, &HashMap::new()).unwrap();
+        let copy = TemplateService::instantiate(&template, "copy", "bastion", "deploy", &resolved)
+            .unwrap();
+        assert_eq!(copy.forwarding_configs, conn.forwarding_configs);
+    }
+}
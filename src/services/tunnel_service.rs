@@ -1,4 +1,6 @@
-use crate::models::{DynamicForwarding, ForwardingConfig, LocalForwarding, RemoteForwarding};
+use crate::models::{
+    ConnectionEvent, DynamicForwarding, ForwardingConfig, LocalForwarding, RemoteForwarding,
+};
 use crate::services::ssh_service::SshSession;
 use crate::utils::error::{Result, SshToolError};
 use std::sync::Arc;
@@ -8,11 +10,17 @@ use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 
+/// Address remote forwards ask the server to listen on, as `ssh -R` does
+/// without a bind address; the server decides (e.g. through `GatewayPorts`)
+/// whether that means loopback only
+const REMOTE_FORWARD_ADDRESS: &str = "localhost";
+
 /// Traffic statistics counter
 #[derive(Debug, Clone)]
 pub struct TrafficCounter {
     bytes_sent: Arc<AtomicU64>,
     bytes_received: Arc<AtomicU64>,
+    active_channels: Arc<AtomicU64>,
 }
 
 impl Default for TrafficCounter {
@@ -26,6 +34,7 @@ impl TrafficCounter {
         Self {
             bytes_sent: Arc::new(AtomicU64::new(0)),
             bytes_received: Arc::new(AtomicU64::new(0)),
+            active_channels: Arc::new(AtomicU64::new(0)),
         }
     }
 
@@ -50,12 +59,31 @@ impl TrafficCounter {
         let received = self.bytes_received.swap(0, Ordering::Relaxed);
         (sent, received)
     }
+
+    /// Count a channel as active until the returned guard is dropped
+    pub fn open_channel(&self) -> ChannelGuard {
+        self.active_channels.fetch_add(1, Ordering::Relaxed);
+        ChannelGuard(self.active_channels.clone())
+    }
+
+    /// Channels currently forwarding data
+    pub fn active_channels(&self) -> u64 {
+        self.active_channels.load(Ordering::Relaxed)
+    }
+}
+
+/// Keeps a channel counted in [`TrafficCounter::active_channels`]
+pub struct ChannelGuard(Arc<AtomicU64>);
+
+impl Drop for ChannelGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
 /// Handle for a running tunnel
 pub struct TunnelHandle {
     pub id: uuid::Uuid,
-    #[allow(dead_code)]
     pub config: ForwardingConfig,
     pub traffic_counter: TrafficCounter,
     task: Option<JoinHandle<()>>,
@@ -186,8 +214,14 @@ impl TunnelService {
         traffic_counter: TrafficCounter,
     ) -> Result<()> {
         let session_guard = session.lock().await;
-
-        let mut channel = session_guard
+        let events = session_guard.events();
+        let target = format!("{}:{}", remote_host, remote_port);
+        let origin = local_stream
+            .peer_addr()
+            .map(|addr| format!("from {}", addr))
+            .ok();
+
+        let channel = session_guard
             .channel_open_direct_tcpip(remote_host, remote_port as u32, "localhost", 0)
             .await
             .map_err(|e| {
@@ -195,14 +229,47 @@ impl TunnelService {
                     "Failed to open channel to {}:{}: {}",
                     remote_host, remote_port, e
                 ))
-            })?;
+            });
 
         drop(session_guard); // Release the lock
 
+        let channel = match channel {
+            Ok(channel) => channel,
+            Err(e) => {
+                events.publish(
+                    ConnectionEvent::ChannelClosed { target },
+                    Some(e.to_string()),
+                );
+                return Err(e);
+            }
+        };
+        events.publish(
+            ConnectionEvent::ChannelOpened {
+                target: target.clone(),
+            },
+            origin,
+        );
+
+        let result = Self::copy_through_channel(channel, local_stream, traffic_counter).await;
+        events.publish(
+            ConnectionEvent::ChannelClosed { target },
+            result.as_ref().err().map(|e| e.to_string()),
+        );
+        result
+    }
+
+    /// Copy data both ways between a local stream and a channel until either
+    /// side closes, counting the bytes
+    async fn copy_through_channel(
+        mut channel: russh::Channel<russh::client::Msg>,
+        local_stream: &mut TcpStream,
+        traffic_counter: TrafficCounter,
+    ) -> Result<()> {
+        let _channel = traffic_counter.open_channel();
+
         // Bidirectional copy with traffic tracking
         let (mut local_read, mut local_write) = local_stream.split();
         let mut buf_local = vec![0u8; 8192];
-        let _buf_channel = vec![0u8; 8192];
 
         loop {
             tokio::select! {
@@ -261,11 +328,14 @@ impl TunnelService {
 
         let mut session_guard = session.lock().await;
 
+        // Route the server's forwarded-tcpip channels for this port
+        let traffic_counter = session_guard.add_remote_forward(config.clone()).await;
+
         // Request remote port forwarding on the SSH server
         // The server will start listening on remote_port and forward connections
         // to our client via forwarded-tcpip channels
         session_guard
-            .tcpip_forward("0.0.0.0", config.remote_port as u32)
+            .tcpip_forward(REMOTE_FORWARD_ADDRESS, config.remote_port as u32)
             .await
             .map_err(|e| {
                 SshToolError::TunnelFailed(format!(
@@ -283,7 +353,6 @@ impl TunnelService {
 
         let remote_port = config.remote_port;
         let session_clone = Arc::clone(&session);
-        let traffic_counter = TrafficCounter::new();
 
         // Background task to monitor the session
         // Incoming connections are automatically handled by the Handler's callback
@@ -408,8 +477,14 @@ impl TunnelService {
         tracing::debug!("SOCKS5 connecting to {}:{}", dest_host, dest_port);
 
         let session_guard = session.lock().await;
-
-        let mut channel = session_guard
+        let events = session_guard.events();
+        let target = format!("{}:{}", dest_host, dest_port);
+        let origin = stream
+            .peer_addr()
+            .map(|addr| format!("from {} (SOCKS)", addr))
+            .ok();
+
+        let channel = session_guard
             .channel_open_direct_tcpip(&dest_host, dest_port as u32, "localhost", 0)
             .await
             .map_err(|e| {
@@ -417,50 +492,33 @@ impl TunnelService {
                     "Failed to connect to {}:{}: {}",
                     dest_host, dest_port, e
                 ))
-            })?;
+            });
 
         drop(session_guard);
 
-        // Bidirectional copy with traffic tracking
-        let (mut local_read, mut local_write) = stream.split();
-        let mut buf_local = vec![0u8; 8192];
-
-        loop {
-            tokio::select! {
-                result = local_read.read(&mut buf_local) => {
-                    match result {
-                        Ok(0) => break,
-                        Ok(n) => {
-                            channel.data(&buf_local[..n]).await
-                                .map_err(|e| SshToolError::TunnelFailed(e.to_string()))?;
-                            // Track bytes sent through SSH tunnel
-                            traffic_counter.add_sent(n as u64);
-                        }
-                        Err(e) => {
-                            tracing::debug!("SOCKS read error: {}", e);
-                            break;
-                        }
-                    }
-                }
-                message = channel.wait() => {
-                    use russh::ChannelMsg;
-                    match message {
-                        Some(ChannelMsg::Data { ref data }) => {
-                            local_write.write_all(data).await
-                                .map_err(|e| SshToolError::TunnelFailed(e.to_string()))?;
-                            // Track bytes received from SSH tunnel
-                            traffic_counter.add_received(data.len() as u64);
-                        }
-                        Some(ChannelMsg::Eof) | Some(ChannelMsg::Close) | None => {
-                            break;
-                        }
-                        _ => {}
-                    }
-                }
+        let channel = match channel {
+            Ok(channel) => channel,
+            Err(e) => {
+                events.publish(
+                    ConnectionEvent::ChannelClosed { target },
+                    Some(e.to_string()),
+                );
+                return Err(e);
             }
-        }
+        };
+        events.publish(
+            ConnectionEvent::ChannelOpened {
+                target: target.clone(),
+            },
+            origin,
+        );
 
-        Ok(())
+        let result = Self::copy_through_channel(channel, stream, traffic_counter).await;
+        events.publish(
+            ConnectionEvent::ChannelClosed { target },
+            result.as_ref().err().map(|e| e.to_string()),
+        );
+        result
     }
 
     /// Perform SOCKS5 handshake
@@ -543,6 +601,35 @@ impl TunnelService {
         Ok((dest_host, dest_port))
     }
 
+    /// Stop a tunnel without closing its SSH session
+    ///
+    /// Remote forwards also ask the server to stop listening on their port.
+    pub async fn stop_tunnel(
+        session: &Arc<Mutex<SshSession>>,
+        mut handle: TunnelHandle,
+    ) -> Result<()> {
+        handle.stop();
+
+        if let ForwardingConfig::Remote(remote) = &handle.config {
+            let session_guard = session.lock().await;
+            session_guard
+                .remove_remote_forward(remote.remote_port)
+                .await;
+            session_guard
+                .cancel_tcpip_forward(REMOTE_FORWARD_ADDRESS, remote.remote_port as u32)
+                .await
+                .map_err(|e| {
+                    SshToolError::TunnelFailed(format!(
+                        "Failed to cancel remote forward on port {}: {}",
+                        remote.remote_port, e
+                    ))
+                })?;
+            tracing::info!("Cancelled remote forward on port {}", remote.remote_port);
+        }
+
+        Ok(())
+    }
+
     /// Create a tunnel based on forwarding config
     pub async fn create_tunnel(
         session: Arc<Mutex<SshSession>>,
@@ -579,8 +666,21 @@ mod tests {
             task,
         );
 
-        assert!(handle.is_running() || true); // May already be finished
+        // May already be finished, so only the stopped state is asserted
         handle.stop();
         assert!(!handle.is_running());
     }
+
+    #[test]
+    fn test_traffic_counter_active_channels() {
+        let counter = TrafficCounter::new();
+        let first = counter.open_channel();
+        let second = counter.clone().open_channel();
+        assert_eq!(counter.active_channels(), 2);
+
+        drop(first);
+        assert_eq!(counter.active_channels(), 1);
+        drop(second);
+        assert_eq!(counter.active_channels(), 0);
+    }
 }
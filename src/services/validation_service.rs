@@ -1,6 +1,8 @@
 #![allow(dead_code)]
 
+use crate::models::{EnvProfile, SshConnection};
 use crate::utils::error::{Result, SshToolError};
+use crate::utils::interpolate::has_placeholders;
 use std::net::{IpAddr, SocketAddr};
 use std::path::Path;
 use tokio::net::TcpListener;
@@ -78,6 +80,11 @@ impl ValidationService {
             return Err(SshToolError::InvalidHost("empty host".to_string()));
         }
 
+        // Placeholders are checked once resolved, see `validate_variables`
+        if has_placeholders(host) {
+            return Ok(());
+        }
+
         // Check if valid IP address
         if host.parse::<IpAddr>().is_ok() {
             return Ok(());
@@ -148,6 +155,18 @@ impl ValidationService {
 
         Ok(())
     }
+
+    /// Check that every `${VAR}` placeholder in a connection resolves with
+    /// `profile` or the process environment
+    ///
+    /// The error lists all unresolved variable names.
+    pub fn validate_variables(
+        &self,
+        connection: &SshConnection,
+        profile: Option<&EnvProfile>,
+    ) -> Result<()> {
+        connection.resolve(profile).map(|_| ())
+    }
 }
 
 impl Default for ValidationService {
@@ -291,6 +310,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::default_constructed_unit_structs)]
     fn test_service_default() {
         let service = ValidationService::default();
         assert!(service.validate_port_range(22).is_ok());
@@ -355,4 +375,18 @@ mod tests {
             .await;
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_validate_variables() {
+        let service = ValidationService::new();
+        let conn = SshConnection::new("DB", "db.${SSH_TM_ENV}.example.com", "user");
+        let profile = EnvProfile::new("staging").with_var("SSH_TM_ENV", "staging");
+
+        assert!(service.validate_host(&conn.host).is_ok());
+        assert!(service.validate_variables(&conn, Some(&profile)).is_ok());
+        assert!(matches!(
+            service.validate_variables(&conn, None),
+            Err(SshToolError::UnresolvedVariables(names)) if names == vec!["SSH_TM_ENV"]
+        ));
+    }
 }
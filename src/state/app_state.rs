@@ -1,11 +1,19 @@
 #![allow(dead_code)]
 
-use crate::models::{ActiveSession, SshConnection};
-use crate::services::config_service::ConfigService;
+use crate::models::{ActiveSession, ConnectionEvent, SshConnection};
+use crate::services::audit_service::AuditService;
+use crate::services::config_service::{AppSettings, ConfigChange, ConfigService, WATCH_INTERVAL};
+use crate::services::group_service::GroupService;
+use crate::services::log_service::LogService;
+use crate::services::metrics_service::MetricsService;
+use crate::services::migration_service::ConfigFileKind;
+use crate::services::notification_service::NotificationService;
 use crate::services::session_manager::SessionManager;
+use crate::services::session_snapshot::SnapshotPublisher;
 use rust_i18n::t;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tokio::sync::broadcast::error::RecvError;
 
 /// Application global state
 pub struct AppState {
@@ -21,6 +29,11 @@ pub struct AppState {
     /// Session manager
     pub session_manager: Arc<SessionManager>,
 
+    /// Connection log, fed by the session manager's events
+    pub log_service: Arc<LogService>,
+    /// Audit trail, unless disabled in the settings
+    pub audit_service: Option<Arc<AuditService>>,
+
     /// Currently selected connection ID
     pub selected_connection_id: Arc<RwLock<Option<uuid::Uuid>>>,
 
@@ -43,6 +56,9 @@ pub struct UiState {
     /// Filter text for connection list
     pub filter_text: String,
 
+    /// Only show connections with this tag
+    pub tag_filter: Option<String>,
+
     /// Current view
     pub current_view: AppView,
 
@@ -72,6 +88,12 @@ pub struct UiState {
 
     /// Current language code ("en" or "zh-CN")
     pub language: String,
+
+    /// Name of the active variable profile
+    pub active_profile: Option<String>,
+
+    /// Names of all variable profiles
+    pub profile_names: Vec<String>,
 }
 
 /// Connection form input data
@@ -333,6 +355,7 @@ impl Default for UiState {
             editing_connection_id: None,
             show_templates: false,
             filter_text: String::new(),
+            tag_filter: None,
             current_view: AppView::ConnectionList,
             password_input_for: None,
             password_value: String::new(),
@@ -343,6 +366,8 @@ impl Default for UiState {
             confirm_delete_id: None,
             dark_mode: false,
             language: crate::utils::i18n::current_language(),
+            active_profile: None,
+            profile_names: Vec::new(),
         }
     }
 }
@@ -352,17 +377,32 @@ impl AppState {
     pub fn new() -> anyhow::Result<Self> {
         let config_service = Arc::new(ConfigService::new()?);
         let connections = config_service.load_connections()?;
+        let settings = config_service.load_settings()?;
 
         // Create session manager with default timeout
-        let session_manager = Arc::new(SessionManager::new(300)); // 5 minutes
+        let session_manager = Arc::new(
+            SessionManager::new(300) // 5 minutes
+                .with_profile(settings.active_profile().cloned()),
+        );
+
+        let log_service = Arc::new(LogService::for_app(&config_service, &settings));
+        let audit_service = AuditService::for_app(&config_service, &settings);
+
+        let ui_state = UiState {
+            active_profile: settings.active_profile().map(|p| p.name.clone()),
+            profile_names: settings.profiles.iter().map(|p| p.name.clone()).collect(),
+            ..UiState::default()
+        };
 
         Ok(Self {
             connections: Arc::new(RwLock::new(connections)),
             sessions: Arc::new(RwLock::new(Vec::new())),
             config_service,
             session_manager,
+            log_service,
+            audit_service,
             selected_connection_id: Arc::new(RwLock::new(None)),
-            ui_state: Arc::new(RwLock::new(UiState::default())),
+            ui_state: Arc::new(RwLock::new(ui_state)),
         })
     }
 
@@ -373,6 +413,246 @@ impl AppState {
         Ok(())
     }
 
+    /// Reload state whenever a config file is changed outside the app
+    ///
+    /// Must be called from within a tokio runtime; the watcher runs for the
+    /// lifetime of the state.
+    pub fn start_config_watcher(self: &Arc<Self>) {
+        let watcher = self.config_service.watch(WATCH_INTERVAL);
+        let mut events = watcher.subscribe();
+        let state = self.clone();
+
+        tokio::spawn(async move {
+            let _watcher = watcher;
+            loop {
+                let change = match events.recv().await {
+                    Ok(change) => change,
+                    // Missed events only mean the files changed again
+                    Err(RecvError::Lagged(_)) => ConfigChange {
+                        kind: ConfigFileKind::Connections,
+                    },
+                    Err(RecvError::Closed) => break,
+                };
+                if let Err(e) = state.handle_config_change(change).await {
+                    state
+                        .show_error(e.to_string(), ErrorSeverity::Warning)
+                        .await;
+                }
+            }
+        });
+    }
+
+    /// Log and audit session events and report sessions that closed on
+    /// their own
+    ///
+    /// Must be called from within a tokio runtime; logging runs for the
+    /// lifetime of the state.
+    pub fn start_event_listener(self: &Arc<Self>) {
+        let recorder = self.log_service.record(self.session_manager.events());
+        let auditor = self
+            .audit_service
+            .as_ref()
+            .map(|audit| audit.record(self.session_manager.events()));
+        let mut events = self.session_manager.events().subscribe();
+        let state = self.clone();
+
+        tokio::spawn(async move {
+            let _recorder = recorder;
+            let _auditor = auditor;
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                let (message, severity) = match event.event {
+                    ConnectionEvent::IdleTimeout => {
+                        (t!("messages.session_idle_closed"), ErrorSeverity::Info)
+                    }
+                    ConnectionEvent::ErrorDisconnect => (
+                        t!("messages.session_lost", name = event.connection_name),
+                        ErrorSeverity::Warning,
+                    ),
+                    _ => continue,
+                };
+                let _ = state.reload_sessions().await;
+                state.show_error(message.to_string(), severity).await;
+            }
+        });
+    }
+
+    /// Serve Prometheus metrics if enabled in the settings
+    ///
+    /// Must be called from within a tokio runtime; the endpoint runs for the
+    /// lifetime of the process.
+    pub fn start_metrics_endpoint(self: &Arc<Self>) {
+        let state = self.clone();
+
+        tokio::spawn(async move {
+            let address = match state.config_service.load_settings() {
+                Ok(settings) => settings.metrics_address(),
+                Err(e) => {
+                    tracing::warn!("Failed to load settings: {}", e);
+                    None
+                }
+            };
+            let Some(address) = address else {
+                return;
+            };
+
+            let metrics = MetricsService::new(state.session_manager.clone());
+            match metrics.start(&address).await {
+                Ok(_server) => std::future::pending::<()>().await,
+                Err(e) => {
+                    state
+                        .show_error(e.to_string(), ErrorSeverity::Warning)
+                        .await
+                }
+            }
+        });
+    }
+
+    /// Publish sessions for `sessions` run from a terminal
+    ///
+    /// Must be called from within a tokio runtime; the snapshot is kept up
+    /// to date for the lifetime of the process.
+    pub fn start_session_publisher(self: &Arc<Self>) {
+        let publisher = SnapshotPublisher::start(
+            self.session_manager.clone(),
+            &self.config_service.sessions_dir(),
+        );
+
+        tokio::spawn(async move {
+            let _publisher = publisher;
+            std::future::pending::<()>().await
+        });
+    }
+
+    /// Send session events to the notification sinks in the settings
+    ///
+    /// Must be called from within a tokio runtime; sinks are read once at
+    /// startup.
+    pub fn start_notifications(self: &Arc<Self>) {
+        let notifier = match self.config_service.load_settings() {
+            Ok(settings) => NotificationService::for_app(&settings),
+            Err(e) => {
+                tracing::warn!("Failed to load settings: {}", e);
+                None
+            }
+        };
+
+        if let Some(notifier) = notifier {
+            notifier.listen(self.session_manager.events());
+        }
+    }
+
+    /// Apply a config file change made on disk
+    pub async fn handle_config_change(&self, change: ConfigChange) -> anyhow::Result<()> {
+        match change.kind {
+            ConfigFileKind::Connections => {
+                self.reload_connections().await?;
+                let connections = self.connections.read().await.clone();
+                let flagged = self
+                    .session_manager
+                    .apply_connection_changes(&connections)
+                    .await;
+                self.reload_sessions().await?;
+
+                if flagged.is_empty() {
+                    self.show_success(t!("messages.config_reloaded").to_string())
+                        .await;
+                } else {
+                    self.show_error(
+                        t!("messages.sessions_config_changed", count = flagged.len()).to_string(),
+                        ErrorSeverity::Info,
+                    )
+                    .await;
+                }
+            }
+            ConfigFileKind::Settings => {
+                let settings = self.config_service.load_settings()?;
+                {
+                    let mut ui_state = self.ui_state.write().await;
+                    if ui_state.language != settings.language {
+                        ui_state.language = settings.language.clone();
+                        rust_i18n::set_locale(&ui_state.language);
+                    }
+                }
+                self.apply_profile(&settings).await?;
+            }
+            // Templates are not cached in the app state
+            ConfigFileKind::Templates => {}
+        }
+        Ok(())
+    }
+
+    /// Switch the variable profile, or clear it with `None`
+    ///
+    /// The choice is saved to the settings; running sessions are re-targeted
+    /// like after an edit of their connections.
+    pub async fn switch_profile(&self, name: Option<String>) -> anyhow::Result<()> {
+        let mut settings = self.config_service.load_settings()?;
+        if let Some(name) = &name
+            && !settings.profiles.iter().any(|p| &p.name == name)
+        {
+            anyhow::bail!(t!("profile.not_found", name = name).to_string());
+        }
+
+        settings.active_profile = name;
+        self.config_service.save_settings(&settings)?;
+        self.apply_profile(&settings).await
+    }
+
+    /// Switch to the next profile, going through "no profile" after the last
+    pub async fn cycle_profile(&self) -> anyhow::Result<()> {
+        let (current, names) = {
+            let ui_state = self.ui_state.read().await;
+            (
+                ui_state.active_profile.clone(),
+                ui_state.profile_names.clone(),
+            )
+        };
+        let next = match current.and_then(|c| names.iter().position(|n| *n == c)) {
+            Some(index) => names.get(index + 1).cloned(),
+            None => names.first().cloned(),
+        };
+        self.switch_profile(next).await
+    }
+
+    /// Use the active profile from `settings` if it changed
+    async fn apply_profile(&self, settings: &AppSettings) -> anyhow::Result<()> {
+        let profile = settings.active_profile().cloned();
+        {
+            let mut ui_state = self.ui_state.write().await;
+            ui_state.active_profile = profile.as_ref().map(|p| p.name.clone());
+            ui_state.profile_names = settings.profiles.iter().map(|p| p.name.clone()).collect();
+        }
+        if self.session_manager.profile().await == profile {
+            return Ok(());
+        }
+
+        self.session_manager.set_profile(profile.clone()).await;
+        let connections = self.connections.read().await.clone();
+        let flagged = self
+            .session_manager
+            .apply_connection_changes(&connections)
+            .await;
+        self.reload_sessions().await?;
+
+        let name = profile.map_or_else(|| t!("profile.none").to_string(), |p| p.name);
+        if flagged.is_empty() {
+            self.show_success(t!("profile.switched", name = name).to_string())
+                .await;
+        } else {
+            self.show_error(
+                t!("messages.sessions_config_changed", count = flagged.len()).to_string(),
+                ErrorSeverity::Info,
+            )
+            .await;
+        }
+        Ok(())
+    }
+
     /// Save a connection
     pub async fn save_connection(&self, connection: &SshConnection) -> anyhow::Result<()> {
         self.config_service.save_connection(connection)?;
@@ -482,6 +762,21 @@ impl AppState {
         self.ui_state.write().await.filter_text = text;
     }
 
+    /// Show only connections with `tag`, or all connections with `None`
+    pub async fn set_tag_filter(&self, tag: Option<String>) {
+        self.ui_state.write().await.tag_filter = tag;
+    }
+
+    /// Every tag used by the saved connections
+    pub async fn all_tags(&self) -> Vec<String> {
+        GroupService::tags(&self.connections.read().await)
+    }
+
+    /// Every group used by the saved connections, including parent groups
+    pub async fn all_groups(&self) -> Vec<String> {
+        GroupService::groups(&self.connections.read().await)
+    }
+
     /// Switch view
     pub async fn switch_view(&self, view: AppView) {
         self.ui_state.write().await.current_view = view;
@@ -618,20 +913,93 @@ impl AppState {
         let connections = self.connections.read().await;
         let ui_state = self.ui_state.read().await;
 
-        if ui_state.filter_text.is_empty() {
-            connections.clone()
-        } else {
-            let filter = ui_state.filter_text.to_lowercase();
-            connections
-                .iter()
-                .filter(|c| {
-                    c.name.to_lowercase().contains(&filter)
-                        || c.host.to_lowercase().contains(&filter)
-                        || c.username.to_lowercase().contains(&filter)
+        GroupService::filter(
+            &connections,
+            &ui_state.filter_text,
+            ui_state.tag_filter.as_deref(),
+        )
+    }
+
+    /// Connect every connection in a group concurrently
+    ///
+    /// Connections that are already connected, or that need a password or
+    /// passphrase (asked for one connection at a time), are skipped. Returns
+    /// how many were connected.
+    pub async fn connect_group(&self, group: &str) -> anyhow::Result<usize> {
+        self.clear_notifications().await;
+
+        let members = GroupService::members(&self.connections.read().await, group);
+        let active: Vec<uuid::Uuid> = self
+            .session_manager
+            .list_sessions()
+            .await
+            .iter()
+            .map(|s| s.connection_id)
+            .collect();
+        let (needs_secret, targets): (Vec<_>, Vec<_>) = members
+            .into_iter()
+            .filter(|c| !active.contains(&c.id))
+            .partition(|c| c.auth_method.requires_secret());
+        let targets: Vec<(SshConnection, Option<String>)> =
+            targets.into_iter().map(|c| (c, None)).collect();
+
+        for (connection, _) in &targets {
+            self.set_connecting(connection.id, true).await;
+        }
+        let results = GroupService::connect_all(&self.session_manager, &targets, |_, _| {}).await;
+        for (connection, _) in &targets {
+            self.set_connecting(connection.id, false).await;
+        }
+        self.reload_sessions().await?;
+
+        let failures: Vec<String> = targets
+            .iter()
+            .zip(&results)
+            .filter_map(|((connection, _), result)| {
+                result.as_ref().err().map(|e| {
+                    t!(
+                        "group.failed",
+                        name = connection.name,
+                        reason = e.user_message()
+                    )
+                    .to_string()
                 })
-                .cloned()
-                .collect()
+            })
+            .collect();
+        let connected = results.len() - failures.len();
+
+        if !failures.is_empty() {
+            self.show_error(failures.join("\n"), ErrorSeverity::Error)
+                .await;
+        } else if !needs_secret.is_empty() {
+            self.show_error(
+                t!("group.skipped", count = needs_secret.len()).to_string(),
+                ErrorSeverity::Info,
+            )
+            .await;
+        } else {
+            self.show_success(
+                t!(
+                    "group.connected",
+                    ok = connected,
+                    total = targets.len(),
+                    group = group
+                )
+                .to_string(),
+            )
+            .await;
         }
+        Ok(connected)
+    }
+
+    /// Disconnect every session of the connections in a group
+    pub async fn disconnect_group(&self, group: &str) -> anyhow::Result<usize> {
+        let members = GroupService::members(&self.connections.read().await, group);
+        let closed = GroupService::disconnect_all(&self.session_manager, &members).await;
+        self.reload_sessions().await?;
+        self.show_success(t!("group.disconnected", count = closed, group = group).to_string())
+            .await;
+        Ok(closed)
     }
 
     /// Connect to an SSH session with optional password
@@ -640,7 +1008,6 @@ impl AppState {
         connection_id: uuid::Uuid,
         password: Option<String>,
     ) -> anyhow::Result<uuid::Uuid> {
-        use crate::services::ssh_service::SshService;
         use crate::utils::error::SshToolError;
 
         // Mark as connecting
@@ -662,14 +1029,11 @@ impl AppState {
             connection.port
         );
 
-        // Establish SSH connection
+        // Establish SSH connection and create the session with its tunnels
         let result = async {
-            let session = SshService::connect(&connection, password.as_deref()).await?;
-
-            // Create session with tunnels
             let session_id = self
                 .session_manager
-                .create_session_with_tunnels(connection.clone(), session)
+                .connect(&connection, password.as_deref())
                 .await?;
 
             // Reload sessions to update UI
@@ -810,60 +1174,58 @@ impl AppState {
         // Create forwarding config if ports are specified
         let mut forwarding_configs = Vec::new();
         match form.forwarding_type.as_str() {
-            "local" => {
-                if !form.local_port.trim().is_empty() && !form.remote_port.trim().is_empty() {
-                    let local_port: u16 = form
-                        .local_port
-                        .parse()
-                        .map_err(|_| anyhow::anyhow!("Invalid local port"))?;
-                    let remote_port: u16 = form
-                        .remote_port
-                        .parse()
-                        .map_err(|_| anyhow::anyhow!("Invalid remote port"))?;
-
-                    forwarding_configs.push(ForwardingConfig::Local(LocalForwarding {
-                        local_port,
-                        remote_host: if form.remote_host.is_empty() {
-                            "localhost".to_string()
-                        } else {
-                            form.remote_host.clone()
-                        },
-                        remote_port,
-                        bind_address: form.bind_address.clone(),
-                    }));
-                }
+            "local"
+                if !form.local_port.trim().is_empty() && !form.remote_port.trim().is_empty() =>
+            {
+                let local_port: u16 = form
+                    .local_port
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid local port"))?;
+                let remote_port: u16 = form
+                    .remote_port
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid remote port"))?;
+
+                forwarding_configs.push(ForwardingConfig::Local(LocalForwarding {
+                    local_port,
+                    remote_host: if form.remote_host.is_empty() {
+                        "localhost".to_string()
+                    } else {
+                        form.remote_host.clone()
+                    },
+                    remote_port,
+                    bind_address: form.bind_address.clone(),
+                }));
             }
-            "remote" => {
-                if !form.remote_port.trim().is_empty() && !form.local_port.trim().is_empty() {
-                    let remote_port: u16 = form
-                        .remote_port
-                        .parse()
-                        .map_err(|_| anyhow::anyhow!("Invalid remote port"))?;
-                    let local_port: u16 = form
-                        .local_port
-                        .parse()
-                        .map_err(|_| anyhow::anyhow!("Invalid local port"))?;
-
-                    forwarding_configs.push(ForwardingConfig::Remote(RemoteForwarding {
-                        remote_port,
-                        local_host: "localhost".to_string(),
-                        local_port,
-                    }));
-                }
+            "remote"
+                if !form.remote_port.trim().is_empty() && !form.local_port.trim().is_empty() =>
+            {
+                let remote_port: u16 = form
+                    .remote_port
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid remote port"))?;
+                let local_port: u16 = form
+                    .local_port
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid local port"))?;
+
+                forwarding_configs.push(ForwardingConfig::Remote(RemoteForwarding {
+                    remote_port,
+                    local_host: "localhost".to_string(),
+                    local_port,
+                }));
             }
-            "dynamic" => {
-                if !form.local_port.trim().is_empty() {
-                    let local_port: u16 = form
-                        .local_port
-                        .parse()
-                        .map_err(|_| anyhow::anyhow!("Invalid local port"))?;
-
-                    forwarding_configs.push(ForwardingConfig::Dynamic(DynamicForwarding {
-                        local_port,
-                        bind_address: form.bind_address.clone(),
-                        socks_version: SocksVersion::Socks5,
-                    }));
-                }
+            "dynamic" if !form.local_port.trim().is_empty() => {
+                let local_port: u16 = form
+                    .local_port
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid local port"))?;
+
+                forwarding_configs.push(ForwardingConfig::Dynamic(DynamicForwarding {
+                    local_port,
+                    bind_address: form.bind_address.clone(),
+                    socks_version: SocksVersion::Socks5,
+                }));
             }
             _ => {}
         }
@@ -883,6 +1245,9 @@ impl AppState {
             verify_host_key: false,
             compression: form.compression,
             quiet_mode: form.quiet_mode,
+            group: None,
+            tags: Vec::new(),
+            hooks: Default::default(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
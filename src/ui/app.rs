@@ -9,8 +9,12 @@ use rust_i18n::t;
 use std::sync::Arc;
 
 use ssh_tunnel_manager::models::auth::AuthMethod;
+use ssh_tunnel_manager::services::group_service::GroupService;
 use ssh_tunnel_manager::state::{AppState, ConnectionFormData, ErrorSeverity};
 
+/// Intervals shown in a session's traffic sparkline
+const SPARKLINE_WIDTH: usize = 30;
+
 /// Main application window with editable form inputs
 pub struct SshTunnelApp {
     app_state: Arc<AppState>,
@@ -86,6 +90,11 @@ impl SshTunnelApp {
     pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
         // Initialize application state
         let app_state = Arc::new(AppState::new().expect("Failed to initialize application state"));
+        app_state.start_config_watcher();
+        app_state.start_event_listener();
+        app_state.start_metrics_endpoint();
+        app_state.start_session_publisher();
+        app_state.start_notifications();
 
         // Start session manager's idle monitor in a background task
         let session_manager = app_state.session_manager.clone();
@@ -1331,14 +1340,21 @@ impl SshTunnelApp {
         let muted_color = theme.muted_foreground;
 
         // Get current UI state
-        let (dark_mode, language) = if let Ok(ui_state) = self.app_state.ui_state.try_read() {
-            (ui_state.dark_mode, ui_state.language.clone())
-        } else {
-            (false, "en".to_string())
-        };
+        let (dark_mode, language, active_profile, has_profiles) =
+            if let Ok(ui_state) = self.app_state.ui_state.try_read() {
+                (
+                    ui_state.dark_mode,
+                    ui_state.language.clone(),
+                    ui_state.active_profile.clone(),
+                    !ui_state.profile_names.is_empty(),
+                )
+            } else {
+                (false, "en".to_string(), None, false)
+            };
 
         let app_state = self.app_state.clone();
         let app_state2 = self.app_state.clone();
+        let app_state3 = self.app_state.clone();
 
         h_flex()
             .items_center()
@@ -1361,6 +1377,30 @@ impl SshTunnelApp {
                 h_flex()
                     .items_center()
                     .gap_3()
+                    // Profile switch button (cycles through the profiles)
+                    .when(has_profiles, |this| {
+                        this.child(
+                            Button::new("profile-switch")
+                                .small()
+                                .ghost()
+                                .label(
+                                    t!(
+                                        "profile.switched",
+                                        name = active_profile
+                                            .unwrap_or_else(|| t!("profile.none").to_string())
+                                    )
+                                    .to_string(),
+                                )
+                                .on_click(move |_, _, _| {
+                                    let app_state = app_state3.clone();
+                                    tokio::spawn(async move {
+                                        if let Err(e) = app_state.cycle_profile().await {
+                                            tracing::error!("Failed to switch profile: {}", e);
+                                        }
+                                    });
+                                }),
+                        )
+                    })
                     // Language toggle button
                     .child(
                         Button::new("lang-toggle")
@@ -1538,6 +1578,18 @@ impl SshTunnelApp {
                                                             received = Self::format_bytes(session.bytes_received)
                                                         ).to_string())
                                                 )
+                                                .child(
+                                                    div()
+                                                        .text_xs()
+                                                        .text_color(muted_color)
+                                                        .child(t!("session.rate", rate = session.format_rate()).to_string())
+                                                )
+                                                .child(
+                                                    div()
+                                                        .text_xs()
+                                                        .text_color(success_color)
+                                                        .child(session.traffic.sparkline(SPARKLINE_WIDTH))
+                                                )
                                         )
                                 )
                                 .child(
@@ -1624,11 +1676,11 @@ impl SshTunnelApp {
             gpui::hsla(0.0, 0.70, 0.35, 1.0)
         };
 
-        // Get filter text and connections
-        let filter_text = if let Ok(ui_state) = self.app_state.ui_state.try_read() {
-            ui_state.filter_text.clone()
+        // Get filter text, tag filter and connections
+        let (filter_text, tag_filter) = if let Ok(ui_state) = self.app_state.ui_state.try_read() {
+            (ui_state.filter_text.clone(), ui_state.tag_filter.clone())
         } else {
-            String::new()
+            (String::new(), None)
         };
 
         let all_connections = if let Ok(conns) = self.app_state.connections.try_read() {
@@ -1652,21 +1704,11 @@ impl SshTunnelApp {
             None
         };
 
-        // Filter connections based on search
-        let connections: Vec<_> = if filter_text.is_empty() {
-            all_connections.clone()
-        } else {
-            let filter_lower = filter_text.to_lowercase();
-            all_connections
-                .iter()
-                .filter(|c| {
-                    c.name.to_lowercase().contains(&filter_lower)
-                        || c.host.to_lowercase().contains(&filter_lower)
-                        || c.username.to_lowercase().contains(&filter_lower)
-                })
-                .cloned()
-                .collect()
-        };
+        // Filter connections based on search and the selected tag
+        let connections =
+            GroupService::filter(&all_connections, &filter_text, tag_filter.as_deref());
+        let all_tags = GroupService::tags(&all_connections);
+        let is_filtered = !filter_text.is_empty() || tag_filter.is_some();
 
         let selected_id = if let Ok(state) = self.app_state.selected_connection_id.try_read() {
             *state
@@ -1712,6 +1754,34 @@ impl SshTunnelApp {
                         Input::new(&self.search_input)
                             .cleanable(true)
                     )
+                    // Tag chips
+                    .when(!all_tags.is_empty(), |this| {
+                        let chip = |id: (&'static str, usize), label: String, tag: Option<String>| {
+                            let app_state = self.app_state.clone();
+                            let active = tag_filter == tag;
+                            Button::new(id)
+                                .compact()
+                                .when(active, |b| b.primary())
+                                .when(!active, |b| b.ghost())
+                                .label(label)
+                                .on_click(move |_, _, _| {
+                                    let app_state = app_state.clone();
+                                    let tag = tag.clone();
+                                    tokio::spawn(async move {
+                                        app_state.set_tag_filter(tag).await;
+                                    });
+                                })
+                        };
+                        this.child(
+                            h_flex()
+                                .flex_wrap()
+                                .gap_1()
+                                .child(chip(("tag_all", 0), t!("group.all_tags").to_string(), None))
+                                .children(all_tags.iter().enumerate().map(|(idx, tag)| {
+                                    chip(("tag", idx), format!("#{}", tag), Some(tag.clone()))
+                                }))
+                        )
+                    })
             )
             // Connection list
             .child(
@@ -1733,13 +1803,13 @@ impl SshTunnelApp {
                                                 .text_sm()
                                                 .text_color(muted_color)
                                                 .text_center()
-                                                .child(if filter_text.is_empty() {
+                                                .child(if !is_filtered {
                                                     t!("connection.no_connections").to_string()
                                                 } else {
                                                     t!("connection.no_matching").to_string()
                                                 })
                                         )
-                                        .when(filter_text.is_empty(), |this| {
+                                        .when(!is_filtered, |this| {
                                             this.child(
                                                 div()
                                                     .text_xs()
@@ -0,0 +1,154 @@
+use crate::utils::error::Result;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Replace a file's contents without ever leaving it half-written
+///
+/// The data is written to a temporary file in the same directory, flushed to
+/// disk and then renamed over the target, so readers (and a crash mid-write)
+/// see either the old or the new contents. Existing permissions are kept.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = sibling(path, &format!("tmp-{}", std::process::id()));
+
+    let result = (|| -> Result<()> {
+        let mut file = File::create(&tmp)?;
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&tmp, path)?;
+        sync_parent(path);
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Keep the last `keep` versions of a file as `<name>.bak.1` (newest) to
+/// `<name>.bak.<keep>` (oldest)
+///
+/// Called before the file is overwritten; does nothing if it does not exist.
+pub fn rotate_backups(path: &Path, keep: usize) -> Result<()> {
+    if keep == 0 || !path.exists() {
+        return Ok(());
+    }
+
+    for n in (1..keep).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 1))?;
+    Ok(())
+}
+
+/// Path of the `n`-th rotated backup of a file
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    sibling(path, &format!("bak.{}", n))
+}
+
+/// Advisory lock shared by every process that edits a config file
+///
+/// The lock is taken on a `<name>.lock` file next to the target rather than
+/// the target itself, because atomic writes replace the target's inode. It is
+/// released when the guard is dropped.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Block until the exclusive lock for `path` is acquired
+    pub fn exclusive(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(sibling(path, "lock"))?;
+        file.lock()?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+/// `<dir>/<name>.<suffix>` for a file at `<dir>/<name>`
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.{}", name, suffix))
+}
+
+/// Persist the rename itself by syncing the containing directory
+#[cfg(unix)]
+fn sync_parent(path: &Path) {
+    if let Some(dir) = path.parent().and_then(|p| File::open(p).ok()) {
+        let _ = dir.sync_all();
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.toml");
+        write_atomic(&path, b"one").unwrap();
+        write_atomic(&path, b"two").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "two");
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.toml");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_rotate_backups() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.toml");
+
+        for version in 1..=4 {
+            rotate_backups(&path, 2).unwrap();
+            write_atomic(&path, version.to_string().as_bytes()).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "4");
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "3");
+        assert_eq!(fs::read_to_string(backup_path(&path, 2)).unwrap(), "2");
+        assert!(!backup_path(&path, 3).exists());
+    }
+}
@@ -36,9 +36,24 @@ pub enum SshToolError {
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
+    #[error("Configuration file changed on disk: {0}")]
+    ConfigModified(String),
+
+    #[error("Template error: {0}")]
+    TemplateError(String),
+
+    #[error("Unresolved variables: {}", .0.join(", "))]
+    UnresolvedVariables(Vec<String>),
+
     #[error("Session not found: {0}")]
     SessionNotFound(String),
 
+    #[error("Hook failed: {0}")]
+    HookFailed(String),
+
+    #[error("File transfer failed: {0}")]
+    TransferFailed(String),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -70,6 +85,14 @@ impl SshToolError {
             Self::SshConnectionFailed(reason) => format!("SSH connection failed: {}", reason),
             Self::TunnelFailed(reason) => format!("Tunnel creation failed: {}", reason),
             Self::ConfigError(reason) => format!("Configuration error: {}", reason),
+            Self::ConfigModified(file) => format!(
+                "{} was modified by another process since it was loaded; reload and try again",
+                file
+            ),
+            Self::UnresolvedVariables(names) => format!(
+                "Unresolved variables: {} (set them in the active profile or the environment)",
+                names.join(", ")
+            ),
             Self::SessionNotFound(id) => format!("Session not found: {}", id),
             _ => self.to_string(),
         }
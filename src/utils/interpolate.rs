@@ -0,0 +1,89 @@
+/// Replace `${NAME}` placeholders in `text` using `lookup`
+///
+/// Returns the names `lookup` could not resolve, each once, if any are
+/// missing. A `$` that does not start a placeholder, an unterminated `${`
+/// and a name with characters other than letters, digits and `_` are kept
+/// as written.
+pub fn interpolate(
+    text: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> std::result::Result<String, Vec<String>> {
+    let mut output = String::with_capacity(text.len());
+    let mut missing: Vec<String> = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let name = after.find('}').map(|end| &after[..end]);
+
+        match name {
+            Some(name) if is_variable_name(name) => {
+                match lookup(name) {
+                    Some(value) => output.push_str(&value),
+                    None if !missing.iter().any(|m| m == name) => missing.push(name.to_string()),
+                    None => {}
+                }
+                rest = &after[name.len() + 1..];
+            }
+            _ => {
+                output.push_str("${");
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+
+    if missing.is_empty() {
+        Ok(output)
+    } else {
+        Err(missing)
+    }
+}
+
+/// Whether `text` contains at least one `${NAME}` placeholder
+pub fn has_placeholders(text: &str) -> bool {
+    interpolate(text, |_| None).is_err()
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "ENV" => Some("staging".to_string()),
+            "DOMAIN" => Some("example.com".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_interpolate_replaces_placeholders() {
+        assert_eq!(
+            interpolate("db.${ENV}.${DOMAIN}", lookup).unwrap(),
+            "db.staging.example.com"
+        );
+        assert_eq!(interpolate("plain", lookup).unwrap(), "plain");
+    }
+
+    #[test]
+    fn test_interpolate_reports_missing_once() {
+        assert_eq!(
+            interpolate("${A}-${ENV}-${A}-${B}", lookup).unwrap_err(),
+            vec!["A", "B"]
+        );
+    }
+
+    #[test]
+    fn test_interpolate_keeps_non_placeholders() {
+        assert_eq!(interpolate("$HOME/${ENV", lookup).unwrap(), "$HOME/${ENV");
+        assert_eq!(interpolate("${not a var}", lookup).unwrap(), "${not a var}");
+        assert!(has_placeholders("~/.ssh/${ENV}_key"));
+        assert!(!has_placeholders("~/.ssh/id_ed25519"));
+    }
+}
@@ -8,7 +8,12 @@ pub fn init() {
 
     tracing_subscriber::registry()
         .with(filter)
-        .with(fmt::layer().with_target(true).with_line_number(true))
+        .with(
+            fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_target(true)
+                .with_line_number(true),
+        )
         .init();
 
     tracing::info!("SSH Tunnel Manager started");
@@ -21,7 +26,12 @@ pub fn init_with_level(level: &str) {
 
     tracing_subscriber::registry()
         .with(filter)
-        .with(fmt::layer().with_target(true).with_line_number(true))
+        .with(
+            fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_target(true)
+                .with_line_number(true),
+        )
         .init();
 
     tracing::info!("Logging initialized with level: {}", level);
@@ -1,4 +1,8 @@
+pub mod atomic_file;
 pub mod error;
 pub mod i18n;
+pub mod interpolate;
 pub mod logger;
 pub mod ssh_command_parser;
+pub mod ssh_config_parser;
+pub mod terminal;
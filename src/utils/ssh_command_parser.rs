@@ -6,7 +6,51 @@ use crate::models::{
     SshConnection,
 };
 use crate::utils::error::{Result, SshToolError};
-use std::path::PathBuf;
+use crate::utils::ssh_config_parser::{self, SshConfigParser};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// ssh(1) flags that take an argument
+const FLAGS_WITH_ARG: &str = "BbcDEeFIiJLlmOopQRSWw";
+
+/// ssh(1) flags without an argument
+const FLAGS_WITHOUT_ARG: &str = "46AaCfGgKkMNnqsTtVvXxYy";
+
+/// Something in an SSH command that could not be carried over into the connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// Flag that ssh does not accept
+    UnknownFlag(String),
+    /// Valid ssh flag with no equivalent in a saved connection (e.g. `-4`, `-W`)
+    IgnoredFlag(String),
+    /// `-o` option that is not mapped onto connection settings
+    UnsupportedOption(String),
+    /// Remote command given after the destination
+    IgnoredCommand(String),
+    /// Problem reported while applying `-o` options or the `-F` config file
+    Config(String),
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFlag(flag) => write!(f, "Unknown option: {}", flag),
+            Self::IgnoredFlag(flag) => {
+                write!(f, "Option has no effect on a saved connection: {}", flag)
+            }
+            Self::UnsupportedOption(option) => write!(f, "Unsupported -o option: {}", option),
+            Self::IgnoredCommand(command) => write!(f, "Remote command ignored: {}", command),
+            Self::Config(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// A parsed SSH command together with everything that was not represented
+#[derive(Debug)]
+pub struct ParsedCommand {
+    pub connection: SshConnection,
+    pub warnings: Vec<ParseWarning>,
+}
 
 /// Parse SSH command line arguments into an SshConnection
 ///
@@ -14,12 +58,116 @@ use std::path::PathBuf;
 /// - `ssh -D 2025 -f -C -q -N root@47.76.205.72`
 /// - `ssh -L 13306:10.0.0.5:3306 user@jump.example.com`
 /// - `ssh -R 8080:localhost:80 user@server.com`
+/// - `ssh -J bastion -o Compression=no -NfL 5432:db:5432 ssh://user@host:2222`
+///
+/// `-o` options and `-F` config files are applied with the same rules as
+/// `~/.ssh/config` imports; command line flags take precedence over both.
 pub struct SshCommandParser;
 
+/// Flags and positional arguments collected from the command line
+#[derive(Default)]
+struct CommandArgs {
+    destination: Option<String>,
+    username: Option<String>,
+    port: Option<u16>,
+    identity_file: Option<String>,
+    proxy_jump: Option<String>,
+    config_file: Option<String>,
+    options: Vec<String>,
+    compression: bool,
+    quiet: bool,
+    forwards: Vec<ForwardingConfig>,
+    command: Vec<String>,
+    warnings: Vec<ParseWarning>,
+}
+
+impl CommandArgs {
+    fn switch(&mut self, flag: char) {
+        match flag {
+            'C' => self.compression = true,
+            'q' => self.quiet = true,
+            // Forwarding-only, background and verbosity flags match how
+            // sessions are run anyway
+            'N' | 'f' | 'n' | 'T' | 'v' => {}
+            other => self
+                .warnings
+                .push(ParseWarning::IgnoredFlag(format!("-{}", other))),
+        }
+    }
+
+    fn option(&mut self, flag: char, value: &str) -> Result<()> {
+        match flag {
+            'L' => self.forwards.push(ForwardingConfig::Local(
+                SshCommandParser::parse_local_forward(value)?,
+            )),
+            'R' => self.forwards.push(ForwardingConfig::Remote(
+                SshCommandParser::parse_remote_forward(value)?,
+            )),
+            'D' => self.forwards.push(ForwardingConfig::Dynamic(
+                SshCommandParser::parse_dynamic_forward(value)?,
+            )),
+            'p' => {
+                self.port =
+                    Some(value.parse().map_err(|_| {
+                        SshToolError::ConfigError(format!("Invalid port: {}", value))
+                    })?)
+            }
+            'i' => self.identity_file = Some(value.to_string()),
+            'l' => self.username = Some(value.to_string()),
+            'J' => self.proxy_jump = Some(value.to_string()),
+            'F' => self.config_file = Some(value.to_string()),
+            'o' => self.options.push(value.to_string()),
+            other => self
+                .warnings
+                .push(ParseWarning::IgnoredFlag(format!("-{} {}", other, value))),
+        }
+        Ok(())
+    }
+
+    /// Config directives for the command line flags, which take precedence
+    /// over `-o` options and the config file (first value wins)
+    fn directives(&self, destination_user: Option<&str>, destination_port: Option<u16>) -> String {
+        let mut directives = String::new();
+        let mut push = |key: &str, value: &str| {
+            directives.push_str(&format!("{} {}\n", key, ssh_config_parser::quote(value)));
+        };
+
+        if let Some(user) = self.username.as_deref().or(destination_user) {
+            push("User", user);
+        }
+        if let Some(port) = self.port.or(destination_port) {
+            push("Port", &port.to_string());
+        }
+        if let Some(identity_file) = &self.identity_file {
+            push("IdentityFile", identity_file);
+        }
+        if let Some(proxy_jump) = &self.proxy_jump {
+            push("ProxyJump", proxy_jump);
+        }
+        if self.compression {
+            push("Compression", "yes");
+        }
+        if self.quiet {
+            push("LogLevel", "QUIET");
+        }
+        directives
+    }
+}
+
 impl SshCommandParser {
     /// Parse a full SSH command string
     pub fn parse_command(command: &str) -> Result<SshConnection> {
-        let parts: Vec<&str> = command.split_whitespace().collect();
+        Self::parse_command_with_warnings(command).map(Self::log_warnings)
+    }
+
+    /// Parse SSH command arguments
+    pub fn parse_args(args: &[&str]) -> Result<SshConnection> {
+        Self::parse_args_with_warnings(args).map(Self::log_warnings)
+    }
+
+    /// Parse a full SSH command string, reporting anything that was not imported
+    pub fn parse_command_with_warnings(command: &str) -> Result<ParsedCommand> {
+        let parts = split_command_line(command)?;
 
         if parts.is_empty() || parts[0] != "ssh" {
             return Err(SshToolError::ConfigError(
@@ -27,191 +175,162 @@ impl SshCommandParser {
             ));
         }
 
-        Self::parse_args(&parts[1..])
+        let args: Vec<&str> = parts[1..].iter().map(String::as_str).collect();
+        Self::parse_args_with_warnings(&args)
     }
 
-    /// Parse SSH command arguments
-    pub fn parse_args(args: &[&str]) -> Result<SshConnection> {
-        let mut local_forwards = Vec::new();
-        let mut remote_forwards = Vec::new();
-        let mut dynamic_forwards = Vec::new();
-        let mut username = String::new();
-        let mut host = String::new();
-        let mut port = 22u16;
-        let mut identity_file: Option<PathBuf> = None;
-        let mut _compression = false;
-        let mut _background = false;
+    /// Parse SSH command arguments, reporting anything that was not imported
+    pub fn parse_args_with_warnings(args: &[&str]) -> Result<ParsedCommand> {
+        let mut parsed = CommandArgs::default();
+        let mut options_done = false;
 
         let mut i = 0;
         while i < args.len() {
             let arg = args[i];
+            i += 1;
 
-            match arg {
-                "-L" => {
-                    // Local forward: -L [bind_address:]port:host:hostport
-                    i += 1;
-                    if i >= args.len() {
-                        return Err(SshToolError::ConfigError(
-                            "-L requires an argument".to_string(),
-                        ));
-                    }
-                    let forward = Self::parse_local_forward(args[i])?;
-                    local_forwards.push(forward);
-                }
-                "-R" => {
-                    // Remote forward: -R [bind_address:]port:host:hostport
-                    i += 1;
-                    if i >= args.len() {
-                        return Err(SshToolError::ConfigError(
-                            "-R requires an argument".to_string(),
-                        ));
-                    }
-                    let forward = Self::parse_remote_forward(args[i])?;
-                    remote_forwards.push(forward);
-                }
-                "-D" => {
-                    // Dynamic forward: -D [bind_address:]port
-                    i += 1;
-                    if i >= args.len() {
-                        return Err(SshToolError::ConfigError(
-                            "-D requires an argument".to_string(),
-                        ));
-                    }
-                    let forward = Self::parse_dynamic_forward(args[i])?;
-                    dynamic_forwards.push(forward);
-                }
-                "-p" => {
-                    // Port
-                    i += 1;
-                    if i >= args.len() {
-                        return Err(SshToolError::ConfigError(
-                            "-p requires an argument".to_string(),
-                        ));
-                    }
-                    port = args[i].parse().map_err(|_| {
-                        SshToolError::ConfigError(format!("Invalid port: {}", args[i]))
-                    })?;
-                }
-                "-i" => {
-                    // Identity file (private key)
-                    i += 1;
-                    if i >= args.len() {
-                        return Err(SshToolError::ConfigError(
-                            "-i requires an argument".to_string(),
-                        ));
-                    }
-                    identity_file = Some(PathBuf::from(args[i]));
-                }
-                "-C" => {
-                    // Compression
-                    _compression = true;
-                }
-                "-f" => {
-                    // Background mode
-                    _background = true;
-                }
-                "-N" => {
-                    // No remote command (port forwarding only)
-                    // This is implicitly supported by our design
-                }
-                "-q" => {
-                    // Quiet mode
-                    // We can ignore this for our purposes
-                }
-                "-v" | "-vv" | "-vvv" => {
-                    // Verbose mode
-                    // We can ignore this or set log level
-                }
-                arg if arg.starts_with('-') => {
-                    // Unknown option, skip
-                    tracing::warn!("Ignoring unknown option: {}", arg);
-                }
-                arg if arg.contains('@') => {
-                    // user@host format
-                    let parts: Vec<&str> = arg.split('@').collect();
-                    if parts.len() == 2 {
-                        username = parts[0].to_string();
-                        host = parts[1].to_string();
+            if !parsed.command.is_empty() {
+                // Everything after the remote command belongs to it
+                parsed.command.push(arg.to_string());
+                continue;
+            }
+            if !options_done && arg == "--" {
+                options_done = true;
+                continue;
+            }
+            if !options_done && arg.starts_with("--") {
+                parsed
+                    .warnings
+                    .push(ParseWarning::UnknownFlag(arg.to_string()));
+                continue;
+            }
+            if !options_done && arg.len() > 1 && arg.starts_with('-') {
+                // Grouped flags: "-NfL 5432:db:5432" or "-p2222"
+                for (offset, flag) in arg[1..].char_indices() {
+                    let rest = &arg[1 + offset + flag.len_utf8()..];
+                    if FLAGS_WITH_ARG.contains(flag) {
+                        let value = if !rest.is_empty() {
+                            rest
+                        } else if i < args.len() {
+                            i += 1;
+                            args[i - 1]
+                        } else {
+                            return Err(SshToolError::ConfigError(format!(
+                                "-{} requires an argument",
+                                flag
+                            )));
+                        };
+                        parsed.option(flag, value)?;
+                        break;
+                    } else if FLAGS_WITHOUT_ARG.contains(flag) {
+                        parsed.switch(flag);
                     } else {
-                        return Err(SshToolError::ConfigError(format!(
-                            "Invalid user@host format: {}",
-                            arg
-                        )));
-                    }
-                }
-                arg => {
-                    // Assume it's just a hostname
-                    if host.is_empty() {
-                        host = arg.to_string();
+                        parsed
+                            .warnings
+                            .push(ParseWarning::UnknownFlag(format!("-{}", flag)));
                     }
                 }
+                continue;
             }
 
-            i += 1;
+            if parsed.destination.is_none() {
+                parsed.destination = Some(arg.to_string());
+            } else {
+                parsed.command.push(arg.to_string());
+            }
         }
 
-        // Validate required fields
-        if host.is_empty() {
-            return Err(SshToolError::ConfigError("Host is required".to_string()));
+        let destination = parsed
+            .destination
+            .take()
+            .ok_or_else(|| SshToolError::ConfigError("Host is required".to_string()))?;
+        let (destination_user, host, destination_port) = parse_destination(&destination)?;
+
+        // Flags, then -o options, then the -F file: the first value wins.
+        // Flags and options only apply to the destination, not to jump hosts.
+        let mut config = format!("Match originalhost {}\n", host);
+        config.push_str(&parsed.directives(destination_user.as_deref(), destination_port));
+        for option in &parsed.options {
+            let key = option
+                .split(|c: char| c == '=' || c.is_whitespace())
+                .next()
+                .unwrap_or("");
+            if SshConfigParser::is_supported_directive(key) {
+                config.push_str(option);
+                config.push('\n');
+            } else {
+                parsed
+                    .warnings
+                    .push(ParseWarning::UnsupportedOption(option.clone()));
+            }
         }
 
-        // Default username to current user or "root"
-        if username.is_empty() {
-            username = std::env::var("USER")
-                .or_else(|_| std::env::var("USERNAME"))
-                .unwrap_or_else(|_| "root".to_string());
+        let mut base_dir = PathBuf::from(".");
+        if let Some(file) = parsed.config_file.as_deref().filter(|f| *f != "none") {
+            let path = std::path::absolute(Path::new(file))?;
+            if !path.is_file() {
+                return Err(SshToolError::ConfigError(format!(
+                    "Config file not found: {}",
+                    file
+                )));
+            }
+            if let Some(parent) = path.parent() {
+                base_dir = parent.to_path_buf();
+            }
+            config.push_str(&format!(
+                "Match all\nInclude {}\n",
+                ssh_config_parser::quote(&path.to_string_lossy())
+            ));
         }
 
-        // Determine auth method
-        let auth_method = if let Some(key_path) = identity_file {
-            AuthMethod::PublicKey {
-                private_key_path: key_path,
-                passphrase_required: false, // Can't determine from command line
-            }
-        } else {
-            AuthMethod::Password
-        };
+        let (mut connection, config_warnings) =
+            SshConfigParser::resolve_host(&config, &base_dir, &host)?;
+        parsed
+            .warnings
+            .extend(config_warnings.into_iter().map(ParseWarning::Config));
+        if !parsed.command.is_empty() {
+            parsed
+                .warnings
+                .push(ParseWarning::IgnoredCommand(parsed.command.join(" ")));
+        }
 
-        // Combine all forwarding configs
-        let mut forwarding_configs = Vec::new();
-        forwarding_configs.extend(local_forwards.into_iter().map(ForwardingConfig::Local));
-        forwarding_configs.extend(remote_forwards.into_iter().map(ForwardingConfig::Remote));
-        forwarding_configs.extend(dynamic_forwards.into_iter().map(ForwardingConfig::Dynamic));
+        // Forwards given on the command line come before those from config
+        let config_forwards = std::mem::take(&mut connection.forwarding_configs);
+        connection.forwarding_configs = parsed.forwards;
+        connection.forwarding_configs.extend(config_forwards);
 
         // Generate connection name
-        let name = if !forwarding_configs.is_empty() {
-            let forward_type = match &forwarding_configs[0] {
-                ForwardingConfig::Local(_) => "Local Forward",
-                ForwardingConfig::Remote(_) => "Remote Forward",
-                ForwardingConfig::Dynamic(_) => "SOCKS Proxy",
-            };
-            format!("{} - {}@{}", forward_type, username, host)
-        } else {
-            format!("{}@{}", username, host)
+        connection.name = match connection.forwarding_configs.first() {
+            Some(forward) => {
+                let forward_type = match forward {
+                    ForwardingConfig::Local(_) => "Local Forward",
+                    ForwardingConfig::Remote(_) => "Remote Forward",
+                    ForwardingConfig::Dynamic(_) => "SOCKS Proxy",
+                };
+                format!(
+                    "{} - {}@{}",
+                    forward_type, connection.username, connection.host
+                )
+            }
+            None => format!("{}@{}", connection.username, connection.host),
         };
 
-        Ok(SshConnection {
-            id: uuid::Uuid::new_v4(),
-            name,
-            host,
-            port,
-            username,
-            auth_method,
-            forwarding_configs,
-            jump_hosts: Vec::new(),
-            idle_timeout_seconds: Some(300),
-            host_key_fingerprint: None,
-            verify_host_key: false,
-            compression: true,
-            quiet_mode: false,
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
+        Ok(ParsedCommand {
+            connection,
+            warnings: parsed.warnings,
         })
     }
 
+    fn log_warnings(parsed: ParsedCommand) -> SshConnection {
+        for warning in &parsed.warnings {
+            tracing::warn!("{}", warning);
+        }
+        parsed.connection
+    }
+
     /// Parse local forward argument: [bind_address:]port:host:hostport
-    fn parse_local_forward(arg: &str) -> Result<LocalForwarding> {
-        let parts: Vec<&str> = arg.split(':').collect();
+    pub(crate) fn parse_local_forward(arg: &str) -> Result<LocalForwarding> {
+        let parts = forward_fields(arg);
 
         let (bind_address, local_port, remote_host, remote_port) = match parts.len() {
             3 => {
@@ -261,8 +380,8 @@ impl SshCommandParser {
     }
 
     /// Parse remote forward argument: [bind_address:]port:host:hostport
-    fn parse_remote_forward(arg: &str) -> Result<RemoteForwarding> {
-        let parts: Vec<&str> = arg.split(':').collect();
+    pub(crate) fn parse_remote_forward(arg: &str) -> Result<RemoteForwarding> {
+        let parts = forward_fields(arg);
 
         let (remote_port, local_host, local_port) = match parts.len() {
             3 => {
@@ -301,8 +420,8 @@ impl SshCommandParser {
     }
 
     /// Parse dynamic forward argument: [bind_address:]port
-    fn parse_dynamic_forward(arg: &str) -> Result<DynamicForwarding> {
-        let parts: Vec<&str> = arg.split(':').collect();
+    pub(crate) fn parse_dynamic_forward(arg: &str) -> Result<DynamicForwarding> {
+        let parts = forward_fields(arg);
 
         let (bind_address, local_port) = match parts.len() {
             1 => {
@@ -351,6 +470,16 @@ impl SshCommandParser {
             cmd.push_str(&format!(" -i {}", private_key_path.display()));
         }
 
+        // Add jump hosts
+        if !connection.jump_hosts.is_empty() {
+            let chain = connection
+                .jump_hosts
+                .iter()
+                .map(|j| ssh_config_parser::jump_spec(&j.username, &j.host, j.port))
+                .collect::<Vec<_>>();
+            cmd.push_str(&format!(" -J {}", chain.join(",")));
+        }
+
         // Add forwarding configs
         for config in &connection.forwarding_configs {
             match config {
@@ -382,6 +511,112 @@ impl SshCommandParser {
     }
 }
 
+/// Fields of a forward argument, split at colons outside `[...]`, with the
+/// brackets around IPv6 addresses removed
+fn forward_fields(arg: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut bracketed = false;
+    for (i, c) in arg.char_indices() {
+        match c {
+            '[' => bracketed = true,
+            ']' => bracketed = false,
+            ':' if !bracketed => {
+                fields.push(&arg[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&arg[start..]);
+    fields
+        .into_iter()
+        .map(|field| {
+            field
+                .strip_prefix('[')
+                .and_then(|field| field.strip_suffix(']'))
+                .unwrap_or(field)
+        })
+        .collect()
+}
+
+/// Split a destination ("host", "user@host" or "ssh://user@host:port")
+fn parse_destination(destination: &str) -> Result<(Option<String>, String, Option<u16>)> {
+    let (uri, rest) = match destination.strip_prefix("ssh://") {
+        Some(rest) => (true, rest.trim_end_matches('/')),
+        None => (false, destination),
+    };
+
+    let (user, host) = match rest.rsplit_once('@') {
+        Some((user, host)) if !user.is_empty() => (Some(user.to_string()), host),
+        Some(_) => {
+            return Err(SshToolError::ConfigError(format!(
+                "Invalid user@host format: {}",
+                destination
+            )));
+        }
+        None => (None, rest),
+    };
+
+    // Only URIs carry a port; a bare IPv6 address contains colons too
+    let (host, port) = if uri {
+        ssh_config_parser::split_host_port(host)?
+    } else {
+        (host, None)
+    };
+    if host.is_empty() {
+        return Err(SshToolError::ConfigError("Host is required".to_string()));
+    }
+
+    Ok((user, host.to_string(), port))
+}
+
+/// Split a command line into words, honouring quotes and backslash escapes
+fn split_command_line(command: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_word = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(SshToolError::ConfigError(
+            "Unterminated quote in command".to_string(),
+        ));
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -461,6 +696,9 @@ mod tests {
             verify_host_key: false,
             compression: true,
             quiet_mode: false,
+            group: None,
+            tags: Vec::new(),
+            hooks: Default::default(),
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -546,6 +784,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_ipv6_forwards() {
+        let conn = SshCommandParser::parse_command(
+            "ssh -L [::1]:8080:[fd00::5]:80 -R 9000:[::1]:3000 -D [::]:1080 user@host.com",
+        )
+        .unwrap();
+
+        let ForwardingConfig::Local(local) = &conn.forwarding_configs[0] else {
+            panic!("Expected local forwarding");
+        };
+        assert_eq!(
+            (local.bind_address.as_str(), local.remote_host.as_str()),
+            ("::1", "fd00::5")
+        );
+        let ForwardingConfig::Remote(remote) = &conn.forwarding_configs[1] else {
+            panic!("Expected remote forwarding");
+        };
+        assert_eq!(remote.local_host, "::1");
+        let ForwardingConfig::Dynamic(dynamic) = &conn.forwarding_configs[2] else {
+            panic!("Expected dynamic forwarding");
+        };
+        assert_eq!(
+            (dynamic.bind_address.as_str(), dynamic.local_port),
+            ("::", 1080)
+        );
+    }
+
     #[test]
     fn test_parse_missing_l_argument() {
         let result = SshCommandParser::parse_command("ssh -L user@host.com");
@@ -653,4 +918,183 @@ mod tests {
         let result = SshCommandParser::parse_command("ssh -i");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_parse_jump_options_and_grouped_flags() {
+        let parsed = SshCommandParser::parse_command_with_warnings(
+            "ssh -J bastion -o ServerAliveInterval=30 -NfL 5432:db:5432 user@host",
+        )
+        .unwrap();
+        let conn = &parsed.connection;
+
+        assert_eq!(conn.username, "user");
+        assert_eq!(conn.host, "host");
+        assert_eq!(conn.jump_hosts.len(), 1);
+        assert_eq!(conn.jump_hosts[0].host, "bastion");
+        assert_eq!(conn.forwarding_configs.len(), 1);
+        assert!(matches!(
+            &conn.forwarding_configs[0],
+            ForwardingConfig::Local(l) if l.local_port == 5432 && l.remote_host == "db"
+        ));
+        assert_eq!(
+            parsed.warnings,
+            vec![ParseWarning::UnsupportedOption(
+                "ServerAliveInterval=30".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_parse_known_flags_without_warnings() {
+        let parsed =
+            SshCommandParser::parse_command_with_warnings("ssh -D 2025 -fCqN -vv root@1.2.3.4")
+                .unwrap();
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        assert!(parsed.connection.quiet_mode);
+        assert!(parsed.connection.compression);
+    }
+
+    #[test]
+    fn test_parse_attached_values() {
+        let conn = SshCommandParser::parse_command("ssh -p2222 -luser -D1080 host").unwrap();
+        assert_eq!(conn.port, 2222);
+        assert_eq!(conn.username, "user");
+        assert_eq!(conn.forwarding_configs.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_login_name_takes_precedence() {
+        let conn = SshCommandParser::parse_command("ssh -l admin guest@host").unwrap();
+        assert_eq!(conn.username, "admin");
+    }
+
+    #[test]
+    fn test_parse_ssh_uri() {
+        let conn = SshCommandParser::parse_command("ssh ssh://deploy@example.com:2200").unwrap();
+        assert_eq!(conn.username, "deploy");
+        assert_eq!(conn.host, "example.com");
+        assert_eq!(conn.port, 2200);
+
+        let conn = SshCommandParser::parse_command("ssh -p 2022 ssh://[::1]:2200").unwrap();
+        assert_eq!(conn.host, "::1");
+        assert_eq!(conn.port, 2022);
+    }
+
+    #[test]
+    fn test_parse_jump_chain() {
+        let conn = SshCommandParser::parse_command(
+            "ssh -l app -p 2222 -J ops@bastion:2200,ssh://edge target",
+        )
+        .unwrap();
+
+        assert_eq!(conn.jump_hosts.len(), 2);
+        assert_eq!(conn.jump_hosts[0].username, "ops");
+        assert_eq!(conn.jump_hosts[0].host, "bastion");
+        assert_eq!(conn.jump_hosts[0].port, 2200);
+        // -l and -p apply to the destination only
+        assert_eq!(conn.jump_hosts[1].host, "edge");
+        assert_eq!(conn.jump_hosts[1].port, 22);
+        assert_ne!(conn.jump_hosts[1].username, "app");
+        assert_eq!(conn.port, 2222);
+    }
+
+    #[test]
+    fn test_parse_o_options() {
+        let conn = SshCommandParser::parse_command(
+            "ssh -o Compression=no -o 'User deploy' -oPort=2200 -o StrictHostKeyChecking=yes host",
+        )
+        .unwrap();
+        assert!(!conn.compression);
+        assert_eq!(conn.username, "deploy");
+        assert_eq!(conn.port, 2200);
+        assert!(conn.verify_host_key);
+    }
+
+    #[test]
+    fn test_parse_flags_override_o_options() {
+        let conn = SshCommandParser::parse_command("ssh -o Port=2200 -p 2222 host").unwrap();
+        assert_eq!(conn.port, 2222);
+    }
+
+    #[test]
+    fn test_parse_ignored_and_unknown_flags() {
+        let parsed = SshCommandParser::parse_command_with_warnings(
+            "ssh -4 -6 -W db:5432 -Z --bogus host uptime -a",
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.warnings,
+            vec![
+                ParseWarning::IgnoredFlag("-4".to_string()),
+                ParseWarning::IgnoredFlag("-6".to_string()),
+                ParseWarning::IgnoredFlag("-W db:5432".to_string()),
+                ParseWarning::UnknownFlag("-Z".to_string()),
+                ParseWarning::UnknownFlag("--bogus".to_string()),
+                ParseWarning::IgnoredCommand("uptime -a".to_string()),
+            ]
+        );
+        assert_eq!(parsed.connection.host, "host");
+    }
+
+    #[test]
+    fn test_parse_with_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config");
+        std::fs::write(
+            &config,
+            "Host bastion\n  HostName bastion.example.com\n  User jump\n\n\
+             Host db\n  HostName 10.0.0.5\n  User postgres\n  IdentityFile /keys/db\n  ProxyJump bastion\n  LocalForward 5432 localhost:5432\n",
+        )
+        .unwrap();
+
+        let conn = SshCommandParser::parse_command(&format!(
+            "ssh -F {} -p 2222 -L 8080:localhost:80 db",
+            config.display()
+        ))
+        .unwrap();
+
+        assert_eq!(conn.host, "10.0.0.5");
+        assert_eq!(conn.username, "postgres");
+        assert_eq!(conn.port, 2222);
+        assert_eq!(conn.auth_method, AuthMethod::public_key("/keys/db", false));
+        assert_eq!(conn.jump_hosts.len(), 1);
+        assert_eq!(conn.jump_hosts[0].host, "bastion.example.com");
+        assert_eq!(conn.jump_hosts[0].username, "jump");
+
+        // Command line forwards come first, then those from the config
+        assert_eq!(conn.forwarding_configs.len(), 2);
+        assert!(matches!(
+            &conn.forwarding_configs[0],
+            ForwardingConfig::Local(l) if l.local_port == 8080
+        ));
+    }
+
+    #[test]
+    fn test_parse_missing_config_file() {
+        let result = SshCommandParser::parse_command("ssh -F /nonexistent/ssh_config host");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_unterminated_quote() {
+        let result = SshCommandParser::parse_command("ssh -o 'User x host");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_to_command_with_jump_hosts() {
+        use crate::models::JumpHost;
+
+        let conn = SshConnection::new("Test", "10.0.0.5", "user")
+            .with_jump_host(JumpHost::new("bastion", "ops").with_port(2200))
+            .with_jump_host(JumpHost::new("edge", "ops"));
+
+        let cmd = SshCommandParser::to_command(&conn);
+        assert!(cmd.contains("-J ops@bastion:2200,ops@edge"));
+
+        let parsed = SshCommandParser::parse_command(&cmd).unwrap();
+        assert_eq!(parsed.jump_hosts.len(), 2);
+        assert_eq!(parsed.jump_hosts[0].port, 2200);
+    }
 }
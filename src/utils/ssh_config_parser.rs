@@ -0,0 +1,1205 @@
+use crate::models::{AuthMethod, ForwardingConfig, JumpHost, SshConnection};
+use crate::utils::error::{Result, SshToolError};
+use crate::utils::ssh_command_parser::SshCommandParser;
+use std::path::{Path, PathBuf};
+
+/// Maximum nesting depth for `Include` directives (same limit as OpenSSH)
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Result of importing an OpenSSH client config
+#[derive(Debug, Default)]
+pub struct SshConfigImport {
+    /// One connection per concrete `Host` alias, in file order
+    pub connections: Vec<SshConnection>,
+    /// Directives or hosts that could not be imported
+    pub warnings: Vec<String>,
+}
+
+/// Parse OpenSSH client config files (`~/.ssh/config`) into SshConnections
+///
+/// Supported directives: Host, Match host/originalhost/all, Include, HostName,
+/// Port, User, IdentityFile, ProxyJump, LocalForward, RemoteForward,
+/// DynamicForward, Compression, StrictHostKeyChecking and LogLevel. As in OpenSSH, the
+/// first value found for a directive wins, while IdentityFile and forwards
+/// accumulate across all matching blocks.
+pub struct SshConfigParser;
+
+impl SshConfigParser {
+    /// Default location of the user's SSH config
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".ssh").join("config"))
+    }
+
+    /// Parse a config file; relative `Include` paths resolve against its directory
+    pub fn parse_file(path: &Path) -> Result<SshConfigImport> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            SshToolError::ConfigError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        Ok(Self::parse_str(&content, base_dir))
+    }
+
+    /// Parse config text; relative `Include` paths resolve against `base_dir`
+    pub fn parse_str(content: &str, base_dir: &Path) -> SshConfigImport {
+        let mut config = SshConfig {
+            blocks: vec![Block::new(Criteria::All)],
+            warnings: Vec::new(),
+        };
+        config.load(content, base_dir, 0);
+        config.into_import()
+    }
+
+    /// Build the connection OpenSSH would use for `alias` from config text
+    ///
+    /// Unlike [`SshConfigParser::parse_str`] this works for any destination,
+    /// including ones only matched by wildcard patterns. Returns the
+    /// connection together with any warnings.
+    pub(crate) fn resolve_host(
+        content: &str,
+        base_dir: &Path,
+        alias: &str,
+    ) -> Result<(SshConnection, Vec<String>)> {
+        let mut config = SshConfig {
+            blocks: vec![Block::new(Criteria::All)],
+            warnings: Vec::new(),
+        };
+        config.load(content, base_dir, 0);
+        let connection = config.build_connection(alias)?;
+        Ok((connection, config.warnings))
+    }
+
+    /// Whether a directive is mapped onto connection settings
+    pub(crate) fn is_supported_directive(key: &str) -> bool {
+        SUPPORTED_DIRECTIVES.contains(&key.to_ascii_lowercase().as_str())
+    }
+
+    /// Render connections as OpenSSH `Host` blocks
+    ///
+    /// The output can be appended to `~/.ssh/config` and read back by
+    /// [`SshConfigParser::parse_str`]. Connection names become host aliases
+    /// (whitespace replaced by `-`); jump host keys are written to
+    /// `Match originalhost` blocks since ProxyJump cannot carry them.
+    pub fn to_config(connections: &[SshConnection]) -> String {
+        let mut out = String::new();
+        let mut used_aliases: Vec<String> = Vec::new();
+        let mut jump_identities: Vec<(String, String)> = Vec::new();
+
+        for connection in connections {
+            let alias = unique_alias(&connection.name, &used_aliases);
+            used_aliases.push(alias.clone());
+
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            if alias != connection.name {
+                out.push_str(&format!("# {}\n", connection.name));
+            }
+            out.push_str(&format!("Host {}\n", alias));
+            push_directive(&mut out, "HostName", &connection.host);
+            if connection.port != 22 {
+                push_directive(&mut out, "Port", &connection.port.to_string());
+            }
+            push_directive(&mut out, "User", &connection.username);
+            if let AuthMethod::PublicKey {
+                private_key_path, ..
+            } = &connection.auth_method
+            {
+                push_directive(
+                    &mut out,
+                    "IdentityFile",
+                    &quote(&display_path(private_key_path)),
+                );
+            }
+
+            if !connection.jump_hosts.is_empty() {
+                let chain = connection
+                    .jump_hosts
+                    .iter()
+                    .map(|jump| {
+                        if let AuthMethod::PublicKey {
+                            private_key_path, ..
+                        } = &jump.auth_method
+                        {
+                            let entry = (jump.host.clone(), display_path(private_key_path));
+                            if !jump_identities.contains(&entry) {
+                                jump_identities.push(entry);
+                            }
+                        }
+                        jump_spec(&jump.username, &jump.host, jump.port)
+                    })
+                    .collect::<Vec<_>>();
+                push_directive(&mut out, "ProxyJump", &chain.join(","));
+            }
+
+            for forward in &connection.forwarding_configs {
+                match forward {
+                    ForwardingConfig::Local(l) => push_directive(
+                        &mut out,
+                        "LocalForward",
+                        &format!(
+                            "{} {}:{}",
+                            bind_spec(&l.bind_address, l.local_port),
+                            l.remote_host,
+                            l.remote_port
+                        ),
+                    ),
+                    ForwardingConfig::Remote(r) => push_directive(
+                        &mut out,
+                        "RemoteForward",
+                        &format!("{} {}:{}", r.remote_port, r.local_host, r.local_port),
+                    ),
+                    ForwardingConfig::Dynamic(d) => push_directive(
+                        &mut out,
+                        "DynamicForward",
+                        &bind_spec(&d.bind_address, d.local_port),
+                    ),
+                }
+            }
+
+            push_directive(
+                &mut out,
+                "Compression",
+                if connection.compression { "yes" } else { "no" },
+            );
+            if connection.verify_host_key {
+                push_directive(&mut out, "StrictHostKeyChecking", "yes");
+            }
+            if connection.quiet_mode {
+                push_directive(&mut out, "LogLevel", "QUIET");
+            }
+            if let Some(fingerprint) = &connection.host_key_fingerprint {
+                // known_hosts needs the full key, so only the fingerprint can be
+                // passed on; the alias keeps it separate from other entries
+                push_directive(&mut out, "HostKeyAlias", &alias);
+                out.push_str(&format!(
+                    "    # Expected host key {}; check with: ssh-keygen -lF {}\n",
+                    fingerprint, alias
+                ));
+            }
+        }
+
+        for (host, identity) in jump_identities {
+            out.push_str(&format!("\nMatch originalhost {}\n", host));
+            push_directive(&mut out, "IdentityFile", &quote(&identity));
+        }
+
+        out
+    }
+}
+
+fn push_directive(out: &mut String, key: &str, value: &str) {
+    out.push_str(&format!("    {} {}\n", key, value));
+}
+
+/// Turn a connection name into a valid, unused Host alias
+fn unique_alias(name: &str, used: &[String]) -> String {
+    let base: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_whitespace() || matches!(c, '*' | '?' | '!' | ',' | '"' | '#') {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let base = if base.is_empty() {
+        "host".to_string()
+    } else {
+        base
+    };
+
+    let mut alias = base.clone();
+    let mut n = 2;
+    while used.iter().any(|u| u == &alias) {
+        alias = format!("{}-{}", base, n);
+        n += 1;
+    }
+    alias
+}
+
+/// Format a ProxyJump entry, bracketing IPv6 addresses
+pub(crate) fn jump_spec(username: &str, host: &str, port: u16) -> String {
+    let host = bracket_ipv6(host);
+    if port == 22 {
+        format!("{}@{}", username, host)
+    } else {
+        format!("{}@{}:{}", username, host, port)
+    }
+}
+
+/// Wrap an IPv6 address in `[...]` so its colons are not read as separators
+pub(crate) fn bracket_ipv6(host: &str) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
+
+/// "[bind:]port", omitting the default loopback bind address
+fn bind_spec(bind_address: &str, port: u16) -> String {
+    if bind_address == "127.0.0.1" {
+        port.to_string()
+    } else {
+        format!("{}:{}", bind_address, port)
+    }
+}
+
+/// Shorten paths under the home directory to `~/...` so they work for others
+fn display_path(path: &Path) -> String {
+    if let Some(rest) = dirs::home_dir().and_then(|home| path.strip_prefix(home).ok()) {
+        return format!("~/{}", rest.display());
+    }
+    path.display().to_string()
+}
+
+pub(crate) fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Directives (lowercase) that [`HostOptions::apply`] understands
+const SUPPORTED_DIRECTIVES: &[&str] = &[
+    "hostname",
+    "port",
+    "user",
+    "proxyjump",
+    "compression",
+    "stricthostkeychecking",
+    "loglevel",
+    "identityfile",
+    "localforward",
+    "remoteforward",
+    "dynamicforward",
+];
+
+/// Which hosts a block of directives applies to
+#[derive(Debug, Clone)]
+enum Criteria {
+    /// `Host pattern...`
+    Host(Vec<String>),
+    /// `Match host pattern,...` (matched against the resolved HostName)
+    MatchHost(Vec<String>),
+    /// `Match originalhost pattern,...` (matched against the alias)
+    MatchOriginalHost(Vec<String>),
+    /// Directives before the first Host line, or `Match all`
+    All,
+    /// `Match` criteria we cannot evaluate; never applies
+    Unsupported,
+}
+
+#[derive(Debug)]
+struct Block {
+    criteria: Criteria,
+    directives: Vec<(String, Vec<String>)>,
+}
+
+impl Block {
+    fn new(criteria: Criteria) -> Self {
+        Self {
+            criteria,
+            directives: Vec::new(),
+        }
+    }
+}
+
+struct SshConfig {
+    blocks: Vec<Block>,
+    warnings: Vec<String>,
+}
+
+/// Options collected for a single host alias
+#[derive(Debug, Default)]
+struct HostOptions {
+    hostname: Option<String>,
+    port: Option<String>,
+    user: Option<String>,
+    identity_files: Vec<String>,
+    proxy_jump: Option<String>,
+    proxy_command: Option<String>,
+    local_forwards: Vec<Vec<String>>,
+    remote_forwards: Vec<Vec<String>>,
+    dynamic_forwards: Vec<String>,
+    compression: Option<String>,
+    strict_host_key_checking: Option<String>,
+    log_level: Option<String>,
+}
+
+impl HostOptions {
+    fn apply(&mut self, key: &str, args: &[String]) {
+        let first = || args.first().cloned();
+        match key {
+            "hostname" => set_once(&mut self.hostname, first()),
+            "port" => set_once(&mut self.port, first()),
+            "user" => set_once(&mut self.user, first()),
+            "proxyjump" => set_once(&mut self.proxy_jump, Some(args.join(","))),
+            "proxycommand" => set_once(&mut self.proxy_command, Some(args.join(" "))),
+            "compression" => set_once(&mut self.compression, first()),
+            "stricthostkeychecking" => set_once(&mut self.strict_host_key_checking, first()),
+            "loglevel" => set_once(&mut self.log_level, first()),
+            "identityfile" => self.identity_files.extend(first()),
+            "localforward" => self.local_forwards.push(args.to_vec()),
+            "remoteforward" => self.remote_forwards.push(args.to_vec()),
+            "dynamicforward" => self.dynamic_forwards.extend(first()),
+            _ => {}
+        }
+    }
+}
+
+fn set_once(slot: &mut Option<String>, value: Option<String>) {
+    if slot.is_none() {
+        *slot = value;
+    }
+}
+
+impl SshConfig {
+    fn load(&mut self, content: &str, base_dir: &Path, depth: usize) {
+        for (line_no, line) in content.lines().enumerate() {
+            let Some((key, args)) = split_line(line) else {
+                continue;
+            };
+
+            match key.as_str() {
+                "host" => self.blocks.push(Block::new(Criteria::Host(args))),
+                "match" => {
+                    let criteria = self.parse_match(&args, line_no + 1);
+                    self.blocks.push(Block::new(criteria));
+                }
+                "include" => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        self.warnings
+                            .push(format!("Include nested too deeply: {}", args.join(" ")));
+                        continue;
+                    }
+                    // Host/Match lines in an included file must not leak into
+                    // the rest of this file, so reopen the current block afterwards
+                    let current = self.current_criteria();
+                    for pattern in &args {
+                        for path in expand_include(pattern, base_dir) {
+                            match std::fs::read_to_string(&path) {
+                                Ok(included) => self.load(&included, base_dir, depth + 1),
+                                Err(e) => {
+                                    tracing::debug!("Skipping include {}: {}", path.display(), e)
+                                }
+                            }
+                        }
+                    }
+                    self.blocks.push(Block::new(current));
+                }
+                _ => {
+                    if let Some(block) = self.blocks.last_mut() {
+                        block.directives.push((key, args));
+                    }
+                }
+            }
+        }
+    }
+
+    fn current_criteria(&self) -> Criteria {
+        self.blocks
+            .last()
+            .map(|b| b.criteria.clone())
+            .unwrap_or(Criteria::All)
+    }
+
+    fn parse_match(&mut self, args: &[String], line_no: usize) -> Criteria {
+        match args
+            .iter()
+            .map(|a| a.to_ascii_lowercase())
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [all] if all == "all" => Criteria::All,
+            [kind, _] if kind == "host" => Criteria::MatchHost(split_patterns(&args[1])),
+            [kind, _] if kind == "originalhost" => {
+                Criteria::MatchOriginalHost(split_patterns(&args[1]))
+            }
+            _ => {
+                self.warnings.push(format!(
+                    "Line {}: unsupported Match criteria '{}', block ignored",
+                    line_no,
+                    args.join(" ")
+                ));
+                Criteria::Unsupported
+            }
+        }
+    }
+
+    /// Concrete aliases from Host lines (no wildcards or negations), in file order
+    fn host_aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for block in &self.blocks {
+            if let Criteria::Host(patterns) = &block.criteria {
+                for pattern in patterns {
+                    let concrete = !pattern.contains(['*', '?', '!']);
+                    if concrete && !aliases.iter().any(|a| a == pattern) {
+                        aliases.push(pattern.clone());
+                    }
+                }
+            }
+        }
+        aliases
+    }
+
+    fn resolve(&self, alias: &str) -> HostOptions {
+        let mut options = HostOptions::default();
+        for block in &self.blocks {
+            let matches = match &block.criteria {
+                Criteria::All => true,
+                Criteria::Host(patterns) => matches_patterns(patterns, alias),
+                Criteria::MatchOriginalHost(patterns) => matches_patterns(patterns, alias),
+                Criteria::MatchHost(patterns) => {
+                    matches_patterns(patterns, options.hostname.as_deref().unwrap_or(alias))
+                }
+                Criteria::Unsupported => false,
+            };
+            if matches {
+                for (key, args) in &block.directives {
+                    options.apply(key, args);
+                }
+            }
+        }
+        options
+    }
+
+    fn into_import(mut self) -> SshConfigImport {
+        let aliases = self.host_aliases();
+        let mut connections = Vec::new();
+        for alias in &aliases {
+            match self.build_connection(alias) {
+                Ok(connection) => connections.push(connection),
+                Err(e) => self.warnings.push(format!("Host {}: {}", alias, e)),
+            }
+        }
+
+        SshConfigImport {
+            connections,
+            warnings: self.warnings,
+        }
+    }
+
+    fn build_connection(&mut self, alias: &str) -> Result<SshConnection> {
+        let options = self.resolve(alias);
+        let host = options
+            .hostname
+            .clone()
+            .unwrap_or_else(|| alias.to_string());
+        let port = parse_port(options.port.as_deref())?;
+        let username = options.user.clone().unwrap_or_else(local_username);
+
+        let mut connection = SshConnection::new(alias, &host, &username)
+            .with_port(port)
+            .with_auth_method(identity_auth(&options, &host, &username));
+
+        if let Some(proxy_jump) = &options.proxy_jump {
+            connection.jump_hosts = self.jump_hosts(proxy_jump, &mut vec![alias.to_string()])?;
+        }
+        if options.proxy_command.is_some() {
+            self.warnings.push(format!(
+                "Host {}: ProxyCommand is not supported and was ignored",
+                alias
+            ));
+        }
+
+        for args in &options.local_forwards {
+            match SshCommandParser::parse_local_forward(&args.join(":")) {
+                Ok(forward) => connection
+                    .forwarding_configs
+                    .push(ForwardingConfig::Local(forward)),
+                Err(e) => self.warnings.push(format!("Host {}: {}", alias, e)),
+            }
+        }
+        for args in &options.remote_forwards {
+            match SshCommandParser::parse_remote_forward(&args.join(":")) {
+                Ok(forward) => connection
+                    .forwarding_configs
+                    .push(ForwardingConfig::Remote(forward)),
+                Err(e) => self.warnings.push(format!("Host {}: {}", alias, e)),
+            }
+        }
+        for arg in &options.dynamic_forwards {
+            match SshCommandParser::parse_dynamic_forward(arg) {
+                Ok(forward) => connection
+                    .forwarding_configs
+                    .push(ForwardingConfig::Dynamic(forward)),
+                Err(e) => self.warnings.push(format!("Host {}: {}", alias, e)),
+            }
+        }
+
+        if let Some(value) = options.compression.as_deref() {
+            connection.compression = parse_yes_no(value);
+        }
+        if let Some(value) = options.log_level.as_deref() {
+            connection.quiet_mode = matches!(
+                value.to_ascii_uppercase().as_str(),
+                "QUIET" | "FATAL" | "ERROR"
+            );
+        }
+        if let Some(value) = options.strict_host_key_checking.as_deref() {
+            connection.verify_host_key =
+                !matches!(value.to_ascii_lowercase().as_str(), "no" | "off" | "false");
+        }
+
+        Ok(connection)
+    }
+
+    /// Expand a ProxyJump value into jump hosts
+    ///
+    /// As in OpenSSH, each jump host is looked up in the config by name, so
+    /// `Host`/`Match` blocks apply to it and its own ProxyJump chain is
+    /// prepended. `visited` holds the hosts on the current chain.
+    fn jump_hosts(&self, value: &str, visited: &mut Vec<String>) -> Result<Vec<JumpHost>> {
+        if value.eq_ignore_ascii_case("none") {
+            return Ok(Vec::new());
+        }
+
+        let mut jump_hosts = Vec::new();
+        for spec in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let spec = spec.strip_prefix("ssh://").unwrap_or(spec);
+            let (user, host_port) = match spec.rsplit_once('@') {
+                Some((user, rest)) => (Some(user.to_string()), rest),
+                None => (None, spec),
+            };
+            let (name, port) = split_host_port(host_port)?;
+            if visited.iter().any(|v| v == name) {
+                return Err(SshToolError::ConfigError(format!(
+                    "ProxyJump loop detected at {}",
+                    name
+                )));
+            }
+
+            let options = self.resolve(name);
+            if let Some(chain) = &options.proxy_jump {
+                visited.push(name.to_string());
+                jump_hosts.extend(self.jump_hosts(chain, visited)?);
+                visited.pop();
+            }
+
+            let host = options.hostname.clone().unwrap_or_else(|| name.to_string());
+            let username = user
+                .or_else(|| options.user.clone())
+                .unwrap_or_else(local_username);
+            let port = match port {
+                Some(port) => port,
+                None => parse_port(options.port.as_deref())?,
+            };
+            jump_hosts.push(
+                JumpHost::new(&host, &username)
+                    .with_port(port)
+                    .with_auth_method(identity_auth(&options, &host, &username)),
+            );
+        }
+        Ok(jump_hosts)
+    }
+}
+
+/// Split a config line into a lowercase keyword and its arguments
+///
+/// Accepts both `Key value` and `Key=value`, and honours double quotes.
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let key_end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let key = line[..key_end].to_ascii_lowercase();
+    let rest = line[key_end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in rest.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+
+    Some((key, args))
+}
+
+fn split_patterns(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// OpenSSH pattern-list semantics: any negated match rejects, otherwise any match accepts
+fn matches_patterns(patterns: &[String], name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    let mut matched = false;
+    for pattern in patterns {
+        let pattern = pattern.to_ascii_lowercase();
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(negated, &name) {
+                return false;
+            }
+        } else if wildcard_match(&pattern, &name) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Glob match supporting `*` and `?`
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Resolve an Include argument to the files it names, sorted like glob(3)
+fn expand_include(pattern: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let expanded = expand_tilde(pattern);
+    let path = if expanded.is_absolute() {
+        expanded
+    } else {
+        base_dir.join(expanded)
+    };
+
+    let file_pattern = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !file_pattern.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut matches: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|p| {
+                    p.is_file()
+                        && p.file_name()
+                            .is_some_and(|n| wildcard_match(&file_pattern, &n.to_string_lossy()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    matches.sort();
+    matches
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Expand the `%` tokens OpenSSH allows in IdentityFile
+fn expand_identity_file(path: &str, host: &str, username: &str) -> PathBuf {
+    let home = dirs::home_dir()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut expanded = String::new();
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('d') => expanded.push_str(&home),
+            Some('h') => expanded.push_str(host),
+            Some('r') => expanded.push_str(username),
+            Some('u') => expanded.push_str(&local_username()),
+            Some('%') => expanded.push('%'),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    expand_tilde(&expanded)
+}
+
+fn identity_auth(options: &HostOptions, host: &str, username: &str) -> AuthMethod {
+    match options
+        .identity_files
+        .iter()
+        .find(|f| !f.eq_ignore_ascii_case("none"))
+    {
+        Some(file) => AuthMethod::public_key(expand_identity_file(file, host, username), false),
+        None => AuthMethod::Password,
+    }
+}
+
+fn parse_port(value: Option<&str>) -> Result<u16> {
+    match value {
+        None => Ok(22),
+        Some(v) => v
+            .parse()
+            .map_err(|_| SshToolError::ConfigError(format!("Invalid port: {}", v))),
+    }
+}
+
+/// Split "host", "host:port" or "[v6]:port"
+pub(crate) fn split_host_port(value: &str) -> Result<(&str, Option<u16>)> {
+    if let Some(rest) = value.strip_prefix('[') {
+        let (host, port) = rest
+            .split_once(']')
+            .ok_or_else(|| SshToolError::ConfigError(format!("Invalid jump host: {}", value)))?;
+        let port = match port.strip_prefix(':') {
+            Some(p) => Some(parse_port(Some(p))?),
+            None => None,
+        };
+        return Ok((host, port));
+    }
+    match value.split_once(':') {
+        Some((host, port)) => Ok((host, Some(parse_port(Some(port))?))),
+        None => Ok((value, None)),
+    }
+}
+
+fn parse_yes_no(value: &str) -> bool {
+    matches!(value.to_ascii_lowercase().as_str(), "yes" | "true" | "on")
+}
+
+fn local_username() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "root".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ForwardingConfig;
+
+    fn parse(content: &str) -> SshConfigImport {
+        SshConfigParser::parse_str(content, Path::new("/nonexistent"))
+    }
+
+    fn find<'a>(import: &'a SshConfigImport, name: &str) -> &'a SshConnection {
+        import
+            .connections
+            .iter()
+            .find(|c| c.name == name)
+            .unwrap_or_else(|| panic!("no connection named {}", name))
+    }
+
+    #[test]
+    fn test_basic_host() {
+        let import = parse(
+            r#"
+# Production database
+Host prod-db
+    HostName 10.0.0.5
+    Port 2222
+    User admin
+    IdentityFile /keys/prod
+    Compression no
+    StrictHostKeyChecking yes
+"#,
+        );
+
+        assert_eq!(import.connections.len(), 1);
+        let conn = find(&import, "prod-db");
+        assert_eq!(conn.host, "10.0.0.5");
+        assert_eq!(conn.port, 2222);
+        assert_eq!(conn.username, "admin");
+        assert!(matches!(
+            &conn.auth_method,
+            AuthMethod::PublicKey { private_key_path, .. } if private_key_path == Path::new("/keys/prod")
+        ));
+        assert!(!conn.compression);
+        assert!(conn.verify_host_key);
+        assert!(import.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_host_without_hostname_uses_alias() {
+        let import = parse("Host server.example.com\n  User bob\n");
+        let conn = find(&import, "server.example.com");
+        assert_eq!(conn.host, "server.example.com");
+        assert_eq!(conn.port, 22);
+        assert!(conn.auth_method.is_password());
+    }
+
+    #[test]
+    fn test_first_value_wins_and_wildcards_apply() {
+        let import = parse(
+            r#"
+Host web
+    User deploy
+
+Host *
+    User nobody
+    Port 2200
+    IdentityFile /keys/default
+"#,
+        );
+
+        // Wildcard-only blocks are not imported as connections
+        assert_eq!(import.connections.len(), 1);
+        let conn = find(&import, "web");
+        assert_eq!(conn.username, "deploy");
+        assert_eq!(conn.port, 2200);
+        assert!(conn.auth_method.is_public_key());
+    }
+
+    #[test]
+    fn test_multiple_aliases_and_negation() {
+        let import = parse(
+            r#"
+Host alpha beta
+    Port 2022
+
+Host * !beta
+    User gamma
+"#,
+        );
+
+        assert_eq!(import.connections.len(), 2);
+        assert_eq!(find(&import, "alpha").username, "gamma");
+        assert_eq!(find(&import, "alpha").port, 2022);
+        assert_ne!(find(&import, "beta").username, "gamma");
+    }
+
+    #[test]
+    fn test_equals_syntax_and_quotes() {
+        let import = parse("Host=quoted\n  HostName=example.com\n  IdentityFile \"/my keys/id\"\n");
+        let conn = find(&import, "quoted");
+        assert_eq!(conn.host, "example.com");
+        assert!(matches!(
+            &conn.auth_method,
+            AuthMethod::PublicKey { private_key_path, .. } if private_key_path == Path::new("/my keys/id")
+        ));
+    }
+
+    #[test]
+    fn test_identity_file_tokens() {
+        let import =
+            parse("Host tok\n  HostName h.example\n  User u\n  IdentityFile /keys/%r@%h\n");
+        assert!(matches!(
+            &find(&import, "tok").auth_method,
+            AuthMethod::PublicKey { private_key_path, .. } if private_key_path == Path::new("/keys/u@h.example")
+        ));
+    }
+
+    #[test]
+    fn test_forwards() {
+        let import = parse(
+            r#"
+Host tunnels
+    LocalForward 13306 db.internal:3306
+    LocalForward 0.0.0.0:8080 web:80
+    RemoteForward 9000 localhost:3000
+    DynamicForward 1080
+"#,
+        );
+
+        let conn = find(&import, "tunnels");
+        assert_eq!(conn.forwarding_configs.len(), 4);
+        match &conn.forwarding_configs[0] {
+            ForwardingConfig::Local(l) => {
+                assert_eq!(l.local_port, 13306);
+                assert_eq!(l.remote_host, "db.internal");
+                assert_eq!(l.remote_port, 3306);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match &conn.forwarding_configs[1] {
+            ForwardingConfig::Local(l) => assert_eq!(l.bind_address, "0.0.0.0"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(
+            &conn.forwarding_configs[2],
+            ForwardingConfig::Remote(r) if r.remote_port == 9000 && r.local_port == 3000
+        ));
+        assert!(matches!(
+            &conn.forwarding_configs[3],
+            ForwardingConfig::Dynamic(d) if d.local_port == 1080
+        ));
+    }
+
+    #[test]
+    fn test_invalid_forward_is_a_warning() {
+        let import = parse("Host bad\n  LocalForward nonsense\n");
+        assert!(find(&import, "bad").forwarding_configs.is_empty());
+        assert_eq!(import.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_proxy_jump_resolves_aliases() {
+        let import = parse(
+            r#"
+Host bastion
+    HostName bastion.example.com
+    User jump
+    Port 2222
+    IdentityFile /keys/bastion
+
+Host inner
+    HostName 10.1.0.10
+    ProxyJump bastion,ops@edge.example.com:2200
+
+Host deep
+    HostName 10.2.0.10
+    ProxyJump inner
+"#,
+        );
+
+        let inner = find(&import, "inner");
+        assert_eq!(inner.jump_hosts.len(), 2);
+        assert_eq!(inner.jump_hosts[0].host, "bastion.example.com");
+        assert_eq!(inner.jump_hosts[0].username, "jump");
+        assert_eq!(inner.jump_hosts[0].port, 2222);
+        assert!(inner.jump_hosts[0].auth_method.is_public_key());
+        assert_eq!(inner.jump_hosts[1].host, "edge.example.com");
+        assert_eq!(inner.jump_hosts[1].username, "ops");
+        assert_eq!(inner.jump_hosts[1].port, 2200);
+
+        // Jumping through an alias with its own ProxyJump expands the chain
+        let deep = find(&import, "deep");
+        let hosts: Vec<_> = deep.jump_hosts.iter().map(|j| j.host.as_str()).collect();
+        assert_eq!(
+            hosts,
+            vec!["bastion.example.com", "edge.example.com", "10.1.0.10"]
+        );
+    }
+
+    #[test]
+    fn test_proxy_jump_none() {
+        let import = parse("Host direct\n  ProxyJump none\n\nHost *\n  ProxyJump bastion\n");
+        assert!(find(&import, "direct").jump_hosts.is_empty());
+    }
+
+    #[test]
+    fn test_proxy_jump_loop() {
+        let import = parse("Host a\n  ProxyJump b\n\nHost b\n  ProxyJump a\n");
+        assert!(import.connections.is_empty());
+        assert!(import.warnings.iter().all(|w| w.contains("loop")));
+    }
+
+    #[test]
+    fn test_match_host() {
+        let import = parse(
+            r#"
+Host app
+    HostName app.internal
+
+Match host *.internal
+    User service
+
+Match exec "test -f /tmp/flag"
+    User never
+"#,
+        );
+
+        assert_eq!(find(&import, "app").username, "service");
+        assert_eq!(import.warnings.len(), 1);
+        assert!(import.warnings[0].contains("Match"));
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf_d = dir.path().join("conf.d");
+        std::fs::create_dir(&conf_d).unwrap();
+        std::fs::write(conf_d.join("a.conf"), "Host included-a\n  Port 2001\n").unwrap();
+        std::fs::write(conf_d.join("b.conf"), "Host included-b\n  Port 2002\n").unwrap();
+        std::fs::write(conf_d.join("skip.txt"), "Host skipped\n").unwrap();
+
+        let main = dir.path().join("config");
+        std::fs::write(
+            &main,
+            "Host main\n  Include conf.d/*.conf\n  User after-include\n",
+        )
+        .unwrap();
+
+        let import = SshConfigParser::parse_file(&main).unwrap();
+        let names: Vec<_> = import.connections.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["main", "included-a", "included-b"]);
+        assert_eq!(find(&import, "included-b").port, 2002);
+
+        // Directives after an Include belong to the enclosing Host block again
+        assert_eq!(find(&import, "main").username, "after-include");
+        assert_ne!(find(&import, "included-a").username, "after-include");
+    }
+
+    #[test]
+    fn test_include_loop_is_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("config");
+        std::fs::write(&main, "Include config\nHost looped\n").unwrap();
+
+        let import = SshConfigParser::parse_file(&main).unwrap();
+        assert!(!import.connections.is_empty());
+        assert!(import.warnings.iter().any(|w| w.contains("Include")));
+    }
+
+    #[test]
+    fn test_invalid_port_skips_host() {
+        let import = parse("Host broken\n  Port abc\n\nHost ok\n");
+        assert_eq!(import.connections.len(), 1);
+        assert_eq!(import.connections[0].name, "ok");
+        assert!(import.warnings[0].contains("broken"));
+    }
+
+    fn round_trip(connections: &[SshConnection]) -> SshConfigImport {
+        let rendered = SshConfigParser::to_config(connections);
+        let import = parse(&rendered);
+        assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+        import
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        use crate::models::{DynamicForwarding, LocalForwarding, RemoteForwarding};
+
+        let mut original = SshConnection::new("prod", "10.0.0.5", "admin")
+            .with_port(2222)
+            .with_auth_method(AuthMethod::public_key("/keys/prod", false))
+            .with_jump_host(
+                JumpHost::new("bastion.example.com", "jump")
+                    .with_port(2200)
+                    .with_auth_method(AuthMethod::public_key("/keys/bastion", false)),
+            )
+            .with_jump_host(JumpHost::new("edge.example.com", "ops"))
+            .with_forwarding(ForwardingConfig::Local(LocalForwarding::new(
+                13306,
+                "db.internal",
+                3306,
+            )))
+            .with_forwarding(ForwardingConfig::Remote(RemoteForwarding::new(
+                9000,
+                "localhost",
+                3000,
+            )))
+            .with_forwarding(ForwardingConfig::Dynamic(
+                DynamicForwarding::new(1080).with_bind_address("0.0.0.0"),
+            ));
+        original.compression = false;
+        original.verify_host_key = true;
+        original.quiet_mode = true;
+
+        let import = round_trip(std::slice::from_ref(&original));
+        assert_eq!(import.connections.len(), 1);
+        let conn = &import.connections[0];
+        assert_eq!(conn.name, original.name);
+        assert_eq!(conn.host, original.host);
+        assert_eq!(conn.port, original.port);
+        assert_eq!(conn.username, original.username);
+        assert_eq!(conn.auth_method, original.auth_method);
+        assert_eq!(conn.forwarding_configs, original.forwarding_configs);
+        assert_eq!(conn.compression, original.compression);
+        assert_eq!(conn.verify_host_key, original.verify_host_key);
+        assert_eq!(conn.quiet_mode, original.quiet_mode);
+
+        assert_eq!(conn.jump_hosts.len(), 2);
+        for (imported, expected) in conn.jump_hosts.iter().zip(&original.jump_hosts) {
+            assert_eq!(imported.host, expected.host);
+            assert_eq!(imported.port, expected.port);
+            assert_eq!(imported.username, expected.username);
+            assert_eq!(imported.auth_method, expected.auth_method);
+        }
+    }
+
+    #[test]
+    fn test_export_multiple_connections_round_trip() {
+        let connections = vec![
+            SshConnection::new("web", "web.example.com", "deploy"),
+            SshConnection::new("db", "db.example.com", "postgres").with_port(2022),
+        ];
+
+        let import = round_trip(&connections);
+        assert_eq!(import.connections.len(), 2);
+        for (imported, original) in import.connections.iter().zip(&connections) {
+            assert_eq!(imported.name, original.name);
+            assert_eq!(imported.host, original.host);
+            assert_eq!(imported.port, original.port);
+            assert_eq!(imported.username, original.username);
+            assert!(imported.auth_method.is_password());
+            assert_eq!(imported.compression, original.compression);
+        }
+    }
+
+    #[test]
+    fn test_export_aliases() {
+        let connections = vec![
+            SshConnection::new("My Server", "a.example.com", "u"),
+            SshConnection::new("My Server", "b.example.com", "u"),
+        ];
+        let rendered = SshConfigParser::to_config(&connections);
+        assert!(rendered.contains("# My Server\nHost My-Server\n"));
+        assert!(rendered.contains("Host My-Server-2\n"));
+
+        let import = parse(&rendered);
+        let hosts: Vec<_> = import.connections.iter().map(|c| c.host.as_str()).collect();
+        assert_eq!(hosts, vec!["a.example.com", "b.example.com"]);
+    }
+
+    #[test]
+    fn test_export_host_key_hint() {
+        let mut conn = SshConnection::new("pinned", "pinned.example.com", "u");
+        conn.host_key_fingerprint = Some("SHA256:abc".to_string());
+        conn.verify_host_key = true;
+
+        let rendered = SshConfigParser::to_config(&[conn]);
+        assert!(rendered.contains("StrictHostKeyChecking yes"));
+        assert!(rendered.contains("HostKeyAlias pinned"));
+        assert!(rendered.contains("SHA256:abc"));
+    }
+
+    #[test]
+    fn test_export_quotes_paths_with_spaces() {
+        let conn = SshConnection::new("spaced", "h", "u")
+            .with_auth_method(AuthMethod::public_key("/my keys/id", false));
+        let import = round_trip(std::slice::from_ref(&conn));
+        assert_eq!(import.connections[0].auth_method, conn.auth_method);
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.example.com", "db.example.com"));
+        assert!(wildcard_match("web-?", "web-1"));
+        assert!(!wildcard_match("web-?", "web-10"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("a*b", "acd"));
+    }
+}
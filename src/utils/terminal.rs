@@ -0,0 +1,166 @@
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::mpsc;
+
+/// How long the stdin reader waits for input before checking whether it
+/// was stopped
+#[cfg(unix)]
+const STDIN_POLL_MS: i64 = 100;
+
+/// Size of the local terminal as (columns, rows)
+pub fn size() -> (u32, u32) {
+    let (rows, cols) = console::Term::stdout().size();
+    (u32::from(cols), u32::from(rows))
+}
+
+/// Terminal type to request for a remote pseudo-terminal
+pub fn term_type() -> String {
+    std::env::var("TERM")
+        .ok()
+        .filter(|term| !term.is_empty())
+        .unwrap_or_else(|| "xterm-256color".to_string())
+}
+
+/// Puts the local terminal into raw mode until dropped
+///
+/// Keys then reach the remote shell as typed (Ctrl-C included) instead of
+/// being line-edited and echoed locally. Does nothing if stdin is not a
+/// terminal, or on platforms without termios.
+pub struct RawMode {
+    #[cfg(unix)]
+    saved: Option<rustix::termios::Termios>,
+}
+
+impl RawMode {
+    #[cfg(unix)]
+    pub fn enable() -> io::Result<Self> {
+        use rustix::termios::{OptionalActions, isatty, tcgetattr, tcsetattr};
+
+        let stdin = io::stdin();
+        if !isatty(&stdin) {
+            return Ok(Self { saved: None });
+        }
+        let saved = tcgetattr(&stdin)?;
+        let mut raw = saved.clone();
+        raw.make_raw();
+        tcsetattr(&stdin, OptionalActions::Now, &raw)?;
+        Ok(Self { saved: Some(saved) })
+    }
+
+    #[cfg(not(unix))]
+    pub fn enable() -> io::Result<Self> {
+        Ok(Self {})
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(saved) = &self.saved {
+            let _ = rustix::termios::tcsetattr(
+                io::stdin(),
+                rustix::termios::OptionalActions::Now,
+                saved,
+            );
+        }
+    }
+}
+
+/// Reads stdin on a background thread until dropped
+///
+/// Unlike `tokio::io::stdin`, the thread waits for input with `poll` and
+/// checks in between whether it was dropped, so it does not linger and
+/// swallow the next key typed after the remote shell has exited.
+pub struct StdinReader {
+    stop: Arc<AtomicBool>,
+    receiver: mpsc::Receiver<Vec<u8>>,
+}
+
+impl StdinReader {
+    pub fn spawn() -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel(16);
+        let stopped = stop.clone();
+        std::thread::spawn(move || read_stdin(&stopped, &sender));
+        Self { stop, receiver }
+    }
+
+    /// Next chunk of input, or `None` at end of input
+    pub async fn recv(&mut self) -> Option<Vec<u8>> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for StdinReader {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn read_stdin(stop: &AtomicBool, sender: &mpsc::Sender<Vec<u8>>) {
+    let mut stdin = io::stdin();
+    // Larger than the stdin buffer, so reads go straight to the terminal and
+    // nothing is left buffered where `poll` cannot see it
+    let mut buf = vec![0u8; 16 * 1024];
+
+    while !stop.load(Ordering::Relaxed) {
+        #[cfg(unix)]
+        if !wait_readable(&stdin) {
+            continue;
+        }
+        match stdin.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                if sender.blocking_send(buf[..n].to_vec()).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn wait_readable(stdin: &io::Stdin) -> bool {
+    use rustix::event::{PollFd, PollFlags, Timespec, poll};
+
+    let mut fds = [PollFd::new(stdin, PollFlags::IN)];
+    let timeout = Timespec {
+        tv_sec: 0,
+        tv_nsec: STDIN_POLL_MS * 1_000_000,
+    };
+    matches!(poll(&mut fds, Some(&timeout)), Ok(n) if n > 0)
+}
+
+/// Fires when the local terminal is resized (`SIGWINCH`)
+///
+/// Never fires on platforms without the signal.
+pub struct ResizeSignal {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl ResizeSignal {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(unix)]
+            signal: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::window_change())
+                .ok(),
+        }
+    }
+
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = &mut self.signal {
+            signal.recv().await;
+            return;
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+impl Default for ResizeSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,905 @@
+//! In-process SSH server harness for end-to-end tests
+//!
+//! `TestSshServer` runs a russh server on an ephemeral localhost port with a
+//! freshly generated host key. It accepts password, public key and
+//! keyboard-interactive authentication for a single test user (keys listed
+//! in `home()/.ssh/authorized_keys` included), and services
+//! the channel types the tunnel code relies on:
+//!
+//! - `direct-tcpip` (used by -L and -D)
+//! - `tcpip-forward` / `cancel-tcpip-forward` (used by -R)
+//! - `direct-streamlocal` / `streamlocal-forward`
+//! - `session` with a tiny `exec` implementation (`echo ...`, `exit N`)
+//! - `session` with a pty and a shell that reports its terminal and size,
+//!   echoes input and exits on `exit`
+//! - `session` with the `sftp` subsystem, serving the local filesystem with
+//!   relative paths starting at `home()`
+//!
+//! Plain TCP and Unix echo servers are provided as forwarding targets.
+
+#![allow(dead_code)]
+
+use russh::keys::ssh_key::rand_core::OsRng;
+use russh::keys::ssh_key::{HashAlg, LineEnding};
+use russh::keys::{Algorithm, PrivateKey, PublicKey};
+use russh::server::{Auth, Config, Handle, Handler, Msg, Response, Session};
+use russh::{Channel, ChannelId, CryptoVec, Pty};
+use russh_sftp::protocol::{
+    Attrs, Data, File, FileAttributes, Handle as SftpHandle, Name, OpenFlags, Status, StatusCode,
+    Version,
+};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Username accepted by the test server
+pub const TEST_USER: &str = "tester";
+
+/// Password accepted by the test server (password and keyboard-interactive)
+pub const TEST_PASSWORD: &str = "secret";
+
+// =============================================================================
+// Test SSH Server
+// =============================================================================
+
+/// State shared between the server and every client connection
+#[derive(Default)]
+struct ServerState {
+    /// Public keys accepted for `TEST_USER`
+    authorized_keys: Vec<PublicKey>,
+    /// Home directory of `TEST_USER`
+    home: PathBuf,
+    /// Ports currently bound by `tcpip-forward` requests
+    remote_forward_ports: Mutex<HashSet<u16>>,
+    /// Number of `direct-tcpip` channels opened so far
+    direct_tcpip_opened: AtomicUsize,
+    /// Number of successful authentications so far
+    authenticated: AtomicUsize,
+}
+
+/// An in-process SSH server bound to `127.0.0.1` on an ephemeral port
+pub struct TestSshServer {
+    addr: SocketAddr,
+    host_key_fingerprint: String,
+    client_key_path: PathBuf,
+    state: Arc<ServerState>,
+    task: JoinHandle<()>,
+    _dir: TempDir,
+}
+
+impl TestSshServer {
+    /// Start a new server with a random host key and a random authorized client key
+    pub async fn start() -> Self {
+        let dir = tempfile::tempdir().expect("create temp dir");
+
+        let host_key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).expect("host key");
+        let host_key_fingerprint = host_key
+            .public_key()
+            .fingerprint(HashAlg::Sha256)
+            .to_string();
+
+        let client_key_path = dir.path().join("id_ed25519");
+        let client_public_key = generate_client_key(&client_key_path);
+
+        let home = dir.path().join("home");
+        std::fs::create_dir(&home).expect("create home dir");
+
+        let state = Arc::new(ServerState {
+            authorized_keys: vec![client_public_key],
+            home,
+            ..Default::default()
+        });
+
+        let config = Arc::new(Config {
+            keys: vec![host_key],
+            auth_rejection_time: Duration::from_millis(10),
+            auth_rejection_time_initial: Some(Duration::ZERO),
+            inactivity_timeout: Some(Duration::from_secs(60)),
+            ..Default::default()
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind test server");
+        let addr = listener.local_addr().expect("server addr");
+
+        let accept_state = state.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let config = config.clone();
+                let handler = TestHandler::new(accept_state.clone());
+                tokio::spawn(async move {
+                    if let Ok(session) = russh::server::run_stream(config, stream, handler).await {
+                        let _ = session.await;
+                    }
+                });
+            }
+        });
+
+        Self {
+            addr,
+            host_key_fingerprint,
+            client_key_path,
+            state,
+            task,
+            _dir: dir,
+        }
+    }
+
+    /// Address the server is listening on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Host the server is listening on
+    pub fn host(&self) -> String {
+        self.addr.ip().to_string()
+    }
+
+    /// Port the server is listening on
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// SHA256 fingerprint of the host key, in the same format as `SshClientHandler`
+    pub fn host_key_fingerprint(&self) -> &str {
+        &self.host_key_fingerprint
+    }
+
+    /// Path of an unencrypted OpenSSH private key accepted for `TEST_USER`
+    pub fn client_key_path(&self) -> &Path {
+        &self.client_key_path
+    }
+
+    /// Home directory of `TEST_USER`, where SFTP paths start
+    pub fn home(&self) -> &Path {
+        &self.state.home
+    }
+
+    /// Ports currently bound on behalf of `tcpip-forward` requests
+    pub async fn remote_forward_ports(&self) -> Vec<u16> {
+        let mut ports: Vec<u16> = self
+            .state
+            .remote_forward_ports
+            .lock()
+            .await
+            .iter()
+            .copied()
+            .collect();
+        ports.sort_unstable();
+        ports
+    }
+
+    /// Number of `direct-tcpip` channels opened so far
+    pub fn direct_tcpip_opened(&self) -> usize {
+        self.state.direct_tcpip_opened.load(Ordering::SeqCst)
+    }
+
+    /// Number of successful authentications so far
+    pub fn authenticated(&self) -> usize {
+        self.state.authenticated.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for TestSshServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// =============================================================================
+// Per-connection Handler
+// =============================================================================
+
+struct TestHandler {
+    state: Arc<ServerState>,
+    /// Listener tasks for `tcpip-forward`, keyed by (address, port)
+    tcp_forwards: HashMap<(String, u32), JoinHandle<()>>,
+    /// Listener tasks for `streamlocal-forward`, keyed by socket path
+    unix_forwards: HashMap<String, JoinHandle<()>>,
+    /// Session channels not yet claimed by a request; a subsystem takes its
+    /// channel over as a stream
+    sessions: HashMap<ChannelId, Channel<Msg>>,
+    /// Session channels running a shell
+    shells: HashSet<ChannelId>,
+}
+
+impl TestHandler {
+    fn new(state: Arc<ServerState>) -> Self {
+        Self {
+            state,
+            tcp_forwards: HashMap::new(),
+            unix_forwards: HashMap::new(),
+            sessions: HashMap::new(),
+            shells: HashSet::new(),
+        }
+    }
+
+    fn accept(&self) -> Auth {
+        self.state.authenticated.fetch_add(1, Ordering::SeqCst);
+        Auth::Accept
+    }
+
+    fn is_authorized(&self, user: &str, public_key: &PublicKey) -> bool {
+        let deployed = std::fs::read_to_string(self.state.home.join(".ssh/authorized_keys"))
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter_map(|field| russh::keys::parse_public_key_base64(field).ok())
+            .collect::<Vec<_>>();
+        user == TEST_USER
+            && self
+                .state
+                .authorized_keys
+                .iter()
+                .chain(&deployed)
+                .any(|k| k.key_data() == public_key.key_data())
+    }
+
+    /// Run a minimal command interpreter for `exec` requests
+    async fn run_command(handle: Handle, channel: ChannelId, command: String) {
+        let (stdout, stderr, status) = if let Some(text) = command.strip_prefix("echo ") {
+            (format!("{}\n", text), String::new(), 0)
+        } else if let Some(code) = command.strip_prefix("exit ") {
+            (
+                String::new(),
+                String::new(),
+                code.trim().parse().unwrap_or(1),
+            )
+        } else {
+            (
+                String::new(),
+                format!("{}: command not found\n", command),
+                127,
+            )
+        };
+
+        if !stdout.is_empty() {
+            let _ = handle.data(channel, CryptoVec::from(stdout)).await;
+        }
+        if !stderr.is_empty() {
+            let _ = handle
+                .extended_data(channel, 1, CryptoVec::from(stderr))
+                .await;
+        }
+        let _ = handle.exit_status_request(channel, status).await;
+        let _ = handle.eof(channel).await;
+        let _ = handle.close(channel).await;
+    }
+}
+
+impl Drop for TestHandler {
+    fn drop(&mut self) {
+        for (_, task) in self.tcp_forwards.drain() {
+            task.abort();
+        }
+        for (path, task) in self.unix_forwards.drain() {
+            task.abort();
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+impl Handler for TestHandler {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+        if user == TEST_USER && password == TEST_PASSWORD {
+            Ok(self.accept())
+        } else {
+            Ok(Auth::reject())
+        }
+    }
+
+    async fn auth_publickey_offered(
+        &mut self,
+        user: &str,
+        public_key: &PublicKey,
+    ) -> Result<Auth, Self::Error> {
+        if self.is_authorized(user, public_key) {
+            Ok(Auth::Accept)
+        } else {
+            Ok(Auth::reject())
+        }
+    }
+
+    async fn auth_publickey(
+        &mut self,
+        user: &str,
+        public_key: &PublicKey,
+    ) -> Result<Auth, Self::Error> {
+        if self.is_authorized(user, public_key) {
+            Ok(self.accept())
+        } else {
+            Ok(Auth::reject())
+        }
+    }
+
+    async fn auth_keyboard_interactive<'a>(
+        &'a mut self,
+        user: &str,
+        _submethods: &str,
+        response: Option<Response<'a>>,
+    ) -> Result<Auth, Self::Error> {
+        match response {
+            None => Ok(Auth::Partial {
+                name: Cow::Borrowed("test-server"),
+                instructions: Cow::Borrowed(""),
+                prompts: Cow::Owned(vec![(Cow::Borrowed("Password: "), false)]),
+            }),
+            Some(mut response) => {
+                let answer = response.next();
+                if user == TEST_USER && answer.as_deref() == Some(TEST_PASSWORD.as_bytes()) {
+                    Ok(self.accept())
+                } else {
+                    Ok(Auth::reject())
+                }
+            }
+        }
+    }
+
+    async fn auth_succeeded(&mut self, _session: &mut Session) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        self.sessions.insert(channel.id(), channel);
+        Ok(true)
+    }
+
+    async fn subsystem_request(
+        &mut self,
+        channel: ChannelId,
+        name: &str,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        match self.sessions.remove(&channel) {
+            Some(claimed) if name == "sftp" => {
+                session.channel_success(channel)?;
+                let handler = SftpHandler::new(self.state.home.clone());
+                russh_sftp::server::run(claimed.into_stream(), handler).await;
+            }
+            _ => session.channel_failure(channel)?,
+        }
+        Ok(())
+    }
+
+    async fn exec_request(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.sessions.remove(&channel);
+        let command = String::from_utf8_lossy(data).into_owned();
+        session.channel_success(channel)?;
+        tokio::spawn(Self::run_command(session.handle(), channel, command));
+        Ok(())
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        term: &str,
+        col_width: u32,
+        row_height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _modes: &[(Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.sessions.remove(&channel);
+        session.channel_success(channel)?;
+        session.data(
+            channel,
+            CryptoVec::from(format!("pty {} {}x{}\r\n", term, col_width, row_height)),
+        )?;
+        Ok(())
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.sessions.remove(&channel);
+        self.shells.insert(channel);
+        session.channel_success(channel)?;
+        session.data(channel, CryptoVec::from_slice(b"$ "))?;
+        Ok(())
+    }
+
+    async fn window_change_request(
+        &mut self,
+        channel: ChannelId,
+        col_width: u32,
+        row_height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.data(
+            channel,
+            CryptoVec::from(format!("resized {}x{}\r\n", col_width, row_height)),
+        )?;
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if !self.shells.contains(&channel) {
+            return Ok(());
+        }
+        session.data(channel, CryptoVec::from_slice(data))?;
+        if data.starts_with(b"exit") {
+            session.exit_status_request(channel, 0)?;
+            session.eof(channel)?;
+            session.close(channel)?;
+        }
+        Ok(())
+    }
+
+    async fn channel_open_direct_tcpip(
+        &mut self,
+        channel: Channel<Msg>,
+        host_to_connect: &str,
+        port_to_connect: u32,
+        _originator_address: &str,
+        _originator_port: u32,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        let target = match TcpStream::connect((host_to_connect, port_to_connect as u16)).await {
+            Ok(stream) => stream,
+            Err(_) => return Ok(false),
+        };
+        self.state
+            .direct_tcpip_opened
+            .fetch_add(1, Ordering::SeqCst);
+        tokio::spawn(pipe(channel.into_stream(), target));
+        Ok(true)
+    }
+
+    async fn channel_open_direct_streamlocal(
+        &mut self,
+        channel: Channel<Msg>,
+        socket_path: &str,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        let target = match UnixStream::connect(socket_path).await {
+            Ok(stream) => stream,
+            Err(_) => return Ok(false),
+        };
+        tokio::spawn(pipe(channel.into_stream(), target));
+        Ok(true)
+    }
+
+    async fn tcpip_forward(
+        &mut self,
+        address: &str,
+        port: &mut u32,
+        session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        let bind_host = match address {
+            "" | "localhost" => "127.0.0.1",
+            other => other,
+        };
+        let listener = match TcpListener::bind((bind_host, *port as u16)).await {
+            Ok(listener) => listener,
+            Err(_) => return Ok(false),
+        };
+        let bound_port = listener.local_addr()?.port();
+        *port = bound_port as u32;
+
+        self.state
+            .remote_forward_ports
+            .lock()
+            .await
+            .insert(bound_port);
+
+        let handle = session.handle();
+        let connected_address = address.to_string();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, peer)) = listener.accept().await {
+                let handle = handle.clone();
+                let connected_address = connected_address.clone();
+                tokio::spawn(async move {
+                    if let Ok(channel) = handle
+                        .channel_open_forwarded_tcpip(
+                            connected_address,
+                            bound_port as u32,
+                            peer.ip().to_string(),
+                            peer.port() as u32,
+                        )
+                        .await
+                    {
+                        pipe(channel.into_stream(), stream).await;
+                    }
+                });
+            }
+        });
+
+        self.tcp_forwards.insert((address.to_string(), *port), task);
+        Ok(true)
+    }
+
+    async fn cancel_tcpip_forward(
+        &mut self,
+        address: &str,
+        port: u32,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        match self.tcp_forwards.remove(&(address.to_string(), port)) {
+            Some(task) => {
+                task.abort();
+                self.state
+                    .remote_forward_ports
+                    .lock()
+                    .await
+                    .remove(&(port as u16));
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn streamlocal_forward(
+        &mut self,
+        socket_path: &str,
+        session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        let listener = match UnixListener::bind(socket_path) {
+            Ok(listener) => listener,
+            Err(_) => return Ok(false),
+        };
+
+        let handle = session.handle();
+        let path = socket_path.to_string();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let handle = handle.clone();
+                let path = path.clone();
+                tokio::spawn(async move {
+                    if let Ok(channel) = handle.channel_open_forwarded_streamlocal(path).await {
+                        pipe(channel.into_stream(), stream).await;
+                    }
+                });
+            }
+        });
+
+        self.unix_forwards.insert(socket_path.to_string(), task);
+        Ok(true)
+    }
+
+    async fn cancel_streamlocal_forward(
+        &mut self,
+        socket_path: &str,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        match self.unix_forwards.remove(socket_path) {
+            Some(task) => {
+                task.abort();
+                let _ = std::fs::remove_file(socket_path);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+// =============================================================================
+// SFTP Subsystem
+// =============================================================================
+
+/// An open file or directory listing of the SFTP server
+enum OpenHandle {
+    File(std::fs::File),
+    /// Entries not yet returned by `readdir`
+    Dir(Option<Vec<File>>),
+}
+
+/// SFTP server over the local filesystem, enough for uploads, downloads and
+/// directory walks
+struct SftpHandler {
+    /// Where relative paths start
+    home: PathBuf,
+    handles: HashMap<String, OpenHandle>,
+    next_handle: usize,
+}
+
+impl SftpHandler {
+    fn new(home: PathBuf) -> Self {
+        Self {
+            home,
+            handles: HashMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        self.home.join(path)
+    }
+
+    fn insert(&mut self, id: u32, handle: OpenHandle) -> SftpHandle {
+        self.next_handle += 1;
+        let name = self.next_handle.to_string();
+        self.handles.insert(name.clone(), handle);
+        SftpHandle { id, handle: name }
+    }
+
+    fn file(&self, handle: &str) -> Result<&std::fs::File, StatusCode> {
+        match self.handles.get(handle) {
+            Some(OpenHandle::File(file)) => Ok(file),
+            _ => Err(StatusCode::Failure),
+        }
+    }
+}
+
+fn status_ok(id: u32) -> Status {
+    Status {
+        id,
+        status_code: StatusCode::Ok,
+        error_message: "Ok".to_string(),
+        language_tag: "en-US".to_string(),
+    }
+}
+
+fn status_code(error: std::io::Error) -> StatusCode {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => StatusCode::NoSuchFile,
+        std::io::ErrorKind::PermissionDenied => StatusCode::PermissionDenied,
+        _ => StatusCode::Failure,
+    }
+}
+
+impl russh_sftp::server::Handler for SftpHandler {
+    type Error = StatusCode;
+
+    fn unimplemented(&self) -> Self::Error {
+        StatusCode::OpUnsupported
+    }
+
+    async fn init(
+        &mut self,
+        _version: u32,
+        _extensions: HashMap<String, String>,
+    ) -> Result<Version, Self::Error> {
+        Ok(Version::new())
+    }
+
+    async fn open(
+        &mut self,
+        id: u32,
+        filename: String,
+        pflags: OpenFlags,
+        _attrs: FileAttributes,
+    ) -> Result<SftpHandle, Self::Error> {
+        let file = std::fs::OpenOptions::from(pflags)
+            .open(self.path(&filename))
+            .map_err(status_code)?;
+        Ok(self.insert(id, OpenHandle::File(file)))
+    }
+
+    async fn close(&mut self, id: u32, handle: String) -> Result<Status, Self::Error> {
+        self.handles.remove(&handle);
+        Ok(status_ok(id))
+    }
+
+    async fn read(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        len: u32,
+    ) -> Result<Data, Self::Error> {
+        use std::os::unix::fs::FileExt;
+        let mut data = vec![0u8; len as usize];
+        let n = self
+            .file(&handle)?
+            .read_at(&mut data, offset)
+            .map_err(status_code)?;
+        if n == 0 {
+            return Err(StatusCode::Eof);
+        }
+        data.truncate(n);
+        Ok(Data { id, data })
+    }
+
+    async fn write(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> Result<Status, Self::Error> {
+        use std::os::unix::fs::FileExt;
+        self.file(&handle)?
+            .write_all_at(&data, offset)
+            .map_err(status_code)?;
+        Ok(status_ok(id))
+    }
+
+    async fn stat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+        let metadata = std::fs::metadata(self.path(&path)).map_err(status_code)?;
+        Ok(Attrs {
+            id,
+            attrs: FileAttributes::from(&metadata),
+        })
+    }
+
+    async fn lstat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+        let metadata = std::fs::symlink_metadata(self.path(&path)).map_err(status_code)?;
+        Ok(Attrs {
+            id,
+            attrs: FileAttributes::from(&metadata),
+        })
+    }
+
+    async fn fstat(&mut self, id: u32, handle: String) -> Result<Attrs, Self::Error> {
+        let metadata = self.file(&handle)?.metadata().map_err(status_code)?;
+        Ok(Attrs {
+            id,
+            attrs: FileAttributes::from(&metadata),
+        })
+    }
+
+    async fn setstat(
+        &mut self,
+        id: u32,
+        path: String,
+        attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        use std::os::unix::fs::PermissionsExt;
+        if let Some(mode) = attrs.permissions {
+            std::fs::set_permissions(
+                self.path(&path),
+                std::fs::Permissions::from_mode(mode & 0o7777),
+            )
+            .map_err(status_code)?;
+        }
+        Ok(status_ok(id))
+    }
+
+    async fn opendir(&mut self, id: u32, path: String) -> Result<SftpHandle, Self::Error> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(self.path(&path)).map_err(status_code)? {
+            let entry = entry.map_err(status_code)?;
+            let metadata = std::fs::symlink_metadata(entry.path()).map_err(status_code)?;
+            entries.push(File::new(
+                entry.file_name().to_string_lossy(),
+                FileAttributes::from(&metadata),
+            ));
+        }
+        Ok(self.insert(id, OpenHandle::Dir(Some(entries))))
+    }
+
+    async fn readdir(&mut self, id: u32, handle: String) -> Result<Name, Self::Error> {
+        match self.handles.get_mut(&handle) {
+            Some(OpenHandle::Dir(entries)) => match entries.take() {
+                Some(files) => Ok(Name { id, files }),
+                None => Err(StatusCode::Eof),
+            },
+            _ => Err(StatusCode::Failure),
+        }
+    }
+
+    async fn mkdir(
+        &mut self,
+        id: u32,
+        path: String,
+        _attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        std::fs::create_dir(self.path(&path)).map_err(status_code)?;
+        Ok(status_ok(id))
+    }
+}
+
+/// Copy data in both directions until either side closes
+async fn pipe<A, B>(mut a: A, mut b: B)
+where
+    A: AsyncRead + AsyncWrite + Unpin,
+    B: AsyncRead + AsyncWrite + Unpin,
+{
+    let _ = tokio::io::copy_bidirectional(&mut a, &mut b).await;
+}
+
+// =============================================================================
+// Forwarding Targets and Helpers
+// =============================================================================
+
+/// Start a TCP echo server on `127.0.0.1` and return its address
+pub async fn spawn_echo_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind echo server");
+    let addr = listener.local_addr().expect("echo server addr");
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let (mut reader, mut writer) = stream.into_split();
+                let _ = tokio::io::copy(&mut reader, &mut writer).await;
+            });
+        }
+    });
+    addr
+}
+
+/// Start a Unix socket echo server at `path`
+pub fn spawn_unix_echo_server(path: &Path) {
+    let listener = UnixListener::bind(path).expect("bind unix echo server");
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let (mut reader, mut writer) = stream.into_split();
+                let _ = tokio::io::copy(&mut reader, &mut writer).await;
+            });
+        }
+    });
+}
+
+/// Write a new unencrypted Ed25519 key in OpenSSH format to `path` and its
+/// public half to `path.pub`, returning the public key
+pub fn generate_client_key(path: &Path) -> PublicKey {
+    let key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).expect("client key");
+    std::fs::write(
+        path,
+        key.to_openssh(LineEnding::LF)
+            .expect("encode client key")
+            .as_bytes(),
+    )
+    .expect("write client key");
+    std::fs::write(
+        path.with_extension("pub"),
+        key.public_key().to_openssh().expect("encode public key"),
+    )
+    .expect("write public key");
+    key.public_key().clone()
+}
+
+/// Find a localhost TCP port that is currently free
+pub fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .map(|a| a.port())
+        .expect("allocate free port")
+}
+
+/// Write `payload` to `stream` and read back the same number of bytes
+pub async fn echo_roundtrip<S>(stream: &mut S, payload: &[u8]) -> Vec<u8>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(payload).await.expect("write payload");
+    let mut buf = vec![0u8; payload.len()];
+    tokio::time::timeout(Duration::from_secs(10), stream.read_exact(&mut buf))
+        .await
+        .expect("echo timed out")
+        .expect("read echo");
+    buf
+}
+
+/// Connect to `127.0.0.1:port`, retrying until a listener is up
+pub async fn connect_with_retry(port: u16) -> TcpStream {
+    for _ in 0..50 {
+        if let Ok(stream) = TcpStream::connect(("127.0.0.1", port)).await {
+            return stream;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("nothing listening on 127.0.0.1:{}", port);
+}
@@ -167,7 +167,7 @@ fn test_template_save_and_load() {
     let template = ConnectionTemplate::new("Development Server", "dev-template");
 
     service
-        .save_templates(&[template.clone()])
+        .save_templates(std::slice::from_ref(&template))
         .expect("Failed to save template");
 
     // Retrieve template
@@ -242,9 +242,11 @@ fn test_settings_persistence() {
     // First instance: set settings
     {
         let service = ConfigService::with_dir(config_path.clone()).unwrap();
-        let mut settings = AppSettings::default();
-        settings.language = "zh-CN".to_string();
-        settings.idle_timeout_seconds = 600;
+        let settings = AppSettings {
+            language: "zh-CN".to_string(),
+            idle_timeout_seconds: 600,
+            ..Default::default()
+        };
         service
             .save_settings(&settings)
             .expect("Failed to save settings");
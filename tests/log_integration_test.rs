@@ -6,9 +6,19 @@
 use ssh_tunnel_manager::models::{ConnectionEvent, ConnectionLog, LogLevel};
 use ssh_tunnel_manager::services::log_service::LogService;
 use std::fs;
+use std::path::Path;
 use tempfile::tempdir;
 use uuid::Uuid;
 
+/// Parse a JSON Lines log file
+fn read_json_lines(path: &Path) -> Vec<ConnectionLog> {
+    fs::read_to_string(path)
+        .expect("Failed to read log file")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Log line is not JSON"))
+        .collect()
+}
+
 // =============================================================================
 // Basic Logging Integration Tests
 // =============================================================================
@@ -348,13 +358,16 @@ async fn test_file_logging_creates_file() {
         .await
         .expect("Failed to log");
 
+    service.flush().await;
+
     // Verify file was created
     assert!(log_path.exists());
 
     // Verify file contents
-    let content = fs::read_to_string(&log_path).expect("Failed to read log file");
-    assert!(content.contains("File Test"));
-    assert!(content.contains("Connected"));
+    let entries = read_json_lines(&log_path);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].connection_name, "File Test");
+    assert_eq!(entries[0].event, ConnectionEvent::Connected);
 }
 
 #[tokio::test]
@@ -379,6 +392,8 @@ async fn test_file_logging_appends() {
             .expect("Failed to log");
     }
 
+    service.flush().await;
+
     // Verify all entries are in file
     let content = fs::read_to_string(&log_path).expect("Failed to read log file");
     let lines: Vec<&str> = content.lines().collect();
@@ -432,10 +447,16 @@ async fn test_file_logging_all_levels() {
         .await
         .unwrap();
 
-    let content = fs::read_to_string(&log_path).expect("Failed to read log file");
-    assert!(content.contains("INFO"));
-    assert!(content.contains("WARN"));
-    assert!(content.contains("ERROR"));
+    service.flush().await;
+
+    let levels: Vec<LogLevel> = read_json_lines(&log_path)
+        .into_iter()
+        .map(|log| log.level)
+        .collect();
+    assert_eq!(
+        levels,
+        vec![LogLevel::Info, LogLevel::Warning, LogLevel::Error]
+    );
 }
 
 // =============================================================================
@@ -600,12 +621,21 @@ async fn test_complete_connection_logging_workflow() {
     assert_eq!(conn_logs.len(), 5);
 
     // Verify file logs
-    let file_content = fs::read_to_string(&log_path).expect("Failed to read log file");
-    assert!(file_content.contains("Production DB"));
-    assert!(file_content.contains("Connected"));
-    assert!(file_content.contains("Authentication successful"));
-    assert!(file_content.contains("Tunnel created"));
-    assert!(file_content.contains("Disconnected"));
+    service.flush().await;
+    let file_logs = read_json_lines(&log_path);
+    assert_eq!(file_logs.len(), 5);
+    assert!(
+        file_logs
+            .iter()
+            .all(|l| l.connection_name == "Production DB")
+    );
+    assert_eq!(file_logs[1].event, ConnectionEvent::Connected);
+    assert_eq!(file_logs[2].event, ConnectionEvent::AuthSuccess);
+    assert!(matches!(
+        file_logs[3].event,
+        ConnectionEvent::TunnelCreated { .. }
+    ));
+    assert_eq!(file_logs[4].event, ConnectionEvent::Disconnected);
 }
 
 #[tokio::test]
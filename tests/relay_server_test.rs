@@ -0,0 +1,260 @@
+//! Integration tests for the embedded relay server (`serve`)
+//!
+//! These tests start a RelayServer on an ephemeral port and drive it with
+//! the regular client services, checking that only permitted forwards work.
+
+mod common;
+
+use common::{
+    connect_with_retry, echo_roundtrip, free_port, generate_client_key, spawn_echo_server,
+};
+use ssh_tunnel_manager::models::{LocalForwarding, RemoteForwarding};
+use ssh_tunnel_manager::services::key_service::KeyService;
+use ssh_tunnel_manager::services::relay_server::{RelayConfig, RelayServer};
+use ssh_tunnel_manager::services::ssh_service::{SshService, SshSession};
+use ssh_tunnel_manager::services::tunnel_service::TunnelService;
+use ssh_tunnel_manager::utils::error::SshToolError;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+struct Relay {
+    addr: SocketAddr,
+    fingerprint: String,
+    authorized_keys: PathBuf,
+    key_service: KeyService,
+    dir: TempDir,
+    task: JoinHandle<()>,
+}
+
+impl Relay {
+    async fn start() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let config = RelayConfig::new(dir.path());
+        let authorized_keys = config.authorized_keys_path.clone();
+        let server = RelayServer::new(config).unwrap();
+        let fingerprint = server.host_key_fingerprint().to_string();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let task = tokio::spawn(async move {
+            let _ = server.run_on(listener).await;
+        });
+
+        let key_service = KeyService::new(dir.path().join("keys")).unwrap();
+
+        Self {
+            addr,
+            fingerprint,
+            authorized_keys,
+            key_service,
+            dir,
+            task,
+        }
+    }
+
+    /// Create a client key and authorize it with the given permissions
+    fn authorize(&self, name: &str, permit_open: &[&str], permit_listen: &[&str]) -> PathBuf {
+        let key_path = self.new_key(name);
+        self.key_service
+            .authorize_key(
+                &self.authorized_keys,
+                &key_path.to_string_lossy(),
+                permit_open.iter().map(|s| s.to_string()).collect(),
+                permit_listen.iter().map(|s| s.to_string()).collect(),
+            )
+            .unwrap();
+        key_path
+    }
+
+    fn new_key(&self, name: &str) -> PathBuf {
+        let key_path = self.dir.path().join(name);
+        generate_client_key(&key_path);
+        key_path
+    }
+
+    async fn connect(
+        &self,
+        key_path: &Path,
+        remote_forwards: Vec<RemoteForwarding>,
+    ) -> Result<SshSession, SshToolError> {
+        SshService::connect_pubkey(
+            &self.addr.ip().to_string(),
+            self.addr.port(),
+            "relay",
+            key_path,
+            None,
+            Some(self.fingerprint.clone()),
+            true,
+            remote_forwards,
+        )
+        .await
+    }
+}
+
+impl Drop for Relay {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// =============================================================================
+// Authentication Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_authorized_key_can_connect() {
+    let relay = Relay::start().await;
+    let key = relay.authorize("alice", &[], &["*"]);
+
+    assert!(relay.connect(&key, vec![]).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_unknown_key_rejected() {
+    let relay = Relay::start().await;
+    relay.authorize("alice", &[], &["*"]);
+    let stranger = relay.new_key("mallory");
+
+    let result = relay.connect(&stranger, vec![]).await;
+    assert!(matches!(result, Err(SshToolError::AuthenticationFailed(_))));
+}
+
+#[tokio::test]
+async fn test_password_auth_not_offered() {
+    let relay = Relay::start().await;
+
+    let result = SshService::connect_password(
+        &relay.addr.ip().to_string(),
+        relay.addr.port(),
+        "relay",
+        "anything",
+        None,
+        false,
+        vec![],
+    )
+    .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_revoked_key_rejected() {
+    let relay = Relay::start().await;
+    let key = relay.authorize("alice", &[], &["*"]);
+    let entry = &KeyService::load_authorized_keys(&relay.authorized_keys).unwrap()[0];
+    let fingerprint = KeyService::authorized_key_fingerprint(entry).unwrap();
+    assert_eq!(
+        KeyService::revoke_key(&relay.authorized_keys, &fingerprint).unwrap(),
+        1
+    );
+
+    let result = relay.connect(&key, vec![]).await;
+    assert!(matches!(result, Err(SshToolError::AuthenticationFailed(_))));
+}
+
+#[tokio::test]
+async fn test_shell_session_refused() {
+    let relay = Relay::start().await;
+    let key = relay.authorize("alice", &["*:*"], &["*"]);
+    let session = relay.connect(&key, vec![]).await.unwrap();
+
+    assert!(session.channel_open_session().await.is_err());
+}
+
+// =============================================================================
+// Remote Forwarding Tests (-R)
+// =============================================================================
+
+#[tokio::test]
+async fn test_permitted_remote_forward() {
+    let relay = Relay::start().await;
+    let echo = spawn_echo_server().await;
+    let remote_port = free_port();
+    let key = relay.authorize("alice", &[], &[&remote_port.to_string()]);
+
+    let config = RemoteForwarding::new(remote_port, echo.ip().to_string(), echo.port());
+    let session = relay.connect(&key, vec![config.clone()]).await.unwrap();
+    let mut handle = TunnelService::create_remote_forward(Arc::new(Mutex::new(session)), config)
+        .await
+        .unwrap();
+
+    let mut stream = connect_with_retry(remote_port).await;
+    let reply = echo_roundtrip(&mut stream, b"relayed").await;
+    assert_eq!(reply, b"relayed");
+
+    handle.stop();
+}
+
+#[tokio::test]
+async fn test_remote_forward_outside_permit_listen_denied() {
+    let relay = Relay::start().await;
+    let allowed = free_port();
+    let key = relay.authorize("alice", &[], &[&allowed.to_string()]);
+
+    let config = RemoteForwarding::new(free_port(), "127.0.0.1", 80);
+    let session = relay.connect(&key, vec![config.clone()]).await.unwrap();
+    let result = TunnelService::create_remote_forward(Arc::new(Mutex::new(session)), config).await;
+
+    assert!(matches!(result, Err(SshToolError::TunnelFailed(_))));
+}
+
+#[tokio::test]
+async fn test_port_only_permit_listen_refuses_all_interfaces() {
+    let relay = Relay::start().await;
+    let port = free_port();
+    let key = relay.authorize("alice", &[], &[&port.to_string()]);
+    let mut session = relay.connect(&key, vec![]).await.unwrap();
+
+    // -R 0.0.0.0:port would expose the listener on every interface
+    assert!(session.tcpip_forward("0.0.0.0", port as u32).await.is_err());
+    assert!(
+        session
+            .tcpip_forward("localhost", port as u32)
+            .await
+            .is_ok()
+    );
+}
+
+// =============================================================================
+// Local Forwarding Tests (-L)
+// =============================================================================
+
+#[tokio::test]
+async fn test_permitted_local_forward() {
+    let relay = Relay::start().await;
+    let echo = spawn_echo_server().await;
+    let key = relay.authorize("alice", &[&format!("127.0.0.1:{}", echo.port())], &[]);
+
+    let session = relay.connect(&key, vec![]).await.unwrap();
+    let local_port = free_port();
+    let mut handle = TunnelService::create_local_forward(
+        Arc::new(Mutex::new(session)),
+        LocalForwarding::new(local_port, "127.0.0.1", echo.port()),
+    )
+    .await
+    .unwrap();
+
+    let mut stream = connect_with_retry(local_port).await;
+    let reply = echo_roundtrip(&mut stream, b"direct").await;
+    assert_eq!(reply, b"direct");
+
+    handle.stop();
+}
+
+#[tokio::test]
+async fn test_destination_outside_permit_open_denied() {
+    let relay = Relay::start().await;
+    let echo = spawn_echo_server().await;
+    let key = relay.authorize("alice", &["127.0.0.1:1"], &[]);
+
+    let session = relay.connect(&key, vec![]).await.unwrap();
+    let result = session
+        .channel_open_direct_tcpip("127.0.0.1", echo.port() as u32, "127.0.0.1", 0)
+        .await;
+
+    assert!(result.is_err());
+}
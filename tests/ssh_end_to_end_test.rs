@@ -0,0 +1,1229 @@
+//! End-to-end tests for SshService, TunnelService and SessionManager
+//!
+//! These tests run against the in-process SSH server in `tests/common`,
+//! covering authentication, host key verification and real traffic through
+//! local (-L), remote (-R) and dynamic (-D) forwards.
+
+mod common;
+
+use common::{
+    TEST_PASSWORD, TEST_USER, TestSshServer, connect_with_retry, echo_roundtrip, free_port,
+    generate_client_key, spawn_echo_server, spawn_unix_echo_server,
+};
+use russh::ChannelMsg;
+use russh::client::{self, KeyboardInteractiveAuthResponse};
+use ssh_tunnel_manager::models::{
+    AuthMethod, ConnectionEvent, DynamicForwarding, EnvProfile, ForwardingConfig, Hook, HookStage,
+    LocalForwarding, RemoteForwarding, SessionStatus, SshConnection,
+};
+use ssh_tunnel_manager::services::event_bus::EventBus;
+use ssh_tunnel_manager::services::exec_service::ExecSession;
+use ssh_tunnel_manager::services::group_service::GroupService;
+use ssh_tunnel_manager::services::key_service::KeyService;
+use ssh_tunnel_manager::services::session_manager::SessionManager;
+use ssh_tunnel_manager::services::sftp_service::{TransferOptions, TransferProgress};
+use ssh_tunnel_manager::services::ssh_service::{
+    OutputStream, SshClientHandler, SshService, SshSession,
+};
+use ssh_tunnel_manager::services::tunnel_service::TunnelService;
+use ssh_tunnel_manager::utils::error::SshToolError;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+async fn connect(server: &TestSshServer) -> Arc<Mutex<SshSession>> {
+    let session = SshService::connect_password(
+        &server.host(),
+        server.port(),
+        TEST_USER,
+        TEST_PASSWORD,
+        None,
+        false,
+        vec![],
+    )
+    .await
+    .expect("password login");
+    Arc::new(Mutex::new(session))
+}
+
+// =============================================================================
+// Authentication Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_password_auth_success() {
+    let server = TestSshServer::start().await;
+
+    let mut session = SshService::connect_password(
+        &server.host(),
+        server.port(),
+        TEST_USER,
+        TEST_PASSWORD,
+        None,
+        false,
+        vec![],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(server.authenticated(), 1);
+    SshService::disconnect(&mut session).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_password_auth_wrong_password() {
+    let server = TestSshServer::start().await;
+
+    let result = SshService::connect_password(
+        &server.host(),
+        server.port(),
+        TEST_USER,
+        "wrong",
+        None,
+        false,
+        vec![],
+    )
+    .await;
+
+    assert!(matches!(result, Err(SshToolError::AuthenticationFailed(_))));
+    assert_eq!(server.authenticated(), 0);
+}
+
+#[tokio::test]
+async fn test_pubkey_auth_success() {
+    let server = TestSshServer::start().await;
+
+    let session = SshService::connect_pubkey(
+        &server.host(),
+        server.port(),
+        TEST_USER,
+        server.client_key_path(),
+        None,
+        None,
+        false,
+        vec![],
+    )
+    .await
+    .unwrap();
+
+    assert!(session.key_fingerprint().unwrap().starts_with("SHA256:"));
+    assert!(session.authentication().starts_with("publickey SHA256:"));
+    assert_eq!(server.authenticated(), 1);
+}
+
+#[tokio::test]
+async fn test_pubkey_auth_unknown_user() {
+    let server = TestSshServer::start().await;
+
+    let result = SshService::connect_pubkey(
+        &server.host(),
+        server.port(),
+        "someone-else",
+        server.client_key_path(),
+        None,
+        None,
+        false,
+        vec![],
+    )
+    .await;
+
+    assert!(matches!(result, Err(SshToolError::AuthenticationFailed(_))));
+}
+
+#[tokio::test]
+async fn test_connect_with_connection_config() {
+    let server = TestSshServer::start().await;
+
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER)
+        .with_port(server.port())
+        .with_auth_method(AuthMethod::PublicKey {
+            private_key_path: server.client_key_path().to_path_buf(),
+            passphrase_required: false,
+        });
+
+    assert!(SshService::connect(&connection, None).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_keyboard_interactive_auth() {
+    let server = TestSshServer::start().await;
+
+    let mut session = client::connect(
+        Arc::new(client::Config::default()),
+        server.addr(),
+        SshClientHandler::new(),
+    )
+    .await
+    .unwrap();
+
+    let response = session
+        .authenticate_keyboard_interactive_start(TEST_USER, None)
+        .await
+        .unwrap();
+    let prompts = match response {
+        KeyboardInteractiveAuthResponse::InfoRequest { prompts, .. } => prompts,
+        other => panic!("expected info request, got {:?}", other),
+    };
+    assert_eq!(prompts.len(), 1);
+    assert!(!prompts[0].echo);
+
+    let response = session
+        .authenticate_keyboard_interactive_respond(vec![TEST_PASSWORD.to_string()])
+        .await
+        .unwrap();
+    assert!(matches!(response, KeyboardInteractiveAuthResponse::Success));
+    assert_eq!(server.authenticated(), 1);
+}
+
+// =============================================================================
+// Host Key Verification Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_host_key_fingerprint_match() {
+    let server = TestSshServer::start().await;
+
+    let result = SshService::connect_password(
+        &server.host(),
+        server.port(),
+        TEST_USER,
+        TEST_PASSWORD,
+        Some(server.host_key_fingerprint().to_string()),
+        true,
+        vec![],
+    )
+    .await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_host_key_fingerprint_mismatch() {
+    let server = TestSshServer::start().await;
+
+    let result = SshService::connect_password(
+        &server.host(),
+        server.port(),
+        TEST_USER,
+        TEST_PASSWORD,
+        Some("SHA256:not-the-right-key".to_string()),
+        true,
+        vec![],
+    )
+    .await;
+
+    assert!(matches!(result, Err(SshToolError::SshConnectionFailed(_))));
+    assert_eq!(server.authenticated(), 0);
+}
+
+// =============================================================================
+// Command Execution Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_execute_command() {
+    let server = TestSshServer::start().await;
+    let session = connect(&server).await;
+    let bus = EventBus::new();
+    let mut events = bus.subscribe();
+    session
+        .lock()
+        .await
+        .events()
+        .set(bus.source(&SshConnection::new("e2e", server.host(), TEST_USER)));
+
+    let output = SshService::execute_command(&mut *session.lock().await, "echo hello")
+        .await
+        .unwrap();
+
+    assert_eq!(output.stdout, "hello\n");
+    assert!(output.stderr.is_empty());
+    assert!(output.success());
+    assert_eq!(
+        events.try_recv().unwrap().event,
+        ConnectionEvent::CommandExecuted {
+            command: "echo hello".to_string()
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_execute_command_exit_status() {
+    let server = TestSshServer::start().await;
+    let session = connect(&server).await;
+    let mut session = session.lock().await;
+
+    let output = SshService::execute_command(&mut session, "exit 3")
+        .await
+        .unwrap();
+    assert_eq!(output.exit_status, Some(3));
+    assert!(!output.success());
+
+    let output = SshService::execute_command(&mut session, "nonexistent")
+        .await
+        .unwrap();
+    assert_eq!(output.exit_status, Some(127));
+    assert_eq!(output.stderr, "nonexistent: command not found\n");
+}
+
+// =============================================================================
+// Local Forwarding Tests (-L)
+// =============================================================================
+
+#[tokio::test]
+async fn test_local_forward_echo() {
+    let server = TestSshServer::start().await;
+    let echo = spawn_echo_server().await;
+    let session = connect(&server).await;
+
+    let local_port = free_port();
+    let config = LocalForwarding::new(local_port, echo.ip().to_string(), echo.port());
+    let mut handle = TunnelService::create_local_forward(session, config)
+        .await
+        .unwrap();
+
+    let mut stream = connect_with_retry(local_port).await;
+    let reply = echo_roundtrip(&mut stream, b"through the tunnel").await;
+
+    assert_eq!(reply, b"through the tunnel");
+    assert_eq!(server.direct_tcpip_opened(), 1);
+
+    // Counters are bumped after each write, so give them a moment to settle
+    let mut stats = handle.get_traffic_stats();
+    for _ in 0..50 {
+        if stats == (18, 18) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        stats = handle.get_traffic_stats();
+    }
+    assert_eq!(stats, (18, 18));
+
+    handle.stop();
+}
+
+#[tokio::test]
+async fn test_local_forward_port_in_use() {
+    let server = TestSshServer::start().await;
+    let session = connect(&server).await;
+
+    let occupied = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = occupied.local_addr().unwrap().port();
+    let config = LocalForwarding::new(port, "127.0.0.1", 80);
+
+    let result = TunnelService::create_local_forward(session, config).await;
+    assert!(matches!(result, Err(SshToolError::PortInUse(p)) if p == port));
+}
+
+// =============================================================================
+// Dynamic Forwarding Tests (-D)
+// =============================================================================
+
+#[tokio::test]
+async fn test_dynamic_forward_socks5_echo() {
+    let server = TestSshServer::start().await;
+    let echo = spawn_echo_server().await;
+    let session = connect(&server).await;
+
+    let local_port = free_port();
+    let config = DynamicForwarding::new(local_port).with_bind_address("127.0.0.1");
+    let mut handle = TunnelService::create_dynamic_forward(session, config)
+        .await
+        .unwrap();
+
+    let mut stream = connect_with_retry(local_port).await;
+
+    // Greeting: version 5, one method, no authentication
+    stream.write_all(&[5, 1, 0]).await.unwrap();
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await.unwrap();
+    assert_eq!(reply, [5, 0]);
+
+    // CONNECT to the echo server by IPv4 address
+    let port = echo.port().to_be_bytes();
+    stream
+        .write_all(&[5, 1, 0, 1, 127, 0, 0, 1, port[0], port[1]])
+        .await
+        .unwrap();
+    let mut reply = [0u8; 10];
+    stream.read_exact(&mut reply).await.unwrap();
+    assert_eq!(reply[1], 0);
+
+    let echoed = echo_roundtrip(&mut stream, b"socks payload").await;
+    assert_eq!(echoed, b"socks payload");
+
+    handle.stop();
+}
+
+// =============================================================================
+// Remote Forwarding Tests (-R)
+// =============================================================================
+
+#[tokio::test]
+async fn test_remote_forward_echo() {
+    let server = TestSshServer::start().await;
+    let echo = spawn_echo_server().await;
+
+    let remote_port = free_port();
+    let config = RemoteForwarding::new(remote_port, echo.ip().to_string(), echo.port());
+
+    let session = SshService::connect_password(
+        &server.host(),
+        server.port(),
+        TEST_USER,
+        TEST_PASSWORD,
+        None,
+        false,
+        vec![config.clone()],
+    )
+    .await
+    .unwrap();
+    let session = Arc::new(Mutex::new(session));
+
+    let mut handle = TunnelService::create_remote_forward(session, config)
+        .await
+        .unwrap();
+    assert_eq!(server.remote_forward_ports().await, vec![remote_port]);
+
+    // Connect to the port the server opened; traffic comes back to the client
+    let mut stream = connect_with_retry(remote_port).await;
+    let reply = echo_roundtrip(&mut stream, b"reverse").await;
+    assert_eq!(reply, b"reverse");
+
+    // Forwarded channels are counted on the tunnel too
+    let mut stats = handle.get_traffic_stats();
+    for _ in 0..50 {
+        if stats == (7, 7) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        stats = handle.get_traffic_stats();
+    }
+    assert_eq!(stats, (7, 7));
+    assert_eq!(handle.traffic_counter.active_channels(), 1);
+
+    handle.stop();
+}
+
+// =============================================================================
+// Stream-local Forwarding Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_direct_streamlocal_echo() {
+    let server = TestSshServer::start().await;
+    let dir = tempfile::tempdir().unwrap();
+    let socket_path = dir.path().join("echo.sock");
+    spawn_unix_echo_server(&socket_path);
+
+    let session = connect(&server).await;
+    let channel = session
+        .lock()
+        .await
+        .channel_open_direct_streamlocal(socket_path.to_string_lossy())
+        .await
+        .unwrap();
+
+    let mut stream = channel.into_stream();
+    let reply = echo_roundtrip(&mut stream, b"unix").await;
+    assert_eq!(reply, b"unix");
+}
+
+// =============================================================================
+// Session Manager Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_session_manager_with_live_tunnels() {
+    let server = TestSshServer::start().await;
+    let echo = spawn_echo_server().await;
+    let local_port = free_port();
+
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER)
+        .with_port(server.port())
+        .with_forwarding(ForwardingConfig::Local(LocalForwarding::new(
+            local_port,
+            echo.ip().to_string(),
+            echo.port(),
+        )));
+
+    let ssh_session = SshService::connect(&connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+
+    let manager = SessionManager::new(300);
+    let session_id = manager
+        .create_session_with_tunnels(connection, ssh_session)
+        .await
+        .unwrap();
+
+    let active = manager.get_session(session_id).await.unwrap();
+    assert_eq!(active.status, SessionStatus::Connected);
+    assert_eq!(manager.list_sessions().await.len(), 1);
+
+    let mut stream = connect_with_retry(local_port).await;
+    let reply = echo_roundtrip(&mut stream, b"managed").await;
+    assert_eq!(reply, b"managed");
+
+    manager.sample_traffic().await;
+    let tunnels = manager.list_tunnels().await;
+    assert_eq!(tunnels.len(), 1);
+    assert_eq!(tunnels[0].session_id, session_id);
+    assert_eq!(tunnels[0].active_channels, 1);
+    assert_eq!(tunnels[0].traffic.samples().count(), 1);
+
+    // A baseline sample is taken when the session starts
+    let active = manager.get_session(session_id).await.unwrap();
+    assert_eq!(active.traffic.samples().count(), 2);
+    assert_eq!(active.traffic.rates().len(), 1);
+    drop(stream);
+
+    manager.disconnect_session(session_id).await.unwrap();
+    assert_eq!(manager.session_count().await, 0);
+
+    // The local listener goes away with the session
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(TcpStream::connect(("127.0.0.1", local_port)).await.is_err());
+}
+
+#[tokio::test]
+async fn test_session_manager_flags_changed_connections() {
+    let server = TestSshServer::start().await;
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER).with_port(server.port());
+    let ssh_session = SshService::connect(&connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+
+    let manager = SessionManager::new(300);
+    let session_id = manager
+        .create_session(connection.clone(), ssh_session)
+        .await
+        .unwrap();
+
+    // A rename is applied without flagging the session
+    let mut renamed = connection.clone();
+    renamed.name = "renamed".to_string();
+    assert!(
+        manager
+            .apply_connection_changes(std::slice::from_ref(&renamed))
+            .await
+            .is_empty()
+    );
+    let active = manager.get_session(session_id).await.unwrap();
+    assert_eq!(active.connection_name, "renamed");
+    assert!(!active.config_changed);
+
+    // Changing what the session runs with flags it once
+    let mut edited = renamed.clone();
+    edited.compression = !edited.compression;
+    let edited = [edited];
+    assert_eq!(
+        manager.apply_connection_changes(&edited).await,
+        vec![session_id]
+    );
+    assert!(manager.apply_connection_changes(&edited).await.is_empty());
+    assert!(
+        manager
+            .get_session(session_id)
+            .await
+            .unwrap()
+            .config_changed
+    );
+
+    manager.disconnect_all().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_session_manager_live_tunnel_changes() {
+    let server = TestSshServer::start().await;
+    let echo = spawn_echo_server().await;
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER).with_port(server.port());
+    let ssh_session = SshService::connect(&connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+
+    let manager = SessionManager::new(300);
+    let session_id = manager
+        .create_session(connection.clone(), ssh_session)
+        .await
+        .unwrap();
+
+    // Add a local and a remote forward to the running session
+    let local_port = free_port();
+    let local = ForwardingConfig::local(local_port, echo.ip().to_string(), echo.port());
+    let remote_port = free_port();
+    let remote = ForwardingConfig::remote(remote_port, echo.ip().to_string(), echo.port());
+    manager.add_tunnel(session_id, local.clone()).await.unwrap();
+    manager
+        .add_tunnel(session_id, remote.clone())
+        .await
+        .unwrap();
+    assert!(manager.add_tunnel(session_id, local.clone()).await.is_err());
+
+    let mut stream = connect_with_retry(local_port).await;
+    assert_eq!(echo_roundtrip(&mut stream, b"added").await, b"added");
+    drop(stream);
+    let mut stream = connect_with_retry(remote_port).await;
+    assert_eq!(echo_roundtrip(&mut stream, b"reverse").await, b"reverse");
+    drop(stream);
+
+    // Reconcile to a different set: the remote forward is cancelled on the
+    // server, the local one keeps running and a SOCKS proxy is started
+    let socks_port = free_port();
+    let dynamic = ForwardingConfig::dynamic(socks_port);
+    let changes = manager
+        .reconcile(session_id, vec![local.clone(), dynamic.clone()])
+        .await
+        .unwrap();
+    assert_eq!(changes.added, vec![dynamic.clone()]);
+    assert_eq!(changes.removed, vec![remote]);
+    assert!(server.remote_forward_ports().await.is_empty());
+
+    let mut stream = connect_with_retry(local_port).await;
+    assert_eq!(echo_roundtrip(&mut stream, b"kept").await, b"kept");
+    drop(stream);
+
+    // Saved connections with only forwards changed are reconciled, not flagged
+    let mut saved = connection.clone();
+    saved.forwarding_configs = vec![dynamic];
+    assert!(manager.apply_connection_changes(&[saved]).await.is_empty());
+    assert!(
+        !manager
+            .get_session(session_id)
+            .await
+            .unwrap()
+            .config_changed
+    );
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(TcpStream::connect(("127.0.0.1", local_port)).await.is_err());
+
+    // The SSH session itself survived every change
+    assert!(
+        manager
+            .remove_tunnel(session_id, &local)
+            .await
+            .is_ok_and(|r| !r)
+    );
+    assert_eq!(manager.session_count().await, 1);
+    manager.disconnect_all().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_session_manager_resolves_profile_variables() {
+    let server = TestSshServer::start().await;
+    let connection =
+        SshConnection::new("e2e", "${E2E_HOST}", "${E2E_USER}").with_port(server.port());
+    let staging = EnvProfile::new("staging")
+        .with_var("E2E_HOST", server.host())
+        .with_var("E2E_USER", TEST_USER);
+
+    // Nothing to fill the placeholders with
+    let manager = SessionManager::new(300);
+    assert!(matches!(
+        manager.connect(&connection, Some(TEST_PASSWORD)).await,
+        Err(SshToolError::UnresolvedVariables(_))
+    ));
+
+    manager.set_profile(Some(staging.clone())).await;
+    let session_id = manager
+        .connect(&connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+    let saved = [connection];
+    assert!(manager.apply_connection_changes(&saved).await.is_empty());
+
+    // Switching profile re-targets the connection, so the session is flagged
+    manager
+        .set_profile(Some(staging.with_var("E2E_HOST", "localhost")))
+        .await;
+    assert_eq!(
+        manager.apply_connection_changes(&saved).await,
+        vec![session_id]
+    );
+
+    manager.disconnect_all().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_session_manager_publishes_lifecycle_events() {
+    let server = TestSshServer::start().await;
+    let echo = spawn_echo_server().await;
+    let local_port = free_port();
+
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER)
+        .with_port(server.port())
+        .with_forwarding(ForwardingConfig::Local(LocalForwarding::new(
+            local_port,
+            echo.ip().to_string(),
+            echo.port(),
+        )));
+
+    let manager = SessionManager::new(300);
+    let mut events = manager.events().subscribe();
+    let session_id = manager
+        .connect(&connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+
+    let mut stream = connect_with_retry(local_port).await;
+    echo_roundtrip(&mut stream, b"observed").await;
+    drop(stream);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    manager.disconnect_session(session_id).await.unwrap();
+
+    let mut kinds = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        assert_eq!(event.connection_id, connection.id);
+        // Only events after the session exists carry its ID
+        let before_session = matches!(
+            event.event,
+            ConnectionEvent::ConnectAttempt | ConnectionEvent::AuthSuccess
+        );
+        assert_eq!(event.session_id.is_none(), before_session);
+        if !before_session {
+            assert_eq!(event.session_id, Some(session_id));
+        }
+        match &event.event {
+            ConnectionEvent::AuthSuccess => {
+                assert_eq!(event.message.as_deref(), Some("password"));
+            }
+            ConnectionEvent::ForwardingActivity {
+                bytes_sent,
+                bytes_received,
+            } => assert_eq!((*bytes_sent, *bytes_received), (8, 8)),
+            _ => {}
+        }
+        kinds.push(event.event.kind());
+    }
+    assert_eq!(
+        kinds,
+        vec![
+            "connect_attempt",
+            "auth_success",
+            "connected",
+            "tunnel_created",
+            "channel_opened",
+            "channel_closed",
+            "forwarding_activity",
+            "disconnected",
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_session_manager_runs_hooks() {
+    let server = TestSshServer::start().await;
+    let echo = spawn_echo_server().await;
+    let local_port = free_port();
+
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER)
+        .with_port(server.port())
+        .with_forwarding(ForwardingConfig::Local(LocalForwarding::new(
+            local_port,
+            echo.ip().to_string(),
+            echo.port(),
+        )))
+        .with_hook(HookStage::PreConnect, Hook::new("true"))
+        .with_hook(HookStage::PostConnect, Hook::new("echo ready"))
+        .with_hook(
+            HookStage::PostTunnel,
+            Hook::new("echo \"$SSH_TUNNEL_CONNECTION:$SSH_TUNNEL_LOCAL_PORT\""),
+        );
+
+    let manager = SessionManager::new(300);
+    let mut events = manager.events().subscribe();
+    manager
+        .connect(&connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+
+    let mut hooks = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let ConnectionEvent::HookExecuted {
+            stage, exit_code, ..
+        } = event.event
+        {
+            hooks.push((stage, exit_code, event.message));
+        }
+    }
+    assert_eq!(
+        hooks,
+        vec![
+            ("pre_connect".to_string(), Some(0), None),
+            (
+                "post_connect".to_string(),
+                Some(0),
+                Some("ready".to_string())
+            ),
+            (
+                "post_tunnel".to_string(),
+                Some(0),
+                Some(format!("e2e:{}", local_port))
+            ),
+        ]
+    );
+    manager.disconnect_all().await.unwrap();
+
+    // A failing remote hook set to abort tears the session down again
+    let failing = connection.with_hook(HookStage::PostConnect, Hook::new("exit 2"));
+    assert!(matches!(
+        manager.connect(&failing, Some(TEST_PASSWORD)).await,
+        Err(SshToolError::HookFailed(ref m)) if m.contains("exited with 2")
+    ));
+    assert_eq!(manager.session_count().await, 0);
+
+    // Nothing is attempted when a pre-connect hook aborts
+    let blocked = SshConnection::new("e2e", server.host(), TEST_USER)
+        .with_port(server.port())
+        .with_hook(HookStage::PreConnect, Hook::new("exit 1"));
+    assert!(matches!(
+        manager.connect(&blocked, Some(TEST_PASSWORD)).await,
+        Err(SshToolError::HookFailed(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_session_manager_drops_session_when_tunnels_fail() {
+    let server = TestSshServer::start().await;
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER)
+        .with_port(server.port())
+        .with_forwarding(ForwardingConfig::Local(LocalForwarding::new(
+            taken.local_addr().unwrap().port(),
+            "127.0.0.1".to_string(),
+            9,
+        )));
+
+    let manager = SessionManager::new(300);
+    let mut events = manager.events().subscribe();
+    assert!(
+        manager
+            .connect(&connection, Some(TEST_PASSWORD))
+            .await
+            .is_err()
+    );
+    assert_eq!(manager.session_count().await, 0);
+
+    let mut failed = false;
+    while let Ok(event) = events.try_recv() {
+        failed |= event.event == ConnectionEvent::ConnectionFailed;
+    }
+    assert!(failed);
+}
+
+#[tokio::test]
+async fn test_session_manager_not_blocked_by_busy_session() {
+    let server = TestSshServer::start().await;
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER).with_port(server.port());
+    let manager = Arc::new(SessionManager::new(300));
+    let busy = manager
+        .connect(&connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+    let other = manager
+        .connect(&connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+
+    // Disconnecting `busy` waits for its session, held here as a long
+    // command would
+    let session = manager.get_ssh_session(busy).await.unwrap();
+    let held = session.lock().await;
+    let disconnecting = tokio::spawn({
+        let manager = manager.clone();
+        async move { manager.disconnect_session(busy).await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let unblocked = async {
+        assert_eq!(manager.list_sessions().await.len(), 1);
+        manager
+            .add_tunnel(
+                other,
+                ForwardingConfig::Local(LocalForwarding::new(
+                    free_port(),
+                    "127.0.0.1".to_string(),
+                    9,
+                )),
+            )
+            .await
+            .unwrap();
+        manager.disconnect_session(other).await.unwrap();
+    };
+    tokio::time::timeout(Duration::from_secs(5), unblocked)
+        .await
+        .expect("other sessions waited for the busy one");
+
+    drop(held);
+    disconnecting.await.unwrap().unwrap();
+    assert_eq!(manager.session_count().await, 0);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_group_connect_all_overlaps() {
+    let server = TestSshServer::start().await;
+    let marks = tempfile::tempdir().unwrap();
+    // Each post-tunnel hook waits for the other one to start, so the group
+    // only connects if both connections are set up at the same time
+    let target = |name: &str, other: &str| {
+        let hook = format!(
+            "touch {dir}/{name}; while [ ! -f {dir}/{other} ]; do sleep 0.05; done",
+            dir = marks.path().display(),
+        );
+        // No forwards: ports picked by `free_port` can be taken by tests
+        // running alongside before both sessions bind them
+        let connection = SshConnection::new(name, server.host(), TEST_USER)
+            .with_port(server.port())
+            .with_hook(HookStage::PostTunnel, Hook::new(hook).with_timeout(5));
+        (connection, Some(TEST_PASSWORD.to_string()))
+    };
+    let targets = vec![target("api", "db"), target("db", "api")];
+
+    let manager = SessionManager::new(300);
+    let results = GroupService::connect_all(&manager, &targets, |_, _| {}).await;
+    assert!(results.iter().all(|r| r.is_ok()), "{:?}", results);
+    assert_eq!(manager.session_count().await, 2);
+
+    let connections: Vec<SshConnection> = targets.into_iter().map(|(c, _)| c).collect();
+    assert_eq!(
+        GroupService::disconnect_all(&manager, &connections).await,
+        2
+    );
+    assert_eq!(manager.session_count().await, 0);
+}
+
+#[tokio::test]
+async fn test_exec_session_reuses_or_opens_session() {
+    let server = TestSshServer::start().await;
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER).with_port(server.port());
+    let manager = SessionManager::new(300);
+    let mut events = manager.events().subscribe();
+
+    // Nothing running yet, so a temporary session is opened
+    let exec = ExecSession::open(&manager, &connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+    assert!(exec.is_temporary());
+    let mut output = Vec::new();
+    let status = exec
+        .run("echo streamed", |stream, data| {
+            output.push((stream, data.to_vec()))
+        })
+        .await
+        .unwrap();
+    assert_eq!(status, Some(0));
+    assert_eq!(output, vec![(OutputStream::Stdout, b"streamed\n".to_vec())]);
+    exec.close().await.unwrap();
+    assert_eq!(manager.session_count().await, 0);
+
+    let event = events.try_recv().unwrap();
+    assert_eq!(event.connection_id, connection.id);
+    assert_eq!(event.event.kind(), "command_executed");
+
+    // A running session of the connection is used instead
+    let session_id = manager
+        .connect(&connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+    let exec = ExecSession::open(&manager, &connection, None)
+        .await
+        .unwrap();
+    assert!(!exec.is_temporary());
+    let mut stderr = Vec::new();
+    let status = exec
+        .run("nonexistent", |stream, data| {
+            assert_eq!(stream, OutputStream::Stderr);
+            stderr.extend_from_slice(data);
+        })
+        .await
+        .unwrap();
+    assert_eq!(status, Some(127));
+    assert!(!stderr.is_empty());
+    exec.close().await.unwrap();
+    assert!(manager.has_session(session_id).await);
+
+    manager.disconnect_all().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_exec_session_shell_with_pty() {
+    let server = TestSshServer::start().await;
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER).with_port(server.port());
+    let manager = SessionManager::new(300);
+    let mut events = manager.events().subscribe();
+
+    let exec = ExecSession::open(&manager, &connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+    let channel = exec.shell("xterm-256color", 120, 40).await.unwrap();
+    // Shells are audited like commands
+    let opened = std::iter::from_fn(|| events.try_recv().ok()).find(
+        |e| matches!(&e.event, ConnectionEvent::ShellOpened { term } if term == "xterm-256color"),
+    );
+    assert_eq!(opened.unwrap().message.as_deref(), Some("120x40"));
+    let (mut reader, writer) = channel.split();
+
+    writer.window_change(100, 30, 0, 0).await.unwrap();
+    writer.data(&b"exit\n"[..]).await.unwrap();
+
+    let mut output = Vec::new();
+    let mut status = None;
+    while let Some(msg) = reader.wait().await {
+        match msg {
+            ChannelMsg::Data { data } => output.extend_from_slice(&data),
+            ChannelMsg::ExitStatus { exit_status } => status = Some(exit_status),
+            ChannelMsg::Close => break,
+            _ => {}
+        }
+    }
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "pty xterm-256color 120x40\r\n$ resized 100x30\r\nexit\n"
+    );
+    assert_eq!(status, Some(0));
+    exec.close().await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_sftp_recursive_transfer_preserves_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let server = TestSshServer::start().await;
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER).with_port(server.port());
+    let manager = SessionManager::new(300);
+    manager
+        .connect(&connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+
+    let local = tempfile::tempdir().unwrap();
+    let source = local.path().join("dumps");
+    std::fs::create_dir_all(source.join("nested")).unwrap();
+    std::fs::write(source.join("db.sql"), vec![7u8; 100_000]).unwrap();
+    std::fs::write(source.join("nested/run.sh"), b"#!/bin/sh\n").unwrap();
+    std::fs::set_permissions(
+        source.join("nested/run.sh"),
+        std::fs::Permissions::from_mode(0o750),
+    )
+    .unwrap();
+
+    // The running session carries the transfer
+    let exec = ExecSession::open(&manager, &connection, None)
+        .await
+        .unwrap();
+    assert!(!exec.is_temporary());
+    let sftp = exec.sftp().await.unwrap();
+    let options = TransferOptions {
+        recursive: true,
+        resume: false,
+    };
+
+    // Uploading into an existing directory copies under the source's name
+    let remote = tempfile::tempdir().unwrap();
+    let remote_root = remote.path().to_str().unwrap().to_string();
+    let mut copied = 0;
+    let summary = sftp
+        .upload(&source, &remote_root, options, |progress| {
+            if let TransferProgress::Copied(bytes) = progress {
+                copied += bytes;
+            }
+        })
+        .await
+        .unwrap();
+    assert_eq!((summary.files, summary.directories), (2, 2));
+    assert_eq!(summary.bytes, 100_010);
+    assert_eq!(copied, summary.bytes);
+    let uploaded = remote.path().join("dumps");
+    assert_eq!(
+        std::fs::read(uploaded.join("db.sql")).unwrap().len(),
+        100_000
+    );
+    let mode = std::fs::metadata(uploaded.join("nested/run.sh"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o750);
+
+    // And back down to a new local path
+    let target = local.path().join("copy");
+    let uploaded = uploaded.to_str().unwrap();
+    let summary = sftp
+        .download(uploaded, &target, options, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(summary.files, 2);
+    assert_eq!(
+        std::fs::read(target.join("nested/run.sh")).unwrap(),
+        b"#!/bin/sh\n"
+    );
+    let mode = std::fs::metadata(target.join("nested/run.sh"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o750);
+
+    // A directory needs a recursive transfer
+    let result = sftp
+        .download(uploaded, &target, TransferOptions::default(), |_| {})
+        .await;
+    assert!(matches!(result, Err(SshToolError::TransferFailed(_))));
+
+    sftp.close().await.unwrap();
+    exec.close().await.unwrap();
+    manager.disconnect_all().await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_sftp_resumes_partial_files() {
+    let server = TestSshServer::start().await;
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER).with_port(server.port());
+    let manager = SessionManager::new(300);
+    let exec = ExecSession::open(&manager, &connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+    let sftp = exec.sftp().await.unwrap();
+    let resume = TransferOptions {
+        recursive: false,
+        resume: true,
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let contents: Vec<u8> = (0..50_000u32).map(|i| (i % 251) as u8).collect();
+    let remote = dir.path().join("remote.bin");
+    std::fs::write(&remote, &contents).unwrap();
+    let remote = remote.to_str().unwrap();
+
+    // Download continues after the bytes already there
+    let local = dir.path().join("local.bin");
+    std::fs::write(&local, &contents[..20_000]).unwrap();
+    let mut started = None;
+    let summary = sftp
+        .download(remote, &local, resume, |progress| {
+            if let TransferProgress::Started { size, offset, .. } = progress {
+                started = Some((size, offset));
+            }
+        })
+        .await
+        .unwrap();
+    assert_eq!(started, Some((50_000, 20_000)));
+    assert_eq!(summary.bytes, 30_000);
+    assert_eq!(std::fs::read(&local).unwrap(), contents);
+
+    // A complete file is left alone
+    let summary = sftp.download(remote, &local, resume, |_| {}).await.unwrap();
+    assert_eq!((summary.files, summary.bytes), (1, 0));
+
+    // Upload resumes the same way
+    let partial = dir.path().join("partial.bin");
+    std::fs::write(&partial, &contents[..1_000]).unwrap();
+    let summary = sftp
+        .upload(&local, partial.to_str().unwrap(), resume, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(summary.bytes, 49_000);
+    assert_eq!(std::fs::read(&partial).unwrap(), contents);
+
+    // Without resume the file is copied again from the start
+    let summary = sftp
+        .upload(
+            &local,
+            partial.to_str().unwrap(),
+            TransferOptions::default(),
+            |_| {},
+        )
+        .await
+        .unwrap();
+    assert_eq!(summary.bytes, 50_000);
+
+    sftp.close().await.unwrap();
+    exec.close().await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_deploy_key_over_sftp() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let server = TestSshServer::start().await;
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER).with_port(server.port());
+    let manager = SessionManager::new(300);
+
+    let keys_dir = tempfile::tempdir().unwrap();
+    let key_path = keys_dir.path().join("id_deploy");
+    generate_client_key(&key_path);
+    let key_service = KeyService::new(keys_dir.path().to_path_buf()).unwrap();
+    let key = key_service
+        .deployable_key(key_path.to_str().unwrap())
+        .unwrap();
+    assert_eq!(key.private_key_path, Some(key_path.clone()));
+
+    // The key cannot log in yet
+    let with_key = connection.clone().with_auth_method(AuthMethod::PublicKey {
+        private_key_path: key.private_key_path.clone().unwrap(),
+        passphrase_required: false,
+    });
+    assert!(SshService::connect(&with_key, None).await.is_err());
+
+    // An existing file without a final newline, writable by the group
+    let ssh_dir = server.home().join(".ssh");
+    let authorized_keys = ssh_dir.join("authorized_keys");
+    std::fs::create_dir(&ssh_dir).unwrap();
+    std::fs::write(&authorized_keys, "ssh-ed25519 AAAAother other@host").unwrap();
+    std::fs::set_permissions(&authorized_keys, std::fs::Permissions::from_mode(0o664)).unwrap();
+
+    let exec = ExecSession::open(&manager, &connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+    let sftp = exec.sftp().await.unwrap();
+    assert!(sftp.authorize_key(&key.public_key).await.unwrap());
+    // Deploying again changes nothing
+    assert!(!sftp.authorize_key(&key.public_key).await.unwrap());
+    sftp.close().await.unwrap();
+    exec.close().await.unwrap();
+
+    let content = std::fs::read_to_string(&authorized_keys).unwrap();
+    assert_eq!(
+        content,
+        format!("ssh-ed25519 AAAAother other@host\n{}\n", key.public_key)
+    );
+    let mode = std::fs::metadata(&authorized_keys)
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o644);
+
+    let mut session = SshService::connect(&with_key, None).await.unwrap();
+    SshService::disconnect(&mut session).await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_deploy_key_creates_ssh_dir() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let server = TestSshServer::start().await;
+    let connection = SshConnection::new("e2e", server.host(), TEST_USER).with_port(server.port());
+    let manager = SessionManager::new(300);
+    let public_key =
+        std::fs::read_to_string(server.client_key_path().with_extension("pub")).unwrap();
+
+    let exec = ExecSession::open(&manager, &connection, Some(TEST_PASSWORD))
+        .await
+        .unwrap();
+    let sftp = exec.sftp().await.unwrap();
+    assert!(sftp.authorize_key(&public_key).await.unwrap());
+    sftp.close().await.unwrap();
+    exec.close().await.unwrap();
+
+    let ssh_dir = server.home().join(".ssh");
+    let mode =
+        |path: &std::path::Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(&ssh_dir), 0o700);
+    assert_eq!(mode(&ssh_dir.join("authorized_keys")), 0o600);
+}
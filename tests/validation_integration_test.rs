@@ -442,6 +442,7 @@ async fn test_validation_workflow_with_port_forwarding() {
 }
 
 #[test]
+#[allow(clippy::default_constructed_unit_structs)]
 fn test_service_default_trait() {
     let service = ValidationService::default();
 